@@ -11,6 +11,78 @@ const COMMANDS: &[&str] = &[
     "commit_transaction",
     "rollback_transaction",
     "migrate",
+    "rollback_all_transactions",
+    "load_in_memory_persistent",
+    "watch_changes",
+    "unwatch_changes",
+    "set_progress_handler",
+    "interrupt",
+    "json_get",
+    "copy_table",
+    "get_alias_config",
+    "schema_version",
+    "delete_by_ids",
+    "begin_read_transaction",
+    "migration_repair",
+    "reindex",
+    "vacuum_into",
+    "drop_table",
+    "execute_async",
+    "select_async",
+    "clone_database",
+    "get_path",
+    "begin_multi_transaction",
+    "commit_multi",
+    "rollback_multi",
+    "export_ndjson",
+    "expand_sql",
+    "set_migrations",
+    "run_migrations",
+    "truncate_table",
+    "diagnostics",
+    "select_multi",
+    "flush",
+    "table_hash",
+    "db_hash",
+    "pragma",
+    "application_id",
+    "poll_watch",
+    "poll_unwatch",
+    "migration_status",
+    "transaction_changes",
+    "execute_many",
+    "select_with_origin",
+    "ensure_table",
+    "indexes",
+    "release_connection",
+    "reset",
+    "now_iso",
+    "select_one",
+    "exists_where",
+    "prepare",
+    "run",
+    "finalize",
+    "analyze",
+    "query_stats",
+    "select_count",
+    "rekey_verified",
+    "execute_returning",
+    "with_foreign_keys_off",
+    "incremental_vacuum",
+    "select_json",
+    "set_limit",
+    "import_sql_file",
+    "is_writable",
+    "select_columnar",
+    "dump_schema",
+    "blob_size",
+    "schema_hash",
+    "assert_schema",
+    "list_background_tasks",
+    "cancel_background_task",
+    "insert",
+    "update",
+    "get_config",
 ];
 
 fn main() {
@@ -4,20 +4,346 @@
 
 import { invoke } from '@tauri-apps/api/core'
 
+/**
+ * Result of `execute` as returned by the plugin, tagged with the kind of
+ * statement that ran.
+ */
+export type ExecuteResult =
+  | { kind: 'ddl' }
+  | { kind: 'insert'; rows: number; lastId: number }
+  | { kind: 'update' | 'delete' | 'other'; rows: number }
+
 export interface QueryResult {
-  /** The number of rows affected by the query. */
+  /**
+   * Kind of statement that ran: `'ddl'` for `CREATE`/`DROP`/`ALTER`,
+   * `'insert'` for `INSERT`/`REPLACE`, `'update'`, `'delete'`, or `'other'`
+   * (e.g. `PRAGMA` or statements starting with `WITH`).
+   */
+  kind: ExecuteResult['kind']
+  /** The number of rows affected by the query. Always `0` for DDL. */
   rowsAffected: number
   /**
-   * The last inserted `id`.
-   *
-   * This value is not set for Postgres databases. If the
-   * last inserted id is required on Postgres, the `select` function
-   * must be used, with a `RETURNING` clause
-   * (`INSERT INTO todos (title) VALUES ($1) RETURNING id`).
+   * The rowid of the last inserted row. Only set for inserts.
    */
   lastInsertId?: number
 }
 
+function toQueryResult(result: ExecuteResult): QueryResult {
+  return {
+    kind: result.kind,
+    rowsAffected: 'rows' in result ? result.rows : 0,
+    lastInsertId: result.kind === 'insert' ? result.lastId : undefined
+  }
+}
+
+/** Result of `Database.executeMany`. */
+export interface ExecuteManyResult {
+  /** Number of rows changed by the rows that ran successfully. */
+  rows: number
+  /** With `perRowCommit`, index of the row that failed; later rows did not run. */
+  failedRow: number | null
+  /** Error of the failed row. */
+  error: string | null
+}
+
+/** Per-database options accepted by `Database.load`. */
+export interface LoadOptions {
+  /** Prefix applied to this database's tables, e.g. a tenant id. */
+  tablePrefix?: string
+  /**
+   * Create the database file if it does not exist. Defaults to the
+   * `createMissingFiles` plugin config. When `false`, loading a missing file
+   * fails instead of silently creating an empty database.
+   */
+  createMissing?: boolean
+  /**
+   * Name of a registered SQLite VFS used for every connection of this
+   * database. Loading fails if the VFS is not registered.
+   */
+  vfs?: string
+  /**
+   * `PRAGMA synchronous` level for every connection of this database.
+   * Defaults to the `synchronous` plugin config. `'normal'` is safe in WAL
+   * mode but may lose the latest commits on power loss; `'off'` can corrupt
+   * the database on power loss or OS crash.
+   */
+  synchronous?: 'off' | 'normal' | 'full' | 'extra'
+  /**
+   * `[name, value]` pairs run as `PRAGMA name = value`, in order, on every
+   * connection of this database (including transactions), after the
+   * `pragmas` plugin config. Only well-known pragmas such as `journal_mode`,
+   * `cache_size` or `foreign_keys` are accepted. Session pragmas like
+   * `case_sensitive_like` are reapplied whenever a connection is reopened,
+   * unlike ones set with `execute`.
+   */
+  pragmas?: Array<[string, string]>
+  /**
+   * Absolute directory the database path is resolved against instead of the
+   * app data directory, e.g. a folder returned by a file dialog. It is created
+   * if missing unless `createMissing` is `false`.
+   */
+  baseDir?: string
+  /**
+   * For a `sqlite::resource:` database, copy the bundled file to the app data
+   * directory on first load and open the writable copy instead of opening the
+   * original read-only.
+   */
+  copyToData?: boolean
+  /**
+   * Apply the migrations registered for this connection string right after
+   * opening it, those set with `setMigrations` or else those added with
+   * `Builder::add_migrations`. A failing migration rejects the load.
+   */
+  migrateOnLoad?: boolean
+}
+
+/** Per-call options accepted by `execute`. */
+export interface ExecuteOptions {
+  /**
+   * Outside a transaction, run the statement in its own `BEGIN IMMEDIATE` /
+   * `COMMIT` and retry the whole transaction while the database is busy or
+   * locked, instead of failing once the busy timeout runs out.
+   */
+  retryOnBusy?: boolean
+  /** How many times to retry with `retryOnBusy`; defaults to `busyRetries`. */
+  maxRetries?: number
+}
+
+/** Per-call options accepted by `select` and `selectAsync`. */
+export interface SelectOptions {
+  /**
+   * Leave NULL columns out of each row instead of returning them as `null`.
+   * Rows may then have different keys, and columns that are NULL in every row
+   * don't appear at all.
+   */
+  skipNulls?: boolean
+  /**
+   * Columns whose text values are parsed as JSON and returned as objects,
+   * arrays or scalars instead of strings. Names are matched after
+   * `columnCase` is applied. Values that are not valid JSON stay strings,
+   * unless the `strictJsonColumns` plugin config is set.
+   */
+  jsonColumns?: string[]
+  /**
+   * Maximum number of rows to return, bound as `LIMIT ?` instead of being
+   * formatted into the SQL. The query must not have its own `LIMIT`.
+   */
+  limit?: number
+  /** Number of rows to skip, bound as `OFFSET ?`. */
+  offset?: number
+}
+
+/** Result of `Database.selectWithOrigin`. */
+export interface OriginResult {
+  columns: Array<{
+    /** Result column name, after `columnCase` is applied. */
+    name: string
+    /** Database of the source table, e.g. `'main'`. */
+    databaseName: string | null
+    /** Table the column is read from; `null` for expressions. */
+    tableName: string | null
+    /** Column name in that table, which differs from `name` with `AS`. */
+    originName: string | null
+  }>
+  /** Rows as arrays of values, in column order. */
+  rows: unknown[][]
+}
+
+/** An index as returned by `Database.indexes`. */
+export interface IndexInfo {
+  name: string
+  unique: boolean
+  /** `'c'` for `CREATE INDEX`, `'u'` for a `UNIQUE` constraint, `'pk'` for a primary key. */
+  origin: 'c' | 'u' | 'pk'
+  /** Whether the index has a `WHERE` clause. */
+  partial: boolean
+  /** Indexed columns in key order; `null` for an expression. */
+  columns: Array<string | null>
+}
+
+/** Options a database was loaded with, as returned by `getAliasConfig`. */
+export interface AliasConfig {
+  tablePrefix: string | null
+  /** Migration version reached by `migrateOnLoad`, or `null` if it didn't run. */
+  migrationVersion: number | null
+}
+
+/** A migration registered at runtime with `setMigrations`. */
+export interface MigrationInput {
+  version: number
+  description?: string
+  upSql: string
+  downSql?: string
+}
+
+/** Result of `migrationRepair`. */
+export interface MigrationReport {
+  /** Schema version after the check (and repair, if any). */
+  currentVersion: number
+  /** Number of defined migrations. */
+  latestVersion: number
+  /** Whether pending migrations were applied. */
+  repaired: boolean
+}
+
+/** Result of `migrationStatus`. */
+export interface MigrationStatus {
+  /** Number of migrations applied to the database (its `user_version`). */
+  currentVersion: number
+  /** Applied migrations, oldest first. */
+  applied: Array<{ version: number; description: string }>
+  /** Migrations not applied yet, in the order they would run. */
+  pending: Array<{ version: number; description: string; direction: 'up' | 'down' }>
+}
+
+/**
+ * Bind value with an explicit SQLite storage class. `blob` data is base64,
+ * `integer` data may be a string to keep precision beyond 2^53, and `json`
+ * data, e.g. an array or object, is bound as its JSON text.
+ */
+export type TypedValue =
+  | { type: 'null' }
+  | { type: 'integer'; data: number | string }
+  | { type: 'real'; data: number | string }
+  | { type: 'text'; data: string }
+  | { type: 'blob'; data: string }
+  | { type: 'json'; data: unknown }
+
+/** Result of `Database.beginMultiTransaction`. */
+export interface MultiTransaction {
+  /** Coordinating id passed to `commitMulti`/`rollbackMulti`. */
+  id: string
+  /** Transaction id of each database path, usable with `execute` and `select`. */
+  transactions: Record<string, TxId>
+}
+
+/** Result of `Database.reset`. */
+export interface ResetReport {
+  /** Open transactions that were rolled back. */
+  transactions: number
+  /** Databases that were closed. */
+  databases: number
+}
+
+/** Result of `Database.run`. */
+export interface RunResult<T = Record<string, unknown>> {
+  /** Rows returned by the statement; empty for statements without results. */
+  rows: T[]
+  /** Rows inserted, updated or deleted; `0` for read-only statements. */
+  rowsAffected: number
+  /** Rowid of the last row inserted on the connection. */
+  lastInsertId: number
+}
+
+/** Result of `Database.incrementalVacuum`. */
+export interface IncrementalVacuumReport {
+  freelistBefore: number
+  freelistAfter: number
+}
+
+/** SQLite limit categories, the `SQLITE_LIMIT_*` names in snake case. */
+export type SqliteLimit =
+  | 'length'
+  | 'sql_length'
+  | 'column'
+  | 'expr_depth'
+  | 'compound_select'
+  | 'vdbe_op'
+  | 'function_arg'
+  | 'attached'
+  | 'like_pattern_length'
+  | 'variable_number'
+  | 'trigger_depth'
+  | 'worker_threads'
+
+/** Result of `Database.queryStats`. */
+export interface QueryStats {
+  /** Rows of `sqlite_stat1`: `tbl`, `idx` and `stat`. */
+  stat1: Array<{ tbl: string; idx: string | null; stat: string }>
+  /** Rows of `sqlite_stat4`; empty unless SQLite is built with STAT4. */
+  stat4: Record<string, unknown>[]
+}
+
+/** One statement of `Database.withForeignKeysOff`. */
+export interface Operation {
+  sql: string
+  values?: unknown[]
+}
+
+/** A row whose foreign key references a missing parent row. */
+export interface ForeignKeyViolation {
+  table: string
+  /** `null` for `WITHOUT ROWID` tables. */
+  rowid: number | null
+  parent: string
+  /** Index of the violated foreign key of `table`. */
+  fkid: number
+}
+
+/** Result of `Database.diagnostics`. */
+export interface Diagnostics {
+  sqliteVersion: string
+  databases: Array<{
+    alias: string
+    /** Resolved path, or `':memory:'`. */
+    path: string
+    /** Size of the database file; `null` for in-memory databases. */
+    sizeBytes: number | null
+    /** `PRAGMA journal_mode`, e.g. `'wal'`; `null` if it could not be read. */
+    journalMode: string | null
+  }>
+  /** Open transactions, oldest first. */
+  transactions: Array<{ id: TxId; ageMs: number }>
+  /** Shared connections opened since startup, including reopened ones. */
+  connectionsCreated: number
+  /** Shared connections reopened because of `maxConnectionLifetimeMs`/`maxIdleTimeMs`. */
+  connectionsRecycled: number
+}
+
+/** Payload of the `sql://changed` event emitted by `Database.pollWatch`. */
+export interface PollChange<T = Record<string, unknown>> {
+  watchId: string
+  dbAlias: string
+  added: T[]
+  removed: T[]
+  /** Rows matched by the key column whose other values changed, as they are now. */
+  changed: T[]
+}
+
+/**
+ * The plugin configuration returned by `Database.getConfig`, keyed like the
+ * `rusqlite2` section of `tauri.conf.json`.
+ */
+export type PluginConfig = Record<string, unknown>
+
+/** A task listed by `Database.listBackgroundTasks`. */
+export interface BackgroundTask {
+  id: string
+  /** What the task is, e.g. `poll_watch`. */
+  kind: string
+  dbAlias: string
+}
+
+/** Payload of the `sql://database-created` event emitted by `Database.load`. */
+export interface DatabaseCreated {
+  dbAlias: string
+}
+
+/** Payload of the `sql://import-progress` event emitted by `Database.importSqlFile`. */
+export interface ImportProgress {
+  dbAlias: string
+  /** Statements executed so far. */
+  statements: number
+}
+
+/** Result of `Database.selectColumnar`. */
+export interface ColumnarResult {
+  /** Result column names, in query order. */
+  columns: string[]
+  /** The values of each column, in row order. */
+  data: Record<string, unknown[]>
+}
+
 /** Transaction identifier. */
 export type TxId = string
 
@@ -43,15 +369,73 @@ export default class Database {
    *
    * The path is relative to `tauri::path::BaseDirectory::App` and must start with `sqlite:`.
    *
+   * If the database file did not exist, a `sql://database-created` event is
+   * emitted before this resolves, e.g. to seed the new database.
+   *
+   * `sqlite::memory:` opens an in-memory database that is shared per alias:
+   * loading the same string again, e.g. from another component, returns the
+   * database already loaded. Use `loadInMemoryPersistent` with distinct names
+   * for isolated in-memory databases.
+   *
    * @example
    * ```ts
    * const db = await Database.load("sqlite:test.db", ["path/to/ext_1", "path/to/ext_2"]);
+   * const tenantDb = await Database.load("sqlite:tenants.db", [], { tablePrefix: "tenant_a_" });
    * ```
    */
-  static async load(path: string, extensions: string[]): Promise<Database> {
+  static async load(
+    path: string,
+    extensions: string[],
+    options?: LoadOptions
+  ): Promise<Database> {
     const _path = await invoke<string>('plugin:rusqlite2|load', {
       db: path,
-      extensions: extensions
+      extensions: extensions,
+      options: options ?? null
+    })
+
+    return new Database(_path)
+  }
+
+  /**
+   * **rekeyVerified**
+   *
+   * Changes the SQLCipher key of this database to `newKey`, then verifies it
+   * by opening the database with the new key. If verification fails the old
+   * key is restored and this rejects. Later connections use the new key.
+   * Run it while no transaction is open on the database.
+   *
+   * @example
+   * ```ts
+   * const db = await Database.load("sqlite:old-key:secret.db", []);
+   * await db.rekeyVerified("new-key");
+   * ```
+   */
+  async rekeyVerified(newKey: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|rekey_verified', {
+      dbAlias: this.path,
+      newKey
+    })
+  }
+
+  /**
+   * **loadInMemoryPersistent**
+   *
+   * Creates an in-memory database registered under `alias`. Unlike
+   * `sqlite::memory:`, every connection for the alias (including transactions)
+   * shares the same database until it is closed, which makes it suitable for
+   * seeding test data through the command API. Each alias is a separate
+   * database, so components can keep isolated scratch databases under their
+   * own names; loading an alias that is already loaded fails.
+   *
+   * @example
+   * ```ts
+   * const db = await Database.loadInMemoryPersistent("scratch");
+   * ```
+   */
+  static async loadInMemoryPersistent(alias: string): Promise<Database> {
+    const _path = await invoke<string>('plugin:rusqlite2|load_in_memory_persistent', {
+      alias
     })
 
     return new Database(_path)
@@ -91,6 +475,7 @@ export default class Database {
    * @param query - The SQL query string.
    * @param bindValues - Optional array of values to bind to placeholders in the query.
    * @param txId - Optional transaction identifier. If provided, the query runs within that transaction.
+   * @param options - Outside a transaction, `retryOnBusy` retries the write while the database is busy.
    * @returns A Promise resolving to the query result.
    *
    * @example
@@ -113,22 +498,145 @@ export default class Database {
    * } catch (e) {
    *   await db.rollbackTransaction(tx);
    * }
+   *
+   * // Retry on write contention from other processes
+   * await db.execute(
+   *   "UPDATE counters SET value = value + 1 WHERE id = ?",
+   *   [ 1 ],
+   *   undefined,
+   *   { retryOnBusy: true, maxRetries: 5 }
+   * );
+   * ```
+   */
+  async execute(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId,
+    options?: ExecuteOptions
+  ): Promise<QueryResult> {
+    const result = await invoke<ExecuteResult>('plugin:rusqlite2|execute', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null,
+      retryOnBusy: options?.retryOnBusy ?? null,
+      maxRetries: options?.maxRetries ?? null
+    })
+    return toQueryResult(result)
+  }
+
+  /**
+   * **executeReturning**
+   *
+   * Runs a write statement with a `RETURNING` clause and resolves to its
+   * rows, one per row inserted, updated or deleted. `execute` rejects
+   * statements that return rows.
+   *
+   * @example
+   * ```ts
+   * const deleted = await db.executeReturning<{ id: number }>(
+   *   "DELETE FROM sessions WHERE expires_at < ? RETURNING id",
+   *   [ Date.now() ]
+   * );
+   * ```
+   */
+  async executeReturning<T = Record<string, unknown>>(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<T[]> {
+    return await invoke<T[]>('plugin:rusqlite2|execute_returning', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **withForeignKeysOff**
+   *
+   * Runs `ops` as one atomic batch with foreign key checks deferred to the
+   * end, so related tables can be bulk loaded in any order. If violations
+   * remain, the batch is rolled back and they are returned; an empty array
+   * means it was committed. The check covers the whole database.
+   *
+   * @example
+   * ```ts
+   * const violations = await db.withForeignKeysOff([
+   *   { sql: "INSERT INTO books (id, author_id) VALUES (?, ?)", values: [ 1, 7 ] },
+   *   { sql: "INSERT INTO authors (id) VALUES (?)", values: [ 7 ] }
+   * ]);
+   * ```
+   */
+  async withForeignKeysOff(ops: Operation[]): Promise<ForeignKeyViolation[]> {
+    return await invoke<ForeignKeyViolation[]>('plugin:rusqlite2|with_foreign_keys_off', {
+      dbAlias: this.path,
+      ops
+    })
+  }
+
+  /**
+   * **importSqlFile**
+   *
+   * Runs the SQL script at `srcPath`, resolved under the app data directory,
+   * one statement at a time as the file is read. The import is atomic: if a
+   * statement fails, nothing it did is kept and the promise rejects. The
+   * dump's own `BEGIN`/`COMMIT` statements are skipped. A
+   * `sql://import-progress` event is emitted every 1000 statements.
+   * Resolves with the number of statements executed.
+   *
+   * @example
+   * ```ts
+   * const count = await db.importSqlFile("dumps/seed.sql");
+   * ```
+   */
+  async importSqlFile(srcPath: string, txId?: TxId): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|import_sql_file', {
+      dbAlias: this.path,
+      srcPath,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **executeMany**
+   *
+   * Runs `query` once for each array of bind values in `rows`.
+   *
+   * By default the whole batch is atomic: when a row fails, none of the rows
+   * is written and the promise rejects. With `perRowCommit` each row commits
+   * on its own, so rows before a failure are kept; the promise then resolves
+   * with the index and error of the failed row, and later rows are not run.
+   * Committing every row is slower and a crash can leave half a batch behind.
+   *
+   * @example
+   * ```ts
+   * const { rows } = await db.executeMany(
+   *   'INSERT INTO items (name) VALUES (?)',
+   *   [['Laptop'], ['Phone']]
+   * );
+   * const { failedRow, error } = await db.executeMany(
+   *   'INSERT INTO items (name) VALUES (?)',
+   *   rows,
+   *   undefined,
+   *   { perRowCommit: true }
+   * );
    * ```
    */
-  async execute(query: string, bindValues?: unknown[], txId?: TxId): Promise<QueryResult> {
-    const [rowsAffected, lastInsertId] = await invoke<[number, number]>(
-      'plugin:rusqlite2|execute',
-      {
-        dbAlias: this.path,
-        query,
-        values: bindValues ?? [],
-        txId: txId ?? null
-      }
-    )
-    return {
-      lastInsertId,
-      rowsAffected
-    }
+  async executeMany(
+    query: string,
+    rows: unknown[][],
+    txId?: TxId,
+    options?: { perRowCommit?: boolean }
+  ): Promise<ExecuteManyResult> {
+    return await invoke<ExecuteManyResult>('plugin:rusqlite2|execute_many', {
+      dbAlias: this.path,
+      query,
+      values: rows,
+      txId: txId ?? null,
+      perRowCommit: options?.perRowCommit ?? null
+    })
   }
 
   /**
@@ -140,6 +648,7 @@ export default class Database {
    * @param query - The SQL query string.
    * @param bindValues - Optional array of values to bind to placeholders in the query.
    * @param txId - Optional transaction identifier. If provided, the query runs within that transaction.
+   * @param options - Optional per-call options, e.g. `skipNulls`.
    * @returns A Promise resolving to the selected rows.
    *
    * @example
@@ -156,94 +665,1526 @@ export default class Database {
    *   tx
    * );
    * await db.rollbackTransaction(tx); // Or commit
+   *
+   * // Third page of 20 users
+   * const page = await db.select<Array<{ id: number }>>(
+   *   "SELECT id FROM users ORDER BY id", [], undefined, { limit: 20, offset: 40 }
+   * );
    * ```
    */
-  async select<T>(query: string, bindValues?: unknown[], txId?: TxId): Promise<T> {
+  async select<T>(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId,
+    options?: SelectOptions
+  ): Promise<T> {
     const result = await invoke<T>('plugin:rusqlite2|select', {
       dbAlias: this.path,
       query,
       values: bindValues ?? [],
-      txId: txId ?? null
+      txId: txId ?? null,
+      skipNulls: options?.skipNulls ?? null,
+      jsonColumns: options?.jsonColumns ?? null,
+      limit: options?.limit ?? null,
+      offset: options?.offset ?? null
     })
 
     return result
   }
 
   /**
-   * **close**
+   * **selectJson**
    *
-   * Removes the database alias association. This prevents new operations
-   * from being started with this alias until `load` is called again.
-   * Does not affect currently active transactions, which will continue until
-   * committed or rolled back.
+   * Like `select`, but the rows are sent as one JSON string that is parsed
+   * here once, which is faster for large results.
    *
    * @example
    * ```ts
-   * const success = await db.close()
+   * const rows = await db.selectJson<{ id: number; name: string }>(
+   *   "SELECT id, name FROM events WHERE day = ?", [ day ]
+   * );
    * ```
-   * @param dbPath - The specific database path/alias to close. If omitted, attempts to close the alias associated with this `Database` instance.
    */
-  async close(dbPath?: string): Promise<boolean> {
-    const success = await invoke<boolean>('plugin:rusqlite2|close', {
-      db: dbPath ?? this.path // Use provided path or instance path
+  async selectJson<T>(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<T[]> {
+    const json = await invoke<string>('plugin:rusqlite2|select_json', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
     })
-    return success
+    return JSON.parse(json) as T[]
   }
 
-  // --- Transaction Commands ---
-
   /**
-   * **beginTransaction**
+   * **selectColumnar**
    *
-   * Starts a new transaction and returns a unique transaction identifier.
-   * All subsequent `execute` or `select` calls using this identifier will run
-   * within the same transaction context.
+   * Like `select`, but resolves to the values grouped by column: `data` maps
+   * each name in `columns` to that column's values in row order, the shape
+   * charting libraries expect for series. Without rows, every column maps to
+   * an empty array.
    *
-   * @returns A Promise resolving to the transaction identifier string.
+   * @example
+   * ```ts
+   * const { data } = await db.selectColumnar(
+   *   "SELECT day, total FROM sales WHERE month = ? ORDER BY day", [ month ]
+   * );
+   * chart.setSeries(data.day, data.total);
+   * ```
+   */
+  async selectColumnar(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<ColumnarResult> {
+    return await invoke<ColumnarResult>('plugin:rusqlite2|select_columnar', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **selectOne**
+   *
+   * Like `select`, but resolves to the first row only, or `null` if there is
+   * none. The query stops at the first row instead of reading all of them.
    *
    * @example
    * ```ts
-   * const txId = await db.beginTransaction();
+   * const user = await db.selectOne<{ id: number; name: string }>(
+   *   "SELECT id, name FROM users WHERE email = ?", [ email ]
+   * );
    * ```
    */
-  async beginTransaction(): Promise<TxId> {
-    return await invoke<TxId>('plugin:rusqlite2|begin_transaction', {
-      dbAlias: this.path
+  async selectOne<T>(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<T | null> {
+    return await invoke<T | null>('plugin:rusqlite2|select_one', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
     })
   }
 
   /**
-   * **commitTransaction**
+   * **insert**
    *
-   * Commits the transaction identified by `txId`.
+   * Inserts a row into `table` from an object mapping column names to values
+   * and resolves to its rowid, so values can't end up bound to the wrong
+   * placeholder. Names are validated and quoted. `onConflict` makes it an
+   * `INSERT OR IGNORE` or `INSERT OR REPLACE`; an ignored row resolves to
+   * `null`.
    *
-   * @param txId - The transaction identifier returned by `beginTransaction`.
+   * @example
+   * ```ts
+   * const id = await db.insert("users", { name: "Bob", email: "bob@example.com" });
+   * await db.insert("tags", { name: "urgent" }, "ignore");
+   * ```
+   */
+  async insert(
+    table: string,
+    values: Record<string, unknown>,
+    onConflict?: 'ignore' | 'replace',
+    txId?: TxId
+  ): Promise<number | null> {
+    return await invoke<number | null>('plugin:rusqlite2|insert', {
+      dbAlias: this.path,
+      table,
+      values,
+      onConflict: onConflict ?? null,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **update**
+   *
+   * Updates the rows of `table` whose columns equal the values in `whereEq`
+   * with the values in `set`, both objects mapping column names to values, and
+   * resolves to the number of rows updated. Names are validated and quoted.
+   * An empty `whereEq` is rejected rather than updating the whole table,
+   * unless `allowFullTable` is true.
    *
    * @example
    * ```ts
-   * await db.commitTransaction(txId);
+   * const updated = await db.update("users", { name: "Robert" }, { id: 1 });
+   * await db.update("users", { active: 0 }, {}, true);
    * ```
    */
-  async commitTransaction(txId: TxId): Promise<void> {
-    await invoke<void>('plugin:rusqlite2|commit_transaction', { txId })
+  async update(
+    table: string,
+    set: Record<string, unknown>,
+    whereEq: Record<string, unknown>,
+    allowFullTable?: boolean,
+    txId?: TxId
+  ): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|update', {
+      dbAlias: this.path,
+      table,
+      set,
+      whereEq,
+      allowFullTable: allowFullTable ?? null,
+      txId: txId ?? null
+    })
   }
 
   /**
-   * **rollbackTransaction**
+   * **existsWhere**
    *
-   * Rolls back the transaction identified by `txId`.
+   * Resolves to whether `table` has a row matching `whereClause`, stopping at
+   * the first match. An empty `whereClause` checks for any row. The table name
+   * is validated, but `whereClause` is raw SQL: use placeholders and pass user
+   * input in `bindValues`.
    *
-   * @param txId - The transaction identifier returned by `beginTransaction`.
+   * @example
+   * ```ts
+   * const taken = await db.existsWhere("users", "email = ?", [ email ]);
+   * ```
+   */
+  async existsWhere(
+    table: string,
+    whereClause: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|exists_where', {
+      dbAlias: this.path,
+      table,
+      whereClause,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **blobSize**
+   *
+   * Resolves to the length of `column` in the row of `table` with `rowid`, in
+   * bytes for a BLOB, without transferring the value, or `null` if the row
+   * doesn't exist or the value is NULL.
    *
    * @example
    * ```ts
-   * await db.rollbackTransaction(txId);
+   * const size = await db.blobSize("attachments", "data", rowid);
    * ```
    */
-  async rollbackTransaction(txId: TxId): Promise<void> {
+  async blobSize(
+    table: string,
+    column: string,
+    rowid: number,
+    txId?: TxId
+  ): Promise<number | null> {
+    return await invoke<number | null>('plugin:rusqlite2|blob_size', {
+      dbAlias: this.path,
+      table,
+      column,
+      rowid,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **selectCount**
+   *
+   * Resolves to how many rows `query` returns, without fetching them, by
+   * running it as `SELECT COUNT(*) FROM (query)`. Pass the query given to
+   * `select` with `limit` and `offset`: it must not have a `LIMIT` of its own.
+   *
+   * @example
+   * ```ts
+   * const query = "SELECT * FROM users WHERE active = ? ORDER BY name";
+   * const total = await db.selectCount(query, [ true ]);
+   * const page = await db.select(query, [ true ], undefined, { limit: 20, offset: 40 });
+   * ```
+   */
+  async selectCount(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|select_count', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **prepare**
+   *
+   * Compiles `sql` once and resolves to a handle for `run`, so a statement
+   * executed many times is not parsed again on every call. The compiled
+   * statement is cached on the database's connection and recompiled
+   * transparently if that connection is reopened. Runs on one database are
+   * serialized with its other calls, so a handle can be used from any window.
+   * It stays valid until `finalize`, `close` or `Database.reset`.
+   *
+   * @example
+   * ```ts
+   * const handle = await db.prepare("INSERT INTO events (name) VALUES (?)");
+   * for (const name of names) {
+   *   await Database.run(handle, [ name ]);
+   * }
+   * await Database.finalize(handle);
+   * ```
+   */
+  async prepare(sql: string): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|prepare', {
+      dbAlias: this.path,
+      sql
+    })
+  }
+
+  /**
+   * **run**
+   *
+   * Runs the statement of a `prepare` handle with `bindValues` and resolves
+   * to its rows, if any, and the rows it changed.
+   *
+   * @example
+   * ```ts
+   * const { rows } = await Database.run<{ id: number }>(handle, [ 42 ]);
+   * ```
+   */
+  static async run<T = Record<string, unknown>>(
+    handle: string,
+    bindValues?: unknown[]
+  ): Promise<RunResult<T>> {
+    return await invoke<RunResult<T>>('plugin:rusqlite2|run', {
+      handle,
+      values: bindValues ?? []
+    })
+  }
+
+  /**
+   * **finalize**
+   *
+   * Drops a `prepare` handle. Resolves to whether it existed.
+   *
+   * @example
+   * ```ts
+   * await Database.finalize(handle);
+   * ```
+   */
+  static async finalize(handle: string): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|finalize', { handle })
+  }
+
+  /**
+   * **selectWithOrigin**
+   *
+   * Like `select`, but returns rows as arrays in column order together with
+   * the table and table column each result column comes from, so same-named
+   * columns of a join can be told apart. Origins require the plugin's
+   * `column_metadata` feature and are `null` without it.
+   *
+   * @example
+   * ```ts
+   * const { columns, rows } = await db.selectWithOrigin(
+   *   'SELECT u.id, o.id FROM users u JOIN orders o ON o.user_id = u.id'
+   * );
+   * // columns[1].tableName === 'orders'
+   * ```
+   */
+  async selectWithOrigin(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId
+  ): Promise<OriginResult> {
+    return await invoke<OriginResult>('plugin:rusqlite2|select_with_origin', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **cloneDatabase**
+   *
+   * Copies this database to `destPath` (relative to `app_data_dir`) and
+   * loads the copy as `newAlias` with the same password, extensions and
+   * options. This database stays loaded. Fails if `newAlias` is already
+   * loaded or `destPath` exists.
+   *
+   * @example
+   * ```ts
+   * const copy = await db.cloneDatabase('sqlite::saved.db', 'saved.db');
+   * ```
+   */
+  async cloneDatabase(newAlias: string, destPath: string): Promise<Database> {
+    const _path = await invoke<string>('plugin:rusqlite2|clone_database', {
+      srcAlias: this.path,
+      newAlias,
+      destPath
+    })
+
+    return new Database(_path)
+  }
+
+  /**
+   * **executeAsync**
+   *
+   * Same as `execute`, but the statement runs on a background thread pool
+   * so a long-running write does not hold up other database calls.
+   * Statements on the same database still run one at a time.
+   *
+   * @example
+   * ```ts
+   * await db.executeAsync('DELETE FROM logs WHERE created_at < ?', [cutoff]);
+   * ```
+   */
+  async executeAsync(query: string, bindValues?: unknown[], txId?: TxId): Promise<QueryResult> {
+    const result = await invoke<ExecuteResult>('plugin:rusqlite2|execute_async', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null
+    })
+    return toQueryResult(result)
+  }
+
+  /**
+   * **selectAsync**
+   *
+   * Same as `select`, but the query runs on a background thread pool so a
+   * heavy query does not hold up other database calls.
+   * Queries on the same database still run one at a time.
+   *
+   * @example
+   * ```ts
+   * const report = await db.selectAsync<Array<{ total: number }>>(
+   *   'SELECT count(*) AS total FROM events'
+   * );
+   * ```
+   */
+  async selectAsync<T>(
+    query: string,
+    bindValues?: unknown[],
+    txId?: TxId,
+    options?: SelectOptions
+  ): Promise<T> {
+    return await invoke<T>('plugin:rusqlite2|select_async', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      txId: txId ?? null,
+      skipNulls: options?.skipNulls ?? null,
+      jsonColumns: options?.jsonColumns ?? null,
+      limit: options?.limit ?? null,
+      offset: options?.offset ?? null
+    })
+  }
+
+  /**
+   * **selectMulti**
+   *
+   * Runs a script of several statements and resolves to one array of rows per
+   * statement, in order. The script is split by SQLite itself, so semicolons
+   * inside string literals or comments are handled correctly.
+   * `valuesPerStatement[i]` is bound to the i-th statement.
+   *
+   * @example
+   * ```ts
+   * const [users, [{ n }]] = await db.selectMulti<
+   *   [Array<{ id: number; name: string }>, Array<{ n: number }>]
+   * >('SELECT id, name FROM users WHERE id = ?; SELECT count(*) AS n FROM orders;', [[1], []]);
+   * ```
+   */
+  async selectMulti<T extends unknown[]>(
+    sql: string,
+    valuesPerStatement?: unknown[][],
+    txId?: TxId
+  ): Promise<T> {
+    return await invoke<T>('plugin:rusqlite2|select_multi', {
+      dbAlias: this.path,
+      sql,
+      valuesPerStatement: valuesPerStatement ?? [],
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **exportNdjson**
+   *
+   * Writes every row of `query` to `destPath` (relative to `app_data_dir`) as
+   * newline-delimited JSON, one object per line. Rows are streamed to the
+   * file, so large exports don't need to fit in memory. An existing file is
+   * replaced only once every row is written; if the export fails it is left
+   * as it was. Resolves to the number of rows written.
+   *
+   * @example
+   * ```ts
+   * const count = await db.exportNdjson('SELECT * FROM events', [], 'exports/events.ndjson');
+   * ```
+   */
+  async exportNdjson(query: string, bindValues: unknown[] | undefined, destPath: string): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|export_ndjson', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      destPath
+    })
+  }
+
+  /**
+   * **expandSql**
+   *
+   * Returns `query` with `bindValues` inlined, to see exactly what a query
+   * would run. For debugging only: the query is not executed and the result
+   * should never be executed either. The returned SQL contains the bound
+   * values verbatim, so avoid logging it when they are sensitive.
+   * Requires the plugin to be built with the `expand_sql` feature.
+   *
+   * @example
+   * ```ts
+   * console.debug(await db.expandSql('SELECT * FROM users WHERE id = ?', [1]));
+   * ```
+   */
+  async expandSql(query: string, bindValues?: unknown[]): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|expand_sql', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? []
+    })
+  }
+
+  /**
+   * **deleteByIds**
+   *
+   * Deletes the rows of `table` whose `idColumn` is one of `ids`.
+   * An empty `ids` list deletes nothing. Long lists are deleted in batches
+   * that either all apply or none do.
+   * Optionally runs within a transaction identified by `txId`.
+   *
+   * @returns A Promise resolving to the number of rows deleted.
+   *
+   * @example
+   * ```ts
+   * const deleted = await db.deleteByIds("users", "id", [ 1, 2, 3 ]);
+   * ```
+   */
+  async deleteByIds(
+    table: string,
+    idColumn: string,
+    ids: unknown[],
+    txId?: TxId
+  ): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|delete_by_ids', {
+      dbAlias: this.path,
+      table,
+      idColumn,
+      ids,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **dropTable**
+   *
+   * Drops `table` and resolves to whether it existed. With `ifExists` a
+   * missing table is not an error. The table name may only contain letters,
+   * digits and underscores.
+   * Optionally runs within the transaction identified by `txId`.
+   *
+   * @example
+   * ```ts
+   * const existed = await db.dropTable('sessions', true);
+   * ```
+   */
+  async dropTable(table: string, ifExists = false, txId?: TxId): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|drop_table', {
+      dbAlias: this.path,
+      table,
+      ifExists,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **truncateTable**
+   *
+   * Deletes every row of `table` using SQLite's truncate optimization and
+   * resolves to the number of rows deleted. With `resetAutoincrement`,
+   * `AUTOINCREMENT` ids start over from 1. The table name may only contain
+   * letters, digits and underscores.
+   *
+   * @example
+   * ```ts
+   * const deleted = await db.truncateTable('logs', true);
+   * ```
+   */
+  async truncateTable(table: string, resetAutoincrement = false): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|truncate_table', {
+      dbAlias: this.path,
+      table,
+      resetAutoincrement
+    })
+  }
+
+  /**
+   * **indexes**
+   *
+   * Lists the indexes of `table` with their uniqueness and the columns they
+   * cover, e.g. to check that an index was actually created. Rejects if the
+   * table does not exist. The table name may only contain letters, digits
+   * and underscores.
+   *
+   * @example
+   * ```ts
+   * const indexes = await db.indexes('items');
+   * ```
+   */
+  async indexes(table: string, txId?: TxId): Promise<IndexInfo[]> {
+    return await invoke<IndexInfo[]>('plugin:rusqlite2|indexes', {
+      dbAlias: this.path,
+      table,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **ensureTable**
+   *
+   * Creates `table` with `createSql` unless it already exists, and resolves
+   * to whether it was created. Rejects if the table exists with a different
+   * definition, reporting both. A lightweight alternative to migrations for
+   * simple apps.
+   *
+   * @example
+   * ```ts
+   * await db.ensureTable('notes', 'CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)');
+   * ```
+   */
+  async ensureTable(table: string, createSql: string): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|ensure_table', {
+      dbAlias: this.path,
+      table,
+      createSql
+    })
+  }
+
+  /**
+   * **schemaVersion**
+   *
+   * Returns the schema cookie (`PRAGMA schema_version`), which changes
+   * whenever the schema is modified. Poll it to know when cached table
+   * information needs to be refreshed.
+   * Optionally reads it within the transaction identified by `txId`.
+   *
+   * @example
+   * ```ts
+   * const version = await db.schemaVersion();
+   * ```
+   */
+  async schemaVersion(txId?: TxId): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|schema_version', {
+      dbAlias: this.path,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **dumpSchema**
+   *
+   * Returns the schema as one SQL script: the `CREATE` statements of the
+   * tables, then views, indexes and triggers, each ended with `;`. SQLite's
+   * internal tables are left out. Handy to attach to support requests.
+   *
+   * @example
+   * ```ts
+   * const ddl = await db.dumpSchema();
+   * ```
+   */
+  async dumpSchema(): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|dump_schema', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **vacuumInto**
+   *
+   * Writes a compacted copy of the database to `destPath` (relative to
+   * `app_data_dir`) with `VACUUM INTO`, leaving the original untouched.
+   * Resolves to the size of the new file in bytes. Fails if `destPath`
+   * already exists.
+   *
+   * @example
+   * ```ts
+   * const size = await db.vacuumInto('backups/compact.db');
+   * ```
+   */
+  async vacuumInto(destPath: string): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|vacuum_into', {
+      dbAlias: this.path,
+      destPath
+    })
+  }
+
+  /**
+   * **reindex**
+   *
+   * Rebuilds indexes with `REINDEX`. Without `target` every index in the
+   * database is rebuilt; otherwise only the named index, the indexes of the
+   * named table, or the indexes using the named collation.
+   *
+   * @example
+   * ```ts
+   * await db.reindex();
+   * await db.reindex('idx_users_email');
+   * ```
+   */
+  async reindex(target?: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|reindex', {
+      dbAlias: this.path,
+      target: target ?? null
+    })
+  }
+
+  /**
+   * **incrementalVacuum**
+   *
+   * Frees up to `pages` unused pages, or all of them, with
+   * `PRAGMA incremental_vacuum`, without the full lock of a `VACUUM`.
+   * Resolves to the freelist count before and after. Only works on databases
+   * with `PRAGMA auto_vacuum = INCREMENTAL` and rejects otherwise.
+   *
+   * @example
+   * ```ts
+   * const { freelistAfter } = await db.incrementalVacuum(100);
+   * ```
+   */
+  async incrementalVacuum(pages?: number): Promise<IncrementalVacuumReport> {
+    return await invoke<IncrementalVacuumReport>('plugin:rusqlite2|incremental_vacuum', {
+      dbAlias: this.path,
+      pages: pages ?? null
+    })
+  }
+
+  /**
+   * **setLimit**
+   *
+   * Sets a SQLite limit of this database on its current and future
+   * connections, e.g. raising `variable_number` for bulk inserts that fail
+   * with "too many SQL variables". Resolves to the value in effect, which
+   * SQLite caps at its compile-time maximum. Requires the `limits` feature.
+   *
+   * @example
+   * ```ts
+   * await db.setLimit('variable_number', 32766);
+   * ```
+   */
+  async setLimit(category: SqliteLimit, value: number): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|set_limit', {
+      dbAlias: this.path,
+      category,
+      value
+    })
+  }
+
+  /**
+   * **analyze**
+   *
+   * Runs `ANALYZE` so the query planner picks indexes from fresh statistics.
+   * Without `target` the whole database is analyzed; otherwise only the
+   * named table or index.
+   *
+   * @example
+   * ```ts
+   * await db.analyze();
+   * await db.analyze('users');
+   * ```
+   */
+  async analyze(target?: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|analyze', {
+      dbAlias: this.path,
+      target: target ?? null
+    })
+  }
+
+  /**
+   * **queryStats**
+   *
+   * Resolves to the rows of `sqlite_stat1` and `sqlite_stat4`, the
+   * statistics `analyze` collects. Both are empty before the first
+   * `analyze`, and `stat4` is only filled by SQLite builds with STAT4.
+   *
+   * @example
+   * ```ts
+   * const { stat1 } = await db.queryStats();
+   * // [{ tbl: 'users', idx: 'idx_users_email', stat: '1000 1' }]
+   * ```
+   */
+  async queryStats(): Promise<QueryStats> {
+    return await invoke<QueryStats>('plugin:rusqlite2|query_stats', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **flush**
+   *
+   * Forces committed data onto disk, e.g. before a risky operation or
+   * shutdown: flushes the connection's page cache and, in WAL mode,
+   * checkpoints the WAL into the database file. Resolves to `false` if the
+   * checkpoint was blocked by another connection (committed data is still
+   * safe in the WAL). Does nothing for in-memory databases.
+   *
+   * @example
+   * ```ts
+   * await db.flush();
+   * ```
+   */
+  async flush(): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|flush', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **tableHash**
+   *
+   * Resolves to a hex SHA-256 digest of the contents of `table`, so two
+   * databases can be compared without transferring their rows. Rows are
+   * hashed in `orderBy` order (every column by default), so insertion order
+   * does not matter. NULL, empty strings and empty blobs hash differently.
+   *
+   * @example
+   * ```ts
+   * if ((await local.tableHash('todos')) !== (await remote.tableHash('todos'))) {
+   *   await sync()
+   * }
+   * ```
+   */
+  async tableHash(table: string, orderBy?: string[]): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|table_hash', {
+      dbAlias: this.path,
+      table,
+      orderBy: orderBy ?? null
+    })
+  }
+
+  /**
+   * **dbHash**
+   *
+   * Resolves to a hex SHA-256 digest of the database file, after
+   * checkpointing the WAL. Databases with the same rows can still hash
+   * differently if their files were written differently; use `tableHash` to
+   * compare contents. Fails for in-memory databases.
+   *
+   * @example
+   * ```ts
+   * const digest = await db.dbHash()
+   * ```
+   */
+  async dbHash(): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|db_hash', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **schemaHash**
+   *
+   * Resolves to a hex SHA-256 digest of the schema, ignoring whitespace and
+   * the order objects were created in. Record it during development and check
+   * it with `assertSchema`.
+   *
+   * @example
+   * ```ts
+   * const expected = await db.schemaHash()
+   * ```
+   */
+  async schemaHash(): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|schema_hash', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **assertSchema**
+   *
+   * Rejects with a `schema hash is ..., expected ...` error unless the schema
+   * hashes to `expectedHash`, as returned by `schemaHash`.
+   *
+   * @example
+   * ```ts
+   * await db.assertSchema(EXPECTED_SCHEMA_HASH)
+   * ```
+   */
+  async assertSchema(expectedHash: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|assert_schema', {
+      dbAlias: this.path,
+      expectedHash
+    })
+  }
+
+  /**
+   * **pragma**
+   *
+   * Reads a pragma and resolves to its rows, e.g.
+   * `[{ journal_mode: 'wal' }]`. Set pragmas with `execute`, which ignores the
+   * row some pragmas return.
+   *
+   * @example
+   * ```ts
+   * const [{ journal_mode }] = await db.pragma<Array<{ journal_mode: string }>>('journal_mode')
+   * ```
+   */
+  async pragma<T>(name: string, txId?: TxId): Promise<T> {
+    return await invoke<T>('plugin:rusqlite2|pragma', {
+      dbAlias: this.path,
+      name,
+      txId: txId ?? null
+    })
+  }
+
+  /**
+   * **applicationId**
+   *
+   * Resolves to the database's `PRAGMA application_id`, after setting it to
+   * `set` if given. Use it to check that a file is one of your app's
+   * databases. The plugin `Builder` can set it on newly created databases.
+   *
+   * @example
+   * ```ts
+   * if ((await db.applicationId()) !== MY_APP_ID) {
+   *   throw new Error('Not one of our databases')
+   * }
+   * ```
+   */
+  async applicationId(set?: number): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|application_id', {
+      dbAlias: this.path,
+      set: set ?? null
+    })
+  }
+
+  /**
+   * **jsonGet**
+   *
+   * Extracts `path` from the JSON stored in `jsonColumn` of every row of
+   * `table` matching the optional `whereClause`. `?` placeholders in
+   * `whereClause` are bound from `bindValues`. Objects and arrays are
+   * returned as JSON text.
+   *
+   * @example
+   * ```ts
+   * const names = await db.jsonGet<string>("docs", "body", "$.name", "id > ?", [ 1 ]);
+   * ```
+   */
+  async jsonGet<T>(
+    table: string,
+    jsonColumn: string,
+    path: string,
+    whereClause?: string,
+    bindValues?: unknown[]
+  ): Promise<T[]> {
+    return await invoke<T[]>('plugin:rusqlite2|json_get', {
+      dbAlias: this.path,
+      table,
+      jsonColumn,
+      path,
+      whereClause: whereClause ?? null,
+      values: bindValues ?? []
+    })
+  }
+
+  /**
+   * **copyTable**
+   *
+   * Copies every row of `table` from this database into the database loaded
+   * as `destAlias` in a single transaction. When the destination table is
+   * missing it is created from the source schema if `createIfMissing` is set.
+   * Fails with a column mismatch error if the two tables differ.
+   *
+   * @returns A Promise resolving to the number of rows copied.
+   *
+   * @example
+   * ```ts
+   * const copied = await db.copyTable(archive.path, "items", true);
+   * ```
+   */
+  async copyTable(destAlias: string, table: string, createIfMissing = false): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|copy_table', {
+      srcAlias: this.path,
+      destAlias,
+      table,
+      createIfMissing
+    })
+  }
+
+  /**
+   * **nowIso**
+   *
+   * Returns the database's current time as an ISO 8601 UTC string with
+   * milliseconds, e.g. `'2024-01-31T12:00:00.000Z'`. Columns listed in the
+   * `unixTimeColumns` config are returned in the same format.
+   *
+   * @example
+   * ```ts
+   * await db.execute('UPDATE notes SET edited_at = ? WHERE id = ?', [await db.nowIso(), 7]);
+   * ```
+   */
+  async nowIso(): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|now_iso', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **getPath**
+   *
+   * Returns the resolved filesystem path of this database, or `':memory:'`
+   * for in-memory databases.
+   *
+   * @example
+   * ```ts
+   * const path = await db.getPath();
+   * ```
+   */
+  async getPath(): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|get_path', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **isWritable**
+   *
+   * Resolves to whether writes to this database succeed, e.g. `false` on
+   * read-only media or without write permission. The probe write is always
+   * rolled back.
+   *
+   * @example
+   * ```ts
+   * if (!(await db.isWritable())) {
+   *   showReadOnlyBanner();
+   * }
+   * ```
+   */
+  async isWritable(): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|is_writable', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **getAliasConfig**
+   *
+   * Returns the options this database was loaded with.
+   *
+   * @example
+   * ```ts
+   * const { tablePrefix } = await db.getAliasConfig();
+   * ```
+   */
+  async getAliasConfig(): Promise<AliasConfig> {
+    return await invoke<AliasConfig>('plugin:rusqlite2|get_alias_config', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **releaseConnection**
+   *
+   * Closes the database's live connection, releasing the file, but keeps the
+   * database loaded with its options; the next call reopens the connection.
+   * Useful before another process modifies or replaces the file. Resolves to
+   * `false` if there was no connection to release; in-memory databases keep
+   * theirs, since closing it would discard their data. It also resolves to
+   * `false` while another call is still using the connection, which is then
+   * closed as soon as that call finishes.
+   *
+   * @example
+   * ```ts
+   * await db.releaseConnection();
+   * // ... another process replaces the file ...
+   * const rows = await db.select('SELECT * FROM items');
+   * ```
+   */
+  async releaseConnection(): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|release_connection', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **close**
+   *
+   * Removes the database alias association. This prevents new operations
+   * from being started with this alias until `load` is called again.
+   * Does not affect currently active transactions, which will continue until
+   * committed or rolled back.
+   *
+   * @example
+   * ```ts
+   * const success = await db.close()
+   * ```
+   * @param dbPath - The specific database path/alias to close. If omitted, attempts to close the alias associated with this `Database` instance.
+   */
+  async close(dbPath?: string): Promise<boolean> {
+    const success = await invoke<boolean>('plugin:rusqlite2|close', {
+      db: dbPath ?? this.path // Use provided path or instance path
+    })
+    return success
+  }
+
+  // --- Transaction Commands ---
+
+  /**
+   * **beginTransaction**
+   *
+   * Starts a new transaction and returns a unique transaction identifier.
+   * All subsequent `execute` or `select` calls using this identifier will run
+   * within the same transaction context.
+   *
+   * @returns A Promise resolving to the transaction identifier string.
+   *
+   * @example
+   * ```ts
+   * const txId = await db.beginTransaction();
+   * ```
+   */
+  async beginTransaction(): Promise<TxId> {
+    return await invoke<TxId>('plugin:rusqlite2|begin_transaction', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **beginReadTransaction**
+   *
+   * Starts a read-only snapshot transaction. It never blocks writers and,
+   * with WAL journaling, keeps seeing the same data until it is committed or
+   * rolled back. The identifier can be used with `select`; `execute` rejects it.
+   *
+   * @returns A Promise resolving to the transaction identifier string.
+   *
+   * @example
+   * ```ts
+   * const txId = await db.beginReadTransaction();
+   * const totals = await db.select("SELECT sum(amount) AS total FROM orders", [], txId);
+   * await db.rollbackTransaction(txId);
+   * ```
+   */
+  async beginReadTransaction(): Promise<TxId> {
+    return await invoke<TxId>('plugin:rusqlite2|begin_read_transaction', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **transactionChanges**
+   *
+   * Returns the number of rows inserted, updated or deleted in the
+   * transaction identified by `txId` so far, across all its statements.
+   *
+   * @example
+   * ```ts
+   * const changed = await db.transactionChanges(txId);
+   * ```
+   */
+  async transactionChanges(txId: TxId): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|transaction_changes', { txId })
+  }
+
+  /**
+   * **commitTransaction**
+   *
+   * Commits the transaction identified by `txId`.
+   *
+   * @param txId - The transaction identifier returned by `beginTransaction`.
+   *
+   * @example
+   * ```ts
+   * await db.commitTransaction(txId);
+   * ```
+   */
+  async commitTransaction(txId: TxId): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|commit_transaction', { txId })
+  }
+
+  /**
+   * **rollbackTransaction**
+   *
+   * Rolls back the transaction identified by `txId`.
+   *
+   * @param txId - The transaction identifier returned by `beginTransaction`.
+   *
+   * @example
+   * ```ts
+   * await db.rollbackTransaction(txId);
+   * ```
+   */
+  async rollbackTransaction(txId: TxId): Promise<void> {
     await invoke<void>('plugin:rusqlite2|rollback_transaction', { txId })
   }
 
+  /**
+   * **rollbackAllTransactions**
+   *
+   * Rolls back every open transaction across all databases.
+   * Intended as a recovery hatch for leaked transactions.
+   *
+   * @returns A Promise resolving to the number of transactions rolled back.
+   *
+   * @example
+   * ```ts
+   * const count = await Database.rollbackAllTransactions();
+   * ```
+   */
+  static async rollbackAllTransactions(): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|rollback_all_transactions')
+  }
+
+  /**
+   * **reset**
+   *
+   * Returns the plugin to a clean slate, e.g. on logout: rolls back every
+   * open transaction, stops every `pollWatch` and closes every database.
+   * Unlike `close()`, which only closes databases, open transactions are
+   * rolled back rather than left running. `Database` instances must be
+   * loaded again afterwards.
+   *
+   * @returns A Promise resolving to how many transactions and databases were cleared.
+   *
+   * @example
+   * ```ts
+   * const { transactions, databases } = await Database.reset();
+   * ```
+   */
+  static async reset(): Promise<ResetReport> {
+    return await invoke<ResetReport>('plugin:rusqlite2|reset')
+  }
+
+  /**
+   * **diagnostics**
+   *
+   * Summarizes the plugin state in one call: the SQLite version, every
+   * loaded database with its path, file size and journal mode, and the open
+   * transactions with their ages. Handy for "about" screens and bug reports.
+   *
+   * @example
+   * ```ts
+   * const report = await Database.diagnostics();
+   * ```
+   */
+  static async diagnostics(): Promise<Diagnostics> {
+    return await invoke<Diagnostics>('plugin:rusqlite2|diagnostics')
+  }
+
+  /**
+   * **getConfig**
+   *
+   * Resolves to the plugin configuration in effect, with the defaults of
+   * unset options filled in and keyed like `tauri.conf.json`. Encryption keys
+   * in `preload` connection strings are replaced with `***`.
+   *
+   * @example
+   * ```ts
+   * const { busyRetries, readerConnections } = await Database.getConfig();
+   * ```
+   */
+  static async getConfig(): Promise<PluginConfig> {
+    return await invoke<PluginConfig>('plugin:rusqlite2|get_config')
+  }
+
+  /**
+   * **beginMultiTransaction**
+   *
+   * Begins a transaction on each of the given databases and groups them
+   * under one id. Use the per-database ids in `transactions` with `execute`
+   * and `select`, then `commitMulti` or `rollbackMulti` with `id`.
+   *
+   * This is not atomic across databases: see `commitMulti`.
+   *
+   * @example
+   * ```ts
+   * const multi = await Database.beginMultiTransaction([orders.path, stock.path]);
+   * await orders.execute('INSERT INTO orders (item) VALUES (?)', [1], multi.transactions[orders.path]);
+   * await stock.execute('UPDATE stock SET qty = qty - 1 WHERE item = ?', [1], multi.transactions[stock.path]);
+   * await Database.commitMulti(multi.id);
+   * ```
+   */
+  static async beginMultiTransaction(paths: string[]): Promise<MultiTransaction> {
+    return await invoke<MultiTransaction>('plugin:rusqlite2|begin_multi_transaction', {
+      aliases: paths
+    })
+  }
+
+  /**
+   * **commitMulti**
+   *
+   * Commits the transactions of a multi-transaction one after another. If a
+   * commit fails, the remaining transactions are rolled back and the error
+   * lists the databases already committed; their changes are kept, so the
+   * databases can end up out of sync.
+   *
+   * @example
+   * ```ts
+   * await Database.commitMulti(multi.id);
+   * ```
+   */
+  static async commitMulti(id: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|commit_multi', { id })
+  }
+
+  /**
+   * **rollbackMulti**
+   *
+   * Rolls back every transaction of a multi-transaction.
+   *
+   * @example
+   * ```ts
+   * await Database.rollbackMulti(multi.id);
+   * ```
+   */
+  static async rollbackMulti(id: string): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|rollback_multi', { id })
+  }
+
+  /**
+   * **setProgressHandler**
+   *
+   * Enables or disables `sql://progress` events for this database. While
+   * enabled, every connection for the database emits an event every
+   * `progressInterval` VM instructions, which lets long operations show a
+   * spinner. Requires the plugin to be built with the `hooks` feature.
+   *
+   * @example
+   * ```ts
+   * import { listen } from '@tauri-apps/api/event'
+   *
+   * await listen('sql://progress', (event) => console.log(event.payload))
+   * await db.setProgressHandler(true)
+   * ```
+   */
+  async setProgressHandler(enabled: boolean): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|set_progress_handler', {
+      dbAlias: this.path,
+      enabled
+    })
+  }
+
+  /**
+   * **interrupt**
+   *
+   * Interrupts the queries currently running on this database's pooled
+   * connection and its reader connections (`readerConnections` config). The
+   * interrupted calls reject with an `interrupted` error.
+   *
+   * @example
+   * ```ts
+   * await db.interrupt()
+   * ```
+   */
+  async interrupt(): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|interrupt', { dbAlias: this.path })
+  }
+
+  /**
+   * **watchChanges**
+   *
+   * Emits a `sql://change` event for every row inserted, updated or deleted
+   * through this database's connections, until `unwatchChanges`, including
+   * ones reopened later. Each event carries the table, rowid, old and new
+   * values and the names of the changed columns.
+   * Requires the plugin to be built with the `preupdate_hook` feature.
+   *
+   * @example
+   * ```ts
+   * import { listen } from '@tauri-apps/api/event'
+   *
+   * await listen('sql://change', (event) => console.log(event.payload))
+   * await db.watchChanges()
+   * ```
+   */
+  async watchChanges(): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|watch_changes', { dbAlias: this.path })
+  }
+
+  /**
+   * **unwatchChanges**
+   *
+   * Stops emitting `sql://change` events for this database.
+   *
+   * @example
+   * ```ts
+   * await db.unwatchChanges()
+   * ```
+   */
+  async unwatchChanges(): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|unwatch_changes', { dbAlias: this.path })
+  }
+
+  /**
+   * **pollWatch**
+   *
+   * Re-runs `query` every `intervalMs` and emits a `sql://changed` event
+   * (see `PollChange`) with the added, removed and changed rows whenever the
+   * result differs from the previous run. Rows are matched by `keyColumn`
+   * when given; otherwise an updated row is reported as removed and added.
+   * Works without the `preupdate_hook` feature and also sees changes made by
+   * transactions. Resolves to the watch id to pass to `pollUnwatch`.
+   *
+   * @example
+   * ```ts
+   * import { listen } from '@tauri-apps/api/event'
+   *
+   * await listen<PollChange>('sql://changed', (event) => console.log(event.payload))
+   * const watchId = await db.pollWatch('SELECT id, title FROM todos', [], 500, 'id')
+   * ```
+   */
+  async pollWatch(
+    query: string,
+    bindValues?: unknown[],
+    intervalMs = 1000,
+    keyColumn?: string
+  ): Promise<string> {
+    return await invoke<string>('plugin:rusqlite2|poll_watch', {
+      dbAlias: this.path,
+      query,
+      values: bindValues ?? [],
+      intervalMs,
+      keyColumn: keyColumn ?? null
+    })
+  }
+
+  /**
+   * **pollUnwatch**
+   *
+   * Stops a watch started with `pollWatch`. Resolves to `false` if it was
+   * not running.
+   *
+   * @example
+   * ```ts
+   * await Database.pollUnwatch(watchId)
+   * ```
+   */
+  static async pollUnwatch(watchId: string): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|poll_unwatch', { watchId })
+  }
+
+  /**
+   * **listBackgroundTasks**
+   *
+   * Lists the tasks the plugin runs in the background, such as `pollWatch`
+   * watches, with their id, kind and database.
+   *
+   * @example
+   * ```ts
+   * const tasks = await Database.listBackgroundTasks()
+   * ```
+   */
+  static async listBackgroundTasks(): Promise<BackgroundTask[]> {
+    return await invoke<BackgroundTask[]>('plugin:rusqlite2|list_background_tasks')
+  }
+
+  /**
+   * **cancelBackgroundTask**
+   *
+   * Stops a background task listed by `listBackgroundTasks`. Resolves to
+   * `false` if it was not running.
+   *
+   * @example
+   * ```ts
+   * for (const task of await Database.listBackgroundTasks()) {
+   *   await Database.cancelBackgroundTask(task.id)
+   * }
+   * ```
+   */
+  static async cancelBackgroundTask(taskId: string): Promise<boolean> {
+    return await invoke<boolean>('plugin:rusqlite2|cancel_background_task', { taskId })
+  }
+
+  /**
+   * **setMigrations**
+   *
+   * Registers migrations for this database at runtime, for databases whose
+   * path isn't known when the plugin is built. Replaces any migrations set
+   * before; they are ordered by `version`, which must run 1, 2, 3, ...
+   * without duplicates or gaps. Nothing is applied until `runMigrations`.
+   *
+   * @example
+   * ```ts
+   * await db.setMigrations([
+   *   { version: 1, upSql: 'CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)' },
+   *   { version: 2, upSql: 'ALTER TABLE notes ADD COLUMN pinned INTEGER' }
+   * ]);
+   * ```
+   */
+  async setMigrations(migrations: MigrationInput[]): Promise<void> {
+    await invoke<void>('plugin:rusqlite2|set_migrations', {
+      dbAlias: this.path,
+      migrations
+    })
+  }
+
+  /**
+   * **runMigrations**
+   *
+   * Applies the migrations registered with `setMigrations` and resolves to
+   * the resulting schema version.
+   *
+   * @example
+   * ```ts
+   * const version = await db.runMigrations();
+   * ```
+   */
+  async runMigrations(): Promise<number> {
+    return await invoke<number>('plugin:rusqlite2|run_migrations', {
+      dbAlias: this.path
+    })
+  }
+
+  /**
+   * **migrationRepair**
+   *
+   * Checks the migrations applied to this database against the ones defined
+   * for it (those from `setMigrations`, otherwise those added with
+   * `Builder::add_migrations`), e.g. after the app crashed mid-migration. Rejects with a
+   * `migrations are inconsistent` error if the database is ahead of the
+   * defined migrations. Pending migrations are applied when `repair` is true.
+   *
+   * @example
+   * ```ts
+   * const report = await db.migrationRepair(true);
+   * ```
+   */
+  async migrationRepair(repair = false): Promise<MigrationReport> {
+    return await invoke<MigrationReport>('plugin:rusqlite2|migration_repair', {
+      dbAlias: this.path,
+      repair
+    })
+  }
+
+  /**
+   * **migrationStatus**
+   *
+   * Lists the migrations applied to this database and those still pending.
+   *
+   * @example
+   * ```ts
+   * const { currentVersion, applied, pending } = await db.migrationStatus();
+   * ```
+   */
+  async migrationStatus(): Promise<MigrationStatus> {
+    return await invoke<MigrationStatus>('plugin:rusqlite2|migration_status', {
+      dbAlias: this.path
+    })
+  }
+
   /**
  * **Migrate To Version**
  *
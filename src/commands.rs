@@ -4,26 +4,98 @@
 
 use indexmap::IndexMap;
 use log::error;
-use rusqlite_migration::Migrations as RusqliteMigrations;
+use rusqlite_migration::{Migrations as RusqliteMigrations, SchemaVersion, M};
 use serde_json::Value as JsonValue;
+use tauri::path::BaseDirectory;
 use tauri::Manager;
 use tauri::{command, AppHandle, Runtime, State};
 
-use crate::utils::lock_mutex;
+use crate::utils::{lock_mutex, quote_identifier};
 // Updated imports
-use crate::{convert, DbInfo, Error, LastInsertId, MigrationList, Rusqlite2Connections}; // Removed DbInfo
-use rusqlite::Connection; // Removed params_from_iter, Statement
-use std::path::PathBuf;
+use crate::{
+    convert, AliasConfig, AppliedMigration, BackgroundTask, BackgroundTaskInfo, BusyHandlerConfig,
+    ColumnCase, ColumnOrigin, ColumnarResult, DatabaseCreated, DatabaseDiagnostics, DbInfo,
+    Diagnostics, Error, ExecuteManyResult, ExecuteResult, ForeignKeyViolation, ImportProgress,
+    IncrementalVacuumReport, LoadOptions, MigrationInput, MigrationKind, MigrationList,
+    MigrationReport, MigrationStatus, MultiTransaction, OnConflict, OpenTransactionOnClose,
+    Operation, OriginResult, PendingMigration, PluginConfig, PollChange, PreparedStatement,
+    QueryStats, ResetReport, RunResult, Rusqlite2Connections, SqliteLimit, TransactionDiagnostics,
+    TransactionIdStyle,
+}; // Removed DbInfo
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, OptionalExtension}; // Removed params_from_iter, Statement
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::RandomState;
+use std::ffi::{c_int, c_void};
+use std::fmt::Write as _;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex}; // Added missing import
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-/// Opens and configures a brand-new `Connection` from a `DbInfo`.
-/// Used by `begin_transaction` and `migrate` which need their own dedicated connection.
+#[cfg(feature = "hooks")]
+use crate::Progress;
+#[cfg(feature = "preupdate_hook")]
+use crate::RowChange;
+#[cfg(feature = "preupdate_hook")]
+use rusqlite::hooks::{
+    Action, PreUpdateCase, PreUpdateNewValueAccessor, PreUpdateOldValueAccessor,
+};
+#[cfg(feature = "preupdate_hook")]
+use std::collections::HashMap;
+use tauri::Emitter;
+
+/// Event emitted periodically by connections with progress reporting enabled.
+#[cfg(feature = "hooks")]
+const PROGRESS_EVENT: &str = "sql://progress";
+
+/// Event emitted by `watch_changes` for every changed row.
+#[cfg(feature = "preupdate_hook")]
+const CHANGE_EVENT: &str = "sql://change";
+
+/// Event emitted by `poll_watch` when the result of a watched query changes.
+const POLL_CHANGE_EVENT: &str = "sql://changed";
+
+/// Event emitted by `load` when opening a database created its file.
+const DATABASE_CREATED_EVENT: &str = "sql://database-created";
+
+/// Event emitted by `import_sql_file` every `IMPORT_PROGRESS_INTERVAL`
+/// statements.
+const IMPORT_PROGRESS_EVENT: &str = "sql://import-progress";
+const IMPORT_PROGRESS_INTERVAL: u64 = 1000;
+
+/// Opens and configures a brand-new `Connection` from a `DbInfo`: key,
+/// extensions, busy handling, `synchronous`, pragmas and limits, in that
+/// order. Every connection of an alias is opened this way, so the dedicated
+/// connections of `begin_transaction` and `migrate` behave like the pooled one.
 fn open_configured_conn(db_info: &DbInfo) -> Result<Connection, crate::Error> {
-    let conn = Connection::open(&db_info.path)
-        .map_err(|e| Error::ConnectionFailed(db_info.path.display().to_string(), e.to_string()))?;
+    let mut flags = OpenFlags::default();
+    if db_info.read_only {
+        flags.remove(OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE);
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+    } else if !db_info.create_missing {
+        flags.remove(OpenFlags::SQLITE_OPEN_CREATE);
+    }
+    let opened = match &db_info.vfs {
+        Some(vfs) => {
+            if !vfs_registered(vfs) {
+                return Err(Error::VfsNotFound(vfs.clone()));
+            }
+            Connection::open_with_flags_and_vfs(&db_info.path, flags, vfs.as_str())
+        }
+        None => Connection::open_with_flags(&db_info.path, flags),
+    };
+    let conn = opened.map_err(|e| match Error::from(e) {
+        Error::Rusqlite(e) => {
+            Error::ConnectionFailed(db_info.path.display().to_string(), e.to_string())
+        }
+        Error::CannotOpen(_) => Error::CannotOpen(db_info.path.display().to_string()),
+        e => e,
+    })?;
 
     if !db_info.pass.is_empty() {
         conn.pragma_update(None, "KEY", &db_info.pass)
@@ -34,12 +106,193 @@ fn open_configured_conn(db_info: &DbInfo) -> Result<Connection, crate::Error> {
 
     load_extensions(&conn, &db_info.extensions)?;
 
-    conn.busy_timeout(Duration::from_millis(5000))
-        .map_err(Error::Rusqlite)?;
+    match db_info.busy_handler {
+        Some(busy) => install_busy_handler(&conn, busy)?,
+        None => conn
+            .busy_timeout(Duration::from_millis(5000))
+            .map_err(Error::from)?,
+    }
+
+    if let Some(level) = db_info.synchronous {
+        conn.pragma_update(None, "synchronous", level.as_pragma())?;
+    }
+    for (name, value) in &db_info.pragmas {
+        conn.pragma_update(None, name.as_str(), value)?;
+    }
+    #[cfg(feature = "limits")]
+    for (&category, &value) in &db_info.limits {
+        conn.set_limit(category.into(), value)?;
+    }
+    #[cfg(not(feature = "limits"))]
+    if !db_info.limits.is_empty() {
+        return Err(Error::FeatureNotEnabled("limits".to_string()));
+    }
+
+    Ok(conn)
+}
+
+/// Installs a busy handler on `conn` that retries with the backoff of `busy`.
+///
+/// `Connection::busy_handler` only takes a plain function pointer, so the
+/// handler is registered through SQLite directly with a pointer to the
+/// settings as its data. Each distinct setting is allocated once and kept for
+/// the life of the process, since SQLite never says when a connection stops
+/// using it.
+fn install_busy_handler(conn: &Connection, busy: BusyHandlerConfig) -> Result<(), crate::Error> {
+    static SETTINGS: Mutex<Vec<&'static BusyHandlerConfig>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn callback(data: *mut c_void, attempt: c_int) -> c_int {
+        let busy = &*(data as *const BusyHandlerConfig);
+        c_int::from(retry_with_backoff(busy, attempt))
+    }
+
+    let busy: &'static BusyHandlerConfig = {
+        let mut settings = lock_mutex(&SETTINGS, "BusyHandlerSettings")?;
+        match settings.iter().find(|known| ***known == busy) {
+            Some(&known) => known,
+            None => {
+                let leaked: &'static BusyHandlerConfig = Box::leak(Box::new(busy));
+                settings.push(leaked);
+                leaked
+            }
+        }
+    };
+    let rc = unsafe {
+        rusqlite::ffi::sqlite3_busy_handler(
+            conn.handle(),
+            Some(callback),
+            busy as *const BusyHandlerConfig as *mut c_void,
+        )
+    };
+    if rc != rusqlite::ffi::SQLITE_OK {
+        return Err(rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rc), None).into());
+    }
+    Ok(())
+}
+
+/// Busy handler sleeping `base_delay_ms * 2^attempt` (capped at one second)
+/// minus up to half of it as jitter, then retrying until `max_attempts` is
+/// reached.
+fn retry_with_backoff(busy: &BusyHandlerConfig, attempt: i32) -> bool {
+    let attempt = attempt.max(0) as u32;
+    if attempt >= busy.max_attempts {
+        return false;
+    }
+    let base = busy.base_delay_ms;
+    let delay = base.saturating_mul(1 << attempt.min(16)).min(1000);
+    let jitter = RandomState::new().build_hasher().finish() % (delay / 2 + 1);
+    std::thread::sleep(Duration::from_millis(delay - jitter));
+    true
+}
+
+/// Pragmas that can be set through the `pragmas` load option and config.
+const ALLOWED_PRAGMAS: &[&str] = &[
+    "analysis_limit",
+    "auto_vacuum",
+    "automatic_index",
+    "busy_timeout",
+    "cache_size",
+    "cache_spill",
+    "case_sensitive_like",
+    "cell_size_check",
+    "defer_foreign_keys",
+    "foreign_keys",
+    "ignore_check_constraints",
+    "journal_mode",
+    "journal_size_limit",
+    "locking_mode",
+    "mmap_size",
+    "page_size",
+    "query_only",
+    "recursive_triggers",
+    "secure_delete",
+    "synchronous",
+    "temp_store",
+    "threads",
+    "trusted_schema",
+    "wal_autocheckpoint",
+];
+
+/// Combines the configured and per-alias pragmas, rejecting names outside
+/// `ALLOWED_PRAGMAS`. Values are bound as literals by `pragma_update`.
+fn validated_pragmas(
+    config: &[(String, String)],
+    options: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, crate::Error> {
+    config
+        .iter()
+        .cloned()
+        .chain(options)
+        .map(|(name, value)| {
+            let name = name.trim().to_ascii_lowercase();
+            if ALLOWED_PRAGMAS.contains(&name.as_str()) {
+                Ok((name, value))
+            } else {
+                Err(Error::PragmaNotAllowed(name))
+            }
+        })
+        .collect()
+}
 
+/// Opens a dedicated connection for `db_alias`, installing the progress handler
+/// when the alias has progress reporting enabled and the change hook on
+/// writable connections when its changes are watched.
+pub(crate) fn open_alias_conn<R: Runtime>(
+    connections: &Rusqlite2Connections<R>,
+    db_alias: &str,
+    db_info: &DbInfo,
+) -> Result<Connection, crate::Error> {
+    let conn = open_configured_conn(db_info)?;
+    #[cfg(feature = "hooks")]
+    if db_info.report_progress {
+        install_progress_handler(&conn, connections, db_alias);
+    }
+    #[cfg(feature = "preupdate_hook")]
+    if db_info.watch_changes && !db_info.read_only {
+        install_change_hook(&conn, connections, db_alias)?;
+    }
+    #[cfg(not(feature = "hooks"))]
+    let _ = (connections, db_alias);
     Ok(conn)
 }
 
+/// Emits a `sql://progress` event every `progress_interval` VM instructions.
+#[cfg(feature = "hooks")]
+fn install_progress_handler<R: Runtime>(
+    conn: &Connection,
+    connections: &Rusqlite2Connections<R>,
+    db_alias: &str,
+) {
+    let interval = connections.config.progress_interval;
+    let app = connections.app.clone();
+    let alias = db_alias.to_string();
+    let mut ticks: u64 = 0;
+
+    conn.progress_handler(
+        interval,
+        Some(move || {
+            ticks += 1;
+            let progress = Progress {
+                db_alias: alias.clone(),
+                ops: ticks.saturating_mul(interval.max(0) as u64),
+            };
+            if let Err(e) = app.emit(PROGRESS_EVENT, progress) {
+                error!("Failed to emit progress event: {}", e);
+            }
+            // Never abort from here; cancellation goes through `interrupt`.
+            false
+        }),
+    );
+}
+
+/// Whether a VFS named `name` has been registered with SQLite.
+fn vfs_registered(name: &str) -> bool {
+    let Ok(name) = std::ffi::CString::new(name) else {
+        return false;
+    };
+    unsafe { !rusqlite::ffi::sqlite3_vfs_find(name.as_ptr()).is_null() }
+}
+
 fn load_extensions(conn: &Connection, extensions: &[String]) -> Result<(), crate::Error> {
     // Load extensions
     unsafe {
@@ -61,10 +314,119 @@ fn load_extensions(conn: &Connection, extensions: &[String]) -> Result<(), crate
     }
 }
 
+/// Resolves the path part of a connection string against `app_data_dir`.
+/// Missing parent directories are created unless `create_missing_dirs` is false,
+/// in which case a missing parent directory is reported as an error.
+fn resolve_db_path<R: Runtime>(
+    app: &AppHandle<R>,
+    path_part: &str,
+    create_missing_dirs: bool,
+) -> Result<PathBuf, crate::Error> {
+    if path_part == ":memory:" {
+        return Ok(PathBuf::from(":memory:"));
+    }
+
+    let base_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| Error::Io(format!("Failed to get app_data_dir: {}", e)))?;
+    resolve_in_dir(&base_dir, path_part, create_missing_dirs)
+}
+
+/// Checks that `base_dir` is an absolute path to a directory, creating it
+/// when `create_missing_dirs` is set.
+fn validated_base_dir(base_dir: &str, create_missing_dirs: bool) -> Result<PathBuf, crate::Error> {
+    let dir = PathBuf::from(base_dir);
+    if !dir.is_absolute() {
+        return Err(Error::InvalidBaseDir(format!(
+            "{} is not an absolute path",
+            base_dir
+        )));
+    }
+    if dir.exists() {
+        if !dir.is_dir() {
+            return Err(Error::InvalidBaseDir(format!(
+                "{} is not a directory",
+                base_dir
+            )));
+        }
+    } else if create_missing_dirs {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| Error::InvalidBaseDir(format!("Failed to create {}: {}", base_dir, e)))?;
+    } else {
+        return Err(Error::InvalidBaseDir(format!(
+            "{} does not exist",
+            base_dir
+        )));
+    }
+    Ok(dir)
+}
+
+fn resolve_in_dir(
+    base_dir: &Path,
+    path_part: &str,
+    create_missing_dirs: bool,
+) -> Result<PathBuf, crate::Error> {
+    let resolved_path = base_dir.join(path_part);
+    if let Some(parent_dir) = resolved_path.parent() {
+        if create_missing_dirs {
+            std::fs::create_dir_all(parent_dir)
+                .map_err(|e| Error::Io(format!("Failed to create parent directory: {}", e)))?;
+        } else if !parent_dir.is_dir() {
+            return Err(Error::Io(format!(
+                "Parent directory does not exist: {}",
+                parent_dir.display()
+            )));
+        }
+    }
+
+    Ok(resolved_path)
+}
+
+/// Path prefix of databases bundled as Tauri resources, e.g.
+/// `sqlite::resource:data/ref.db`.
+const RESOURCE_PREFIX: &str = "resource:";
+
+/// Resolves `resource` against the app's resource directory and returns the
+/// path to open and whether to open it read-only. Bundled files are often on a
+/// read-only filesystem, so they are opened read-only in place; with
+/// `copy_to_data` the file is instead copied to the same relative path under
+/// `app_data_dir` (unless a copy already exists there) and opened read-write.
+fn resolve_resource<R: Runtime>(
+    app: &AppHandle<R>,
+    resource: &str,
+    copy_to_data: bool,
+) -> Result<(PathBuf, bool), crate::Error> {
+    let source = app
+        .path()
+        .resolve(resource, BaseDirectory::Resource)
+        .map_err(|e| Error::Io(format!("Failed to resolve resource {}: {}", resource, e)))?;
+    if !source.is_file() {
+        return Err(Error::CannotOpen(source.display().to_string()));
+    }
+    if !copy_to_data {
+        return Ok((source, true));
+    }
+
+    let dest = resolve_db_path(app, resource, true)?;
+    if !dest.exists() {
+        std::fs::copy(&source, &dest).map_err(|e| {
+            Error::Io(format!(
+                "Failed to copy {} to {}: {}",
+                source.display(),
+                dest.display(),
+                e
+            ))
+        })?;
+    }
+    Ok((dest, false))
+}
+
 #[command]
 pub(crate) fn get_conn_url<R: Runtime>(
     app: AppHandle<R>,
     db: &str,
+    create_missing_dirs: bool,
 ) -> Result<PathBuf, crate::Error> {
     let split_db_conn: Vec<&str> = db.splitn(3, ':').collect();
     let kind = split_db_conn[0];
@@ -75,20 +437,7 @@ pub(crate) fn get_conn_url<R: Runtime>(
         return Err(Error::UnsupportedDatabaseType(kind.to_string()));
     }
 
-    let path = if path_part == ":memory:" {
-        PathBuf::from(":memory:")
-    } else {
-        let base_dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| Error::Io(format!("Failed to get app_data_dir: {}", e)))?;
-        let resolved_path = base_dir.join(path_part);
-        if let Some(parent_dir) = resolved_path.parent() {
-            std::fs::create_dir_all(parent_dir)
-                .map_err(|e| Error::Io(format!("Failed to create parent directory: {}", e)))?;
-        }
-        resolved_path
-    };
+    let path = resolve_db_path(&app, path_part, create_missing_dirs)?;
 
     // Verify we can open/close a connection, but don't keep it open.
     // This checks permissions and path validity.
@@ -149,13 +498,22 @@ pub(crate) fn get_conn_url<R: Runtime>(
 }
 
 // Refactored load command
+/// Opens the database of connection string `db` and registers it with `db`
+/// as its alias.
+///
+/// An in-memory database (`sqlite::memory:`) is shared per alias: loading the
+/// same string again returns the database already loaded instead of an empty
+/// one. Use `load_in_memory_persistent` for isolated, named in-memory
+/// databases.
 #[command]
 pub(crate) fn load<R: Runtime>(
     app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
     db: &str,
     extensions: Vec<String>,
+    options: Option<LoadOptions>,
 ) -> Result<String, crate::Error> {
+    let options = options.unwrap_or_default();
     let split_db_conn: Vec<&str> = db.splitn(3, ':').collect();
     let kind = split_db_conn[0];
     let pass = split_db_conn[1];
@@ -164,35 +522,81 @@ pub(crate) fn load<R: Runtime>(
     if kind != "sqlite" {
         return Err(Error::UnsupportedDatabaseType(kind.to_string()));
     }
-
-    let path = if path_part == ":memory:" {
-        PathBuf::from(":memory:")
+    // `sqlite::memory:`, the form other SQLite drivers use, splits into an
+    // empty password and `memory:`; it is the same as `sqlite:::memory:`.
+    let path_part = if path_part == "memory:" {
+        ":memory:"
     } else {
-        let base_dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| Error::Io(format!("Failed to get app_data_dir: {}", e)))?;
-        let resolved_path = base_dir.join(path_part);
-        if let Some(parent_dir) = resolved_path.parent() {
-            std::fs::create_dir_all(parent_dir)
-                .map_err(|e| Error::Io(format!("Failed to create parent directory: {}", e)))?;
-        }
-        resolved_path
+        path_part
+    };
+    if path_part == ":memory:"
+        && lock_mutex(&connections.inner().connections.0, "ConnectionManager")?.contains_key(db)
+    {
+        // In-memory databases are shared per alias: loading one again must not
+        // replace it with an empty database.
+        return Ok(db.to_string());
+    }
+
+    let resource = path_part.strip_prefix(RESOURCE_PREFIX);
+    let create_missing = path_part == ":memory:"
+        || resource.is_none()
+            && options
+                .create_missing
+                .unwrap_or(connections.inner().config.create_missing_files);
+    // Directories are only worth creating for a file that may be created.
+    let create_dirs = create_missing && connections.inner().config.create_missing_dirs;
+    let (path, read_only) = match (resource, options.base_dir.as_deref()) {
+        (Some(resource), _) => resolve_resource(&app, resource, options.copy_to_data)?,
+        (None, Some(base_dir)) if path_part != ":memory:" => (
+            resolve_in_dir(
+                &validated_base_dir(base_dir, create_dirs)?,
+                path_part,
+                create_dirs,
+            )?,
+            false,
+        ),
+        _ => (resolve_db_path(&app, path_part, create_dirs)?, false),
     };
 
-    let db_info = DbInfo {
+    let mut db_info = DbInfo {
         path: path.clone(),
         extensions: extensions.clone(),
         pass: pass.to_string(),
+        report_progress: false,
+        watch_changes: false,
+        table_prefix: options.table_prefix,
+        create_missing,
+        vfs: options.vfs,
+        read_only,
+        synchronous: options
+            .synchronous
+            .or(connections.inner().config.synchronous),
+        busy_handler: connections.inner().config.busy_handler,
+        pragmas: validated_pragmas(&connections.inner().config.pragmas, options.pragmas)?,
+        limits: connections.inner().config.limits.clone(),
+        migration_version: None,
     };
 
+    // Checked before opening, which creates the file.
+    let created = path_part != ":memory:" && !path.exists();
+
     // Open, configure and keep the connection — this becomes the pool entry.
     // open_configured_conn validates pass, loads extensions, sets busy timeout.
-    let conn = open_configured_conn(&db_info).map_err(|e| {
+    let mut conn = open_configured_conn(&db_info).map_err(|e| {
         error!("{e:?}");
         e
     })?;
-    let conn_arc = Arc::new(Mutex::new(conn));
+    if let Some(application_id) = app.try_state::<crate::ApplicationId>() {
+        // A database without pages was just created by opening it.
+        let page_count: i64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        if page_count == 0 {
+            conn.pragma_update(None, "application_id", application_id.0)?;
+        }
+    }
+    if options.migrate_on_load {
+        db_info.migration_version = migrate_on_load(&app, connections.inner(), db, &mut conn)?;
+    }
+    let interrupt = Arc::new(conn.get_interrupt_handle());
 
     // Store DbInfo and insert the live connection into the pool.
     // If the alias was already loaded the old pool Arc is dropped here,
@@ -204,466 +608,10487 @@ pub(crate) fn load<R: Runtime>(
         }
         connection_map.insert(db.to_string(), db_info);
     }
-    connections
-        .inner()
-        .pool
-        .0
-        .lock()
-        .unwrap()
-        .insert(db.to_string(), conn_arc);
+    connections.inner().pool.insert(db, conn)?;
+    lock_mutex(&connections.inner().interrupts.0, "InterruptManager")?
+        .insert(db.to_string(), interrupt);
+
+    if created {
+        let payload = DatabaseCreated {
+            db_alias: db.to_string(),
+        };
+        if let Err(e) = app.emit(DATABASE_CREATED_EVENT, payload) {
+            error!("Failed to emit {DATABASE_CREATED_EVENT} event: {e}");
+        }
+    }
 
     Ok(db.to_string())
 }
 
-/// Allows the database connection(s) to be closed; if no database
-/// name is passed in then _all_ database connection pools will be
-/// shut down.
+/// Loads an in-memory database under `alias` that persists for as long as the
+/// alias stays loaded.
+///
+/// Unlike `sqlite::memory:`, every connection opened for this alias (including
+/// the dedicated connections used by transactions and migrations) shares the
+/// same database. The database is backed by SQLite's `memdb` VFS under a unique
+/// name and is kept alive by the alias' pooled connection until `close`.
+///
+/// Each alias gets its own database, so separate parts of an app can keep
+/// isolated scratch databases under different names. Fails if `alias` is
+/// already loaded rather than replacing another part's database.
 #[command]
-pub(crate) fn close<R: Runtime>(
+pub(crate) fn load_in_memory_persistent<R: Runtime>(
     _app: AppHandle<R>,
-    // Removed async as no async ops needed now
     connections: State<'_, Rusqlite2Connections<R>>,
-    // transactions: State<'_, TransactionManager>, // TODO: Handle open transactions?
-    db: Option<String>,
-) -> Result<bool, crate::Error> {
-    // Changed return to match old signature (bool)
-    let mut connection_map = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+    alias: &str,
+) -> Result<String, crate::Error> {
+    let db_info = DbInfo {
+        path: PathBuf::from(format!("file:/rusqlite2-{}?vfs=memdb", Uuid::new_v4())),
+        extensions: Vec::new(),
+        pass: String::new(),
+        report_progress: false,
+        watch_changes: false,
+        table_prefix: None,
+        create_missing: true,
+        vfs: None,
+        read_only: false,
+        synchronous: connections.inner().config.synchronous,
+        busy_handler: connections.inner().config.busy_handler,
+        pragmas: validated_pragmas(&connections.inner().config.pragmas, Vec::new())?,
+        limits: connections.inner().config.limits.clone(),
+        migration_version: None,
+    };
 
-    let mut pool = lock_mutex(&connections.inner().pool.0, "ConnectionManager")?;
+    let conn = open_configured_conn(&db_info)?;
 
-    let aliases_to_remove = if let Some(db_alias) = db {
-        if !connection_map.contains_key(&db_alias) {
-            // Return Ok(false) or Error? Old code returned Error::DatabaseNotLoaded.
-            // Let's stick to that for now.
-            return Err(Error::DatabaseNotLoaded(db_alias));
+    {
+        let mut connection_map =
+            lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+        if connection_map.contains_key(alias) {
+            return Err(Error::AliasAlreadyLoaded(alias.to_string()));
         }
-        vec![db_alias]
-    } else {
-        connection_map.keys().cloned().collect()
-    };
-
-    for alias in aliases_to_remove {
-        // Remove the alias from the connection manager.
-        // Note: This does not affect active transactions associated with this alias.
-        // Active transactions hold their own connection Arc and will continue until
-        // commit or rollback. The connection is closed when the Arc count drops to 0.
-        // Attempting to start *new* operations (load, execute, select, begin_transaction)
-        // with this alias will fail until it is loaded again.
-        connection_map.remove(&alias);
-        pool.remove(&alias);
+        connection_map.insert(alias.to_string(), db_info);
     }
+    lock_mutex(&connections.inner().interrupts.0, "InterruptManager")?
+        .insert(alias.to_string(), Arc::new(conn.get_interrupt_handle()));
+    connections.inner().pool.insert(alias, conn)?;
 
-    Ok(true)
+    Ok(alias.to_string())
 }
 
-// --- Transaction Commands --- Implementation ---
-
+/// Copies the database of `src_alias` to `dest_path` (resolved under
+/// `app_data_dir`) and loads the copy as `new_alias`, with the same password,
+/// extensions and options. The source stays loaded. Fails if `new_alias` is
+/// already loaded or `dest_path` exists.
+///
+/// Uses the online backup API with the `backup` feature, `VACUUM INTO`
+/// otherwise. Either way the copy reflects committed data only.
 #[command]
-pub(crate) fn begin_transaction<R: Runtime>(
-    _app: AppHandle<R>,
+pub(crate) fn clone_database<R: Runtime>(
+    app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
-    db_alias: &str,
+    src_alias: &str,
+    new_alias: &str,
+    dest_path: &str,
 ) -> Result<String, crate::Error> {
-    // Get DbInfo from ConnectionManager
-    let db_info = connections
-        .inner()
-        .connections
-        .0
-        .lock()
-        .unwrap()
-        .get(db_alias)
-        .cloned()
-        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
+    let src_info = {
+        let connection_map = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+        if connection_map.contains_key(new_alias) {
+            return Err(Error::AliasAlreadyLoaded(new_alias.to_string()));
+        }
+        connection_map
+            .get(src_alias)
+            .cloned()
+            .ok_or_else(|| Error::DatabaseNotLoaded(src_alias.to_string()))?
+    };
 
-    // Transactions always get their own dedicated connection
-    let tx_conn = open_configured_conn(&db_info)?;
+    let dest = resolve_db_path(&app, dest_path, connections.config.create_missing_dirs)?;
+    if dest.exists() {
+        return Err(Error::DestinationExists(dest.display().to_string()));
+    }
+    let dest_info = DbInfo {
+        path: dest,
+        create_missing: true,
+        read_only: false,
+        ..src_info
+    };
 
-    // Begin the transaction on the new connection
-    // Use IMMEDIATE (default behavior, allows concurrent reads until first write)
-    tx_conn
-        .execute_batch("BEGIN IMMEDIATE")
-        .map_err(Error::Rusqlite)?;
+    #[cfg(feature = "backup")]
+    let conn = {
+        let mut conn = open_configured_conn(&dest_info)?;
+        with_connection(connections.inner(), src_alias, None, |src| {
+            rusqlite::backup::Backup::new(src, &mut conn)?.run_to_completion(
+                256,
+                Duration::ZERO,
+                None,
+            )?;
+            Ok(())
+        })?;
+        conn
+    };
+    #[cfg(not(feature = "backup"))]
+    let conn = {
+        with_connection(connections.inner(), src_alias, None, |src| {
+            src.execute("VACUUM INTO ?", [dest_info.path.to_string_lossy()])?;
+            Ok(())
+        })?;
+        open_configured_conn(&dest_info)?
+    };
 
-    // Generate ID and store the new connection (wrapped in Arc<Mutex<_>>) in TransactionManager
-    let tx_id = Uuid::new_v4();
-    connections
-        .inner()
-        .transactions
-        .0
-        .lock()
-        .unwrap()
-        .insert(tx_id, Arc::new(Mutex::new(tx_conn)));
+    lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+        .insert(new_alias.to_string(), dest_info);
+    lock_mutex(&connections.inner().interrupts.0, "InterruptManager")?
+        .insert(new_alias.to_string(), Arc::new(conn.get_interrupt_handle()));
+    connections.inner().pool.insert(new_alias, conn)?;
 
-    Ok(tx_id.to_string())
+    Ok(new_alias.to_string())
 }
 
+/// Changes the SQLCipher key of `db_alias` to `new_key` with `PRAGMA rekey`,
+/// then verifies it by opening the database with the new key on a fresh
+/// connection and reading its schema. If that fails the old key is restored
+/// and the failure reported, so an interrupted rotation can't leave the
+/// database under a key nobody knows. On success every connection opened for
+/// the alias afterwards uses the new key.
+///
+/// Run it while no transaction is open on the database: their connections
+/// block the rekey. Requires one of the SQLCipher features.
 #[command]
-pub(crate) fn commit_transaction<R: Runtime>(
+pub(crate) fn rekey_verified<R: Runtime>(
     _app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
-    tx_id: &str,
+    db_alias: &str,
+    new_key: &str,
 ) -> Result<(), crate::Error> {
-    let uuid = Uuid::from_str(tx_id).map_err(|_| Error::InvalidUuid(tx_id.to_string()))?;
+    #[cfg(any(
+        feature = "sqlcipher",
+        feature = "bundled-sqlcipher",
+        feature = "bundled-sqlcipher-vendored-openssl"
+    ))]
+    {
+        let db_info = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .get(db_alias)
+            .cloned()
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
+        if display_path(&db_info) == ":memory:" || db_info.pass.is_empty() {
+            return Err(Error::EncryptionFailed(format!(
+                "{db_alias} is not an encrypted database file"
+            )));
+        }
+        if new_key.is_empty() {
+            return Err(Error::EncryptionFailed(
+                "the new key must not be empty".to_string(),
+            ));
+        }
+        let new_info = DbInfo {
+            pass: new_key.to_string(),
+            ..db_info.clone()
+        };
 
-    // Ensure correct State access
-    let maybe_conn = connections
-        .inner()
-        .transactions
-        .0
-        .lock()
-        .unwrap()
-        .remove(&uuid);
+        with_connection(connections.inner(), db_alias, None, |conn| {
+            conn.pragma_update(None, "rekey", new_key)
+                .map_err(|e| Error::EncryptionFailed(format!("rekey failed: {e}")))?;
+            let verified = open_configured_conn(&new_info).and_then(|check| {
+                check.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+                Ok(())
+            });
+            if let Err(e) = verified {
+                return Err(match conn.pragma_update(None, "rekey", &db_info.pass) {
+                    Ok(()) => Error::EncryptionFailed(format!(
+                        "the new key could not be verified, the old key was restored: {e}"
+                    )),
+                    Err(restore) => Error::EncryptionFailed(format!(
+                        "the new key could not be verified ({e}) and restoring the old key failed: {restore}"
+                    )),
+                });
+            }
 
-    match maybe_conn {
-        Some(conn_arc) => conn_arc
-            .lock()
-            .unwrap()
-            .execute_batch("COMMIT")
-            .map_err(Error::Rusqlite),
-        None => Err(Error::TransactionNotFound(tx_id.to_string())),
+            // Updated while the pooled connection is still locked, so nothing
+            // reopens it with the old key in between.
+            if let Some(db_info) =
+                lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+                    .get_mut(db_alias)
+            {
+                db_info.pass = new_key.to_string();
+            }
+            // Readers hold the old key. They are dropped only once the new key
+            // is stored, so none is reopened with the old key after the clear.
+            if let Some(pooled) =
+                lock_mutex(&connections.inner().pool.0, "ConnectionManager")?.get_mut(db_alias)
+            {
+                pooled.readers.clear();
+            }
+            Ok(())
+        })
+    }
+
+    #[cfg(not(any(
+        feature = "sqlcipher",
+        feature = "bundled-sqlcipher",
+        feature = "bundled-sqlcipher-vendored-openssl"
+    )))]
+    {
+        let _ = (connections, db_alias, new_key);
+        Err(Error::FeatureNotEnabled("sqlcipher".to_string()))
     }
 }
 
+/// Returns the options `db_alias` was loaded with.
 #[command]
-pub(crate) fn rollback_transaction<R: Runtime>(
+pub(crate) fn get_alias_config<R: Runtime>(
     _app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
-    tx_id: &str,
-) -> Result<(), crate::Error> {
-    let uuid = Uuid::from_str(tx_id).map_err(|_| Error::InvalidUuid(tx_id.to_string()))?;
-
-    // Ensure correct State access
-    let maybe_conn = connections
-        .inner()
-        .transactions
-        .0
-        .lock()
-        .unwrap()
-        .remove(&uuid);
+    db_alias: &str,
+) -> Result<AliasConfig, crate::Error> {
+    let connection_map = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+    let db_info = connection_map
+        .get(db_alias)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
 
-    match maybe_conn {
-        Some(conn_arc) => {
-            if let Err(e) = lock_mutex(&conn_arc, "ConnectionManager")?.execute_batch("ROLLBACK") {
-                log::error!("Error rolling back transaction {}: {}", tx_id, e);
-            }
-            Ok(())
-        }
-        None => Err(Error::TransactionNotFound(tx_id.to_string())),
-    }
+    Ok(AliasConfig {
+        table_prefix: db_info.table_prefix.clone(),
+        migration_version: db_info.migration_version,
+    })
 }
 
-// --- Existing Commands to be Refactored (Step 6 & 7) ---
-
-/// Execute a command against the database
+/// Returns the resolved filesystem path of `db_alias`, or `":memory:"` for
+/// in-memory databases (including those from `load_in_memory_persistent`).
 #[command]
-pub(crate) fn execute<R: Runtime>(
+pub(crate) fn get_path<R: Runtime>(
     _app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
     db_alias: &str,
-    query: &str,
-    values: Vec<JsonValue>,
-    tx_id: Option<String>,
-) -> Result<(u64, LastInsertId), crate::Error> {
-    let converted_params = convert::json_to_rusqlite_params(values)?;
-
-    if let Some(tx_id_str) = tx_id {
-        // --- transactional path: use the transaction's dedicated connection ---
-        let uuid = Uuid::from_str(&tx_id_str).map_err(|_| Error::InvalidUuid(tx_id_str.clone()))?;
-        let tx_map = lock_mutex(&connections.inner().transactions.0, "ConnectionManager")?;
-        let conn_arc = tx_map
-            .get(&uuid)
-            .cloned()
-            .ok_or_else(|| Error::TransactionNotFound(tx_id_str))?;
+) -> Result<String, crate::Error> {
+    let connection_map = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+    let db_info = connection_map
+        .get(db_alias)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
 
-        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
-        let changes = conn
-            .execute(query, rusqlite::params_from_iter(converted_params))
-            .map_err(Error::Rusqlite)?;
-        let last_id = conn.last_insert_rowid();
-        Ok((changes as u64, LastInsertId::Sqlite(last_id)))
-    } else {
-        // --- non-transactional path: use the pooled persistent connection ---
-        let conn_arc = connections.inner().get_conn(db_alias)?;
-        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
-        let changes = conn
-            .execute(query, rusqlite::params_from_iter(converted_params))
-            .map_err(Error::Rusqlite)?;
-        let last_id = conn.last_insert_rowid();
-        Ok((changes as u64, LastInsertId::Sqlite(last_id)))
-    }
+    Ok(display_path(db_info))
 }
 
+/// Returns whether writes to `db_alias` succeed, by creating a table in a
+/// savepoint that is always rolled back, so nothing is left behind. The table
+/// is created in `main` rather than `temp`, which stays writable on read-only
+/// connections. This detects databases that can only be read because of file
+/// permissions or read-only media, which `load` can't tell. Other failures,
+/// like the database being locked, are returned as errors.
 #[command]
-pub(crate) fn select<R: Runtime>(
+pub(crate) fn is_writable<R: Runtime>(
     _app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
     db_alias: &str,
-    query: &str,
-    values: Vec<JsonValue>,
-    tx_id: Option<String>,
-) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
-    let converted_params = convert::json_to_rusqlite_params(values)?;
-
-    if let Some(tx_id_str) = tx_id {
-        // --- transactional path ---
-        let uuid = Uuid::from_str(&tx_id_str).map_err(|_| Error::InvalidUuid(tx_id_str.clone()))?;
-        let tx_map = lock_mutex(&connections.inner().transactions.0, "ConnectionManager")?;
+) -> Result<bool, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.execute_batch("SAVEPOINT is_writable")?;
+        let probe = conn.execute_batch("CREATE TABLE main.__probe__ (x)");
+        conn.execute_batch("ROLLBACK TO is_writable; RELEASE is_writable")?;
+        match probe {
+            Ok(()) => Ok(true),
+            Err(e)
+                if matches!(
+                    e.sqlite_error_code(),
+                    Some(
+                        rusqlite::ErrorCode::ReadOnly
+                            | rusqlite::ErrorCode::PermissionDenied
+                            | rusqlite::ErrorCode::CannotOpen
+                    )
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    })
+}
 
-        let conn_arc = tx_map
-            .get(&uuid)
-            .cloned()
-            .ok_or_else(|| Error::TransactionNotFound(tx_id_str))?;
+/// The resolved path of `db_info`, or `":memory:"` for in-memory databases.
+pub(crate) fn display_path(db_info: &DbInfo) -> String {
+    let path = db_info.path.to_string_lossy();
+    if path == ":memory:" || (path.starts_with("file:") && path.contains("vfs=memdb")) {
+        return ":memory:".to_string();
+    }
+    path.into_owned()
+}
 
-        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
-        query_rows(&conn, query, converted_params)
-    } else {
-        // --- non-transactional path: use the pooled persistent connection ---
-        let conn_arc = connections.inner().get_conn(db_alias)?;
-        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+/// Summarizes the plugin state for diagnostics screens and bug reports: the
+/// SQLite version, every loaded alias with its path, file size and journal
+/// mode, and the open transactions with their ages.
+#[command]
+pub(crate) fn diagnostics<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+) -> Result<Diagnostics, crate::Error> {
+    let mut aliases: Vec<(String, DbInfo)> =
+        lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .iter()
+            .map(|(alias, db_info)| (alias.clone(), db_info.clone()))
+            .collect();
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
 
-        query_rows(&conn, query, converted_params)
+    let mut databases = Vec::with_capacity(aliases.len());
+    for (alias, db_info) in aliases {
+        let path = display_path(&db_info);
+        let size_bytes = if path == ":memory:" {
+            None
+        } else {
+            std::fs::metadata(&db_info.path).ok().map(|m| m.len())
+        };
+        let journal_mode = with_connection(connections.inner(), &alias, None, |conn| {
+            Ok(conn.pragma_query_value(None, "journal_mode", |row| row.get::<_, String>(0))?)
+        })
+        .ok();
+        databases.push(DatabaseDiagnostics {
+            alias,
+            path,
+            size_bytes,
+            journal_mode,
+        });
     }
+
+    let mut transactions: Vec<TransactionDiagnostics> =
+        lock_mutex(&connections.inner().transactions.2, "TransactionManager")?
+            .iter()
+            .map(|(id, started)| TransactionDiagnostics {
+                id: id.clone(),
+                age_ms: started.elapsed().as_millis() as u64,
+            })
+            .collect();
+    transactions.sort_by_key(|t| std::cmp::Reverse(t.age_ms));
+
+    let stats = &connections.inner().pool.1;
+    Ok(Diagnostics {
+        sqlite_version: rusqlite::version().to_string(),
+        databases,
+        transactions,
+        connections_created: stats.created.load(Ordering::Relaxed),
+        connections_recycled: stats.recycled.load(Ordering::Relaxed),
+    })
 }
 
-fn query_rows(
-    conn: &Connection,
-    query: &str,
-    params: Vec<Box<dyn rusqlite::ToSql>>,
-) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
-    let mut stmt = conn.prepare(query).map_err(Error::Rusqlite)?;
-    let col_names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
-    let mut rows = stmt
-        .query(rusqlite::params_from_iter(params))
-        .map_err(Error::Rusqlite)?;
+/// Allows the database connection(s) to be closed; if no database
+/// name is passed in then _all_ database connection pools will be
+/// shut down.
+#[command]
+pub(crate) fn close<R: Runtime>(
+    _app: AppHandle<R>,
+    // Removed async as no async ops needed now
+    connections: State<'_, Rusqlite2Connections<R>>,
+    // transactions: State<'_, TransactionManager>, // TODO: Handle open transactions?
+    db: Option<String>,
+) -> Result<bool, crate::Error> {
+    // Changed return to match old signature (bool)
+    close_aliases(connections.inner(), db, false).map(|_| true)
+}
 
-    let mut results = Vec::new();
-    while let Some(row) = rows.next().map_err(Error::Rusqlite)? {
-        let mut row_map = IndexMap::new();
-        for (i, col_name) in col_names.iter().enumerate() {
-            let value_ref = row.get_ref(i).map_err(Error::Rusqlite)?;
-            let value_json = convert::rusqlite_value_to_json(value_ref)?;
-            row_map.insert(col_name.clone(), value_json);
+/// Unregisters `db`, or every alias when `None`, and closes their pooled
+/// connections. Returns the number of aliases unregistered.
+///
+/// A transaction left open on a pooled connection is committed or fails the
+/// call, per `open_transaction_on_close`, instead of being rolled back by
+/// closing the connection. With `rollback_open` it is rolled back regardless
+/// of the policy, so the call can't fail halfway.
+fn close_aliases<R: Runtime>(
+    connections: &Rusqlite2Connections<R>,
+    db: Option<String>,
+    rollback_open: bool,
+) -> Result<usize, crate::Error> {
+    let aliases_to_remove = {
+        let connection_map = lock_mutex(&connections.connections.0, "ConnectionManager")?;
+        if let Some(db_alias) = db {
+            if !connection_map.contains_key(&db_alias) {
+                // Return Ok(false) or Error? Old code returned Error::DatabaseNotLoaded.
+                // Let's stick to that for now.
+                return Err(Error::DatabaseNotLoaded(db_alias));
+            }
+            vec![db_alias]
+        } else {
+            connection_map.keys().cloned().collect()
+        }
+    };
+
+    // Settle transactions left open on the pooled connections, e.g. by a raw
+    // `BEGIN`, before unregistering anything, so a failure keeps them loaded.
+    let pooled_conns: Vec<(String, Arc<Mutex<Connection>>)> = {
+        let pool = lock_mutex(&connections.pool.0, "ConnectionManager")?;
+        aliases_to_remove
+            .iter()
+            .filter_map(|alias| Some((alias.clone(), pool.get(alias)?.conn.clone())))
+            .collect()
+    };
+    for (alias, conn_arc) in pooled_conns {
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+        if !conn.is_autocommit() {
+            if rollback_open {
+                conn.execute_batch("ROLLBACK")?;
+                continue;
+            }
+            match connections.config.open_transaction_on_close {
+                OpenTransactionOnClose::Commit => conn.execute_batch("COMMIT")?,
+                OpenTransactionOnClose::Error => {
+                    return Err(Error::OpenTransaction(alias));
+                }
+            }
         }
-        results.push(row_map);
     }
-    Ok(results)
+
+    let mut closed_conns = Vec::new();
+    let mut closed = Vec::new();
+    {
+        let mut connection_map = lock_mutex(&connections.connections.0, "ConnectionManager")?;
+
+        let mut pool = lock_mutex(&connections.pool.0, "ConnectionManager")?;
+        let mut interrupts = lock_mutex(&connections.interrupts.0, "InterruptManager")?;
+
+        for alias in aliases_to_remove {
+            // Remove the alias from the connection manager.
+            // Note: This does not affect active transactions associated with this alias.
+            // Active transactions hold their own connection Arc and will continue until
+            // commit or rollback. The connection is closed when the Arc count drops to 0.
+            // Attempting to start *new* operations (load, execute, select, begin_transaction)
+            // with this alias will fail until it is loaded again.
+            if connection_map.remove(&alias).is_none() {
+                // Closed by another call meanwhile.
+                continue;
+            }
+            interrupts.remove(&alias);
+            if let Some(pooled) = pool.remove(&alias) {
+                closed_conns.push((alias.clone(), pooled.conn));
+            }
+            closed.push(alias);
+        }
+    }
+
+    lock_mutex(&connections.prepared.0, "PreparedStatementManager")?
+        .retain(|_, prepared| !closed.contains(&prepared.db_alias));
+
+    // Close outside of the manager locks; optimizing can take a while.
+    let optimize = connections.config.optimize_on_close;
+    let mut first_error = None;
+    for (alias, conn_arc) in closed_conns {
+        if let Err(e) = close_pooled_conn(&alias, conn_arc, optimize) {
+            error!("{e}");
+            first_error.get_or_insert(e);
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(closed.len()),
+    }
 }
 
-/// Execute a command against the database
-/// db is the database in sqlite:xyz.db
-/// Migrate both up and down using the migration version number
+/// Closes the live connection of `db_alias` but keeps the alias loaded with
+/// its configuration, e.g. to release the file before another process
+/// replaces it. The next call on the alias reopens the connection. Returns
+/// whether a connection was released: in-memory databases would lose their
+/// contents and keep their connection, so `false` is returned for them, as
+/// for an alias whose connection was already released. `false` is also
+/// returned when another call still holds the connection; it is closed as soon
+/// as that call finishes.
+///
+/// Unlike `close`, the alias stays registered, so it does not have to be
+/// `load`ed again. Open transactions keep their own connection.
 #[command]
-pub(crate) fn migrate<R: Runtime>(
-    app: AppHandle<R>,
+pub(crate) fn release_connection<R: Runtime>(
+    _app: AppHandle<R>,
     connections: State<'_, Rusqlite2Connections<R>>,
-    version: usize,
-    db: &str,
-) -> Result<(), crate::Error> {
+    db_alias: &str,
+) -> Result<bool, crate::Error> {
+    let released = {
+        let connection_map = lock_mutex(&connections.connections.0, "ConnectionManager")?;
+        let Some(db_info) = connection_map.get(db_alias) else {
+            return Err(Error::DatabaseNotLoaded(db_alias.to_string()));
+        };
+        if display_path(db_info) == ":memory:" {
+            return Ok(false);
+        }
+        let pooled = lock_mutex(&connections.pool.0, "ConnectionManager")?.remove(db_alias);
+        lock_mutex(&connections.interrupts.0, "InterruptManager")?.remove(db_alias);
+        pooled
+    };
+
+    match released {
+        Some(pooled) => close_pooled_conn(db_alias, pooled.conn, false),
+        None => Ok(false),
+    }
+}
+
+/// Flushes the statement cache of a pooled connection, optionally runs
+/// `PRAGMA optimize`, and closes it. Returns whether it was closed now.
+///
+/// If another thread still holds the connection, e.g. for a statement in
+/// flight, it can't be closed here: it is dropped, and so closed, when that
+/// thread releases it, and `false` is returned. That close can't be reported
+/// as an error here since it hasn't happened yet.
+fn close_pooled_conn(
+    alias: &str,
+    conn_arc: Arc<Mutex<Connection>>,
+    optimize: bool,
+) -> Result<bool, crate::Error> {
+    {
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+        conn.flush_prepared_statement_cache();
+        if optimize {
+            conn.execute_batch("PRAGMA optimize")?;
+        }
+    }
+
+    match Arc::try_unwrap(conn_arc) {
+        Ok(mutex) => mutex
+            .into_inner()
+            .map_err(|e| Error::LockPoisoned(format!("ConnectionManager: {}", e)))?
+            .close()
+            .map(|()| true)
+            .map_err(|(_, e)| {
+                Error::ConnectionFailed(
+                    alias.to_string(),
+                    format!("Failed to close connection: {}", e),
+                )
+            }),
+        Err(_) => {
+            log::warn!(
+                "Connection for '{}' is still in use; it will be closed once released.",
+                alias
+            );
+            Ok(false)
+        }
+    }
+}
+
+// --- Transaction Commands --- Implementation ---
+
+/// Begins an `IMMEDIATE` transaction on a new connection of `db_alias` and
+/// returns its id. The connection is opened from the alias' `DbInfo`, so it
+/// gets the same pragmas, limits and busy handling as the pooled one.
+#[command]
+pub(crate) fn begin_transaction<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    // Get DbInfo from ConnectionManager
     let db_info = connections
         .inner()
         .connections
         .0
         .lock()
         .unwrap()
-        .get(db)
+        .get(db_alias)
         .cloned()
-        .ok_or_else(|| Error::DatabaseNotLoaded(db.to_string()))?;
+        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
 
-    // Migrations need exclusive access, so use a fresh dedicated connection
-    let mut conn = open_configured_conn(&db_info)?;
+    // Transactions always get their own dedicated connection
+    let tx_conn = open_alias_conn(connections.inner(), db_alias, &db_info)?;
 
-    let migration_list = app.state::<Mutex<MigrationList>>();
-    let mig_list = lock_mutex(&migration_list, "MigrationManager")?;
+    #[cfg(feature = "hooks")]
+    if let Some(guard) = connections
+        .app
+        .try_state::<crate::CommitGuards>()
+        .and_then(|guards| guards.0.get(db_alias).cloned())
+    {
+        tx_conn.commit_hook(Some(move || !guard()));
+    }
 
-    let resolved_migrations = mig_list.clone().resolve();
-    let migrations = RusqliteMigrations::new(resolved_migrations);
+    // Begin the transaction on the new connection
+    // Use IMMEDIATE (default behavior, allows concurrent reads until first write)
+    tx_conn
+        .execute_batch("BEGIN IMMEDIATE")
+        .map_err(Error::from)?;
 
-    let _ = migrations.to_version(&mut conn, version);
+    register_transaction(connections.inner(), tx_conn)
+}
 
-    conn.close().map_err(|(_, e)| {
-        Error::ConnectionFailed(
-            db_info.path.display().to_string(),
-            format!("Failed to close migration connection: {}", e),
-        )
-    })?;
+/// Starts a read-only snapshot transaction and returns its identifier.
+///
+/// The dedicated connection is opened read-only and begins a deferred
+/// transaction whose snapshot is taken immediately, so it never blocks writers
+/// and, under WAL, keeps seeing the same data until committed or rolled back.
+/// The id can be used with `select`; `execute` is rejected.
+#[command]
+pub(crate) fn begin_read_transaction<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    let db_info = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+        .get(db_alias)
+        .cloned()
+        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
+    let db_info = DbInfo {
+        read_only: true,
+        ..db_info
+    };
+
+    let tx_conn = open_alias_conn(connections.inner(), db_alias, &db_info)?;
+    tx_conn.execute_batch("BEGIN DEFERRED")?;
+    // Reading the schema starts the read transaction and pins the snapshot now
+    // rather than at the first `select`.
+    tx_conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+
+    register_transaction(connections.inner(), tx_conn)
+}
+
+/// Stores a connection with an open transaction in the `TransactionManager`
+/// under a new id.
+fn register_transaction<R: Runtime>(
+    connections: &Rusqlite2Connections<R>,
+    tx_conn: Connection,
+) -> Result<String, crate::Error> {
+    // Generate ID and store the new connection (wrapped in Arc<Mutex<_>>) in TransactionManager
+    let transactions = &connections.transactions;
+    let mut tx_map = lock_mutex(&transactions.0, "ConnectionManager")?;
+    let tx_id = match connections.config.transaction_id_style {
+        TransactionIdStyle::Uuid => Uuid::new_v4().to_string(),
+        // Skip ids still in use so a wrapped-around counter never collides.
+        TransactionIdStyle::Sequential => loop {
+            let id = transactions
+                .1
+                .fetch_add(1, Ordering::Relaxed)
+                .wrapping_add(1);
+            let id = id.to_string();
+            if !tx_map.contains_key(&id) {
+                break id;
+            }
+        },
+    };
+    tx_map.insert(tx_id.clone(), Arc::new(Mutex::new(tx_conn)));
+    lock_mutex(&transactions.2, "TransactionManager")?.insert(tx_id.clone(), Instant::now());
+
+    Ok(tx_id)
+}
+
+/// Checks that `tx_id` matches the configured transaction id style and returns
+/// it in the form ids are stored in, so e.g. an uppercase or braced UUID finds
+/// its transaction.
+fn validate_tx_id<R: Runtime>(
+    connections: &Rusqlite2Connections<R>,
+    tx_id: &str,
+) -> Result<String, crate::Error> {
+    let normalized = match connections.config.transaction_id_style {
+        TransactionIdStyle::Uuid => Uuid::from_str(tx_id).ok().map(|id| id.to_string()),
+        TransactionIdStyle::Sequential => tx_id.parse::<u64>().ok().map(|id| id.to_string()),
+    };
+    normalized.ok_or_else(|| Error::InvalidUuid(tx_id.to_string()))
+}
+
+/// Returns the number of rows inserted, updated or deleted in transaction
+/// `tx_id` so far, including rows changed by triggers. Rows changed by
+/// statements later undone with `ROLLBACK TO` a savepoint are still counted.
+#[command]
+pub(crate) fn transaction_changes<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    tx_id: &str,
+) -> Result<u64, crate::Error> {
+    // The dedicated connection is opened by `begin_transaction`, so its total
+    // covers exactly the statements run in the transaction.
+    with_connection(connections.inner(), "", Some(tx_id.to_string()), |conn| {
+        Ok(conn.total_changes())
+    })
+}
+
+#[command]
+pub(crate) fn commit_transaction<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    tx_id: &str,
+) -> Result<(), crate::Error> {
+    let tx_id = &validate_tx_id(connections.inner(), tx_id)?;
+
+    let conn_arc = lock_mutex(&connections.inner().transactions.0, "ConnectionManager")?
+        .get(tx_id)
+        .cloned()
+        .ok_or_else(|| Error::TransactionNotFound(tx_id.to_string()))?;
+
+    let result = execute_with_busy_retry(
+        &*lock_mutex(&conn_arc, "ConnectionManager")?,
+        "COMMIT",
+        connections.inner().config.busy_retries,
+    );
+
+    // A commit that is still busy leaves the transaction open, so keep it
+    // registered and let the caller retry or roll back.
+    match result {
+        Err(e) if is_busy(&e) => Err(Error::from(e)),
+        result => {
+            lock_mutex(&connections.inner().transactions.0, "ConnectionManager")?.remove(tx_id);
+            lock_mutex(&connections.inner().transactions.2, "TransactionManager")?.remove(tx_id);
+            result.map_err(|e| match e.sqlite_error() {
+                Some(err) if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_COMMITHOOK => {
+                    Error::CommitVetoed(tx_id.to_string())
+                }
+                _ => Error::from(e),
+            })
+        }
+    }
+}
+
+/// Delay before the first retry of a busy statement; grows linearly per attempt.
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+fn is_busy(e: &rusqlite::Error) -> bool {
+    e.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseBusy)
+}
+
+/// Runs `sql`, retrying up to `retries` times while SQLite reports `SQLITE_BUSY`.
+fn execute_with_busy_retry(conn: &Connection, sql: &str, retries: u32) -> rusqlite::Result<()> {
+    let mut attempt = 0;
+    loop {
+        match conn.execute_batch(sql) {
+            Err(e) if is_busy(&e) && attempt < retries => {
+                attempt += 1;
+                std::thread::sleep(BUSY_RETRY_DELAY * attempt);
+            }
+            result => return result,
+        }
+    }
+}
+
+#[command]
+pub(crate) fn rollback_transaction<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    tx_id: &str,
+) -> Result<(), crate::Error> {
+    let tx_id = &validate_tx_id(connections.inner(), tx_id)?;
+
+    // Ensure correct State access
+    let maybe_conn = connections
+        .inner()
+        .transactions
+        .0
+        .lock()
+        .unwrap()
+        .remove(tx_id);
+    lock_mutex(&connections.inner().transactions.2, "TransactionManager")?.remove(tx_id);
+
+    match maybe_conn {
+        Some(conn_arc) => {
+            if let Err(e) = execute_with_busy_retry(
+                &*lock_mutex(&conn_arc, "ConnectionManager")?,
+                "ROLLBACK",
+                connections.inner().config.busy_retries,
+            ) {
+                log::error!("Error rolling back transaction {}: {}", tx_id, e);
+            }
+            Ok(())
+        }
+        None => Err(Error::TransactionNotFound(tx_id.to_string())),
+    }
+}
+
+/// Begins a transaction on each of `aliases` and groups them under one
+/// coordinating id. Each transaction gets its own id for `execute`/`select`.
+/// If any of them fails to begin, the ones already begun are rolled back.
+///
+/// This is best-effort coordination, not an atomic commit across files: see
+/// `commit_multi`.
+#[command]
+pub(crate) fn begin_multi_transaction<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    aliases: Vec<String>,
+) -> Result<MultiTransaction, crate::Error> {
+    let mut transactions = IndexMap::new();
+    for alias in aliases {
+        if transactions.contains_key(&alias) {
+            continue;
+        }
+        match begin_transaction(app.clone(), connections.clone(), &alias) {
+            Ok(tx_id) => {
+                transactions.insert(alias, tx_id);
+            }
+            Err(e) => {
+                for tx_id in transactions.values() {
+                    let _ = rollback_transaction(app.clone(), connections.clone(), tx_id);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    lock_mutex(
+        &connections.inner().multi_transactions.0,
+        "MultiTransactionManager",
+    )?
+    .insert(id.clone(), transactions.clone());
+
+    Ok(MultiTransaction { id, transactions })
+}
+
+/// Commits the transactions of multi-transaction `id` in the order their
+/// aliases were given. If a commit fails, the transactions not yet committed
+/// are rolled back and `PartialCommit` reports which aliases were already
+/// committed: those changes stay, so the databases can end up out of sync.
+#[command]
+pub(crate) fn commit_multi<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    id: &str,
+) -> Result<(), crate::Error> {
+    let transactions = lock_mutex(
+        &connections.inner().multi_transactions.0,
+        "MultiTransactionManager",
+    )?
+    .remove(id)
+    .ok_or_else(|| Error::MultiTransactionNotFound(id.to_string()))?;
+
+    let mut committed = Vec::new();
+    let mut pending = transactions.into_iter();
+    while let Some((alias, tx_id)) = pending.next() {
+        if let Err(e) = commit_transaction(app.clone(), connections.clone(), &tx_id) {
+            // A busy commit keeps its transaction open; roll it back with the rest.
+            let _ = rollback_transaction(app.clone(), connections.clone(), &tx_id);
+            for (_, tx_id) in pending {
+                let _ = rollback_transaction(app.clone(), connections.clone(), &tx_id);
+            }
+            return Err(Error::PartialCommit {
+                alias,
+                committed,
+                source: Box::new(e),
+            });
+        }
+        committed.push(alias);
+    }
+
+    Ok(())
+}
 
-    // Evict the pool connection so the next query sees the migrated schema
-    lock_mutex(&connections.inner().pool.0, "ConnectionManager")?.remove(db);
+/// Rolls back every transaction of multi-transaction `id`.
+#[command]
+pub(crate) fn rollback_multi<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    id: &str,
+) -> Result<(), crate::Error> {
+    let transactions = lock_mutex(
+        &connections.inner().multi_transactions.0,
+        "MultiTransactionManager",
+    )?
+    .remove(id)
+    .ok_or_else(|| Error::MultiTransactionNotFound(id.to_string()))?;
 
+    for tx_id in transactions.values() {
+        if let Err(e) = rollback_transaction(app.clone(), connections.clone(), tx_id) {
+            log::error!("Error rolling back transaction {}: {}", tx_id, e);
+        }
+    }
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{ConnectionManager, ConnectionPool, TransactionManager};
-    use serde_json::json;
-    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
-    use tauri::Manager;
+/// Rolls back every open transaction and clears the `TransactionManager`.
+/// This is a recovery hatch for leaked transactions and is distinct from
+/// `close`, which only unregisters database aliases.
+/// Returns the number of transactions that were rolled back.
+#[command]
+pub(crate) fn rollback_all_transactions<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+) -> Result<usize, crate::Error> {
+    let drained: Vec<_> = lock_mutex(&connections.inner().transactions.0, "TransactionManager")?
+        .drain()
+        .collect();
+    lock_mutex(&connections.inner().transactions.2, "TransactionManager")?.clear();
 
-    const MEMORY_DB_ALIAS: &str = "sqlite:::memory:";
+    let count = drained.len();
+    for (tx_id, conn_arc) in drained {
+        log::warn!("Rolling back leaked transaction {}", tx_id);
+        if let Err(e) = lock_mutex(&conn_arc, "TransactionManager")?.execute_batch("ROLLBACK") {
+            log::error!("Error rolling back transaction {}: {}", tx_id, e);
+        }
+    }
+
+    Ok(count)
+}
+
+// --- Existing Commands to be Refactored (Step 6 & 7) ---
+
+/// Returns the plugin to a clean slate, e.g. on logout: rolls back every open
+/// transaction, forgets multi-transactions, stops every `poll_watch`, then
+/// unregisters every database and closes its connection like `close` without
+/// an alias. Returns how many transactions and databases were cleared.
+///
+/// A transaction left open on a pooled connection, e.g. by a raw `BEGIN`, is
+/// rolled back whatever `openTransactionOnClose` says.
+///
+/// Each manager is locked on its own, transactions first, and connections
+/// are closed in `close`'s lock order, so `reset` can't deadlock with
+/// concurrent calls.
+#[command]
+pub(crate) fn reset<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+) -> Result<ResetReport, crate::Error> {
+    let transactions = rollback_all_transactions(app, connections.clone())?;
+    lock_mutex(
+        &connections.inner().multi_transactions.0,
+        "MultiTransactionManager",
+    )?
+    .clear();
+    // Dropping the senders stops the watch threads.
+    lock_mutex(
+        &connections.inner().background_tasks.0,
+        "BackgroundTaskManager",
+    )?
+    .clear();
+    let databases = close_aliases(connections.inner(), None, true)?;
+
+    Ok(ResetReport {
+        transactions,
+        databases,
+    })
+}
+
+/// Execute a command against the database. The result is classified by the
+/// statement's leading keyword, so e.g. DDL does not report a stale last
+/// insert id.
+///
+/// Outside a transaction, `retry_on_busy` runs the statement in its own
+/// `BEGIN IMMEDIATE`/`COMMIT` and retries the whole transaction up to
+/// `max_retries` times (default `busyRetries`) while the database is busy or
+/// locked, sleeping a little longer after each attempt. This goes beyond the
+/// connection's busy timeout, which only waits on the statement in flight.
+#[command]
+pub(crate) fn execute<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+    retry_on_busy: Option<bool>,
+    max_retries: Option<u32>,
+) -> Result<ExecuteResult, crate::Error> {
+    let config = &connections.inner().config;
+    check_sql_length(config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let run = |conn: &Connection| {
+        execute_statement(conn, query, &converted_params, config.allow_unused_params)
+    };
+
+    let result = if retry_on_busy.unwrap_or(false) && tx_id.is_none() {
+        let retries = max_retries.unwrap_or(config.busy_retries);
+        let mut attempt = 0;
+        loop {
+            let result = with_connection(connections.inner(), db_alias, None, |conn| {
+                conn.execute_batch("BEGIN IMMEDIATE")?;
+                let result = run(conn).and_then(|result| {
+                    conn.execute_batch("COMMIT")?;
+                    Ok(result)
+                });
+                if result.is_err() && !conn.is_autocommit() {
+                    if let Err(e) = conn.execute_batch("ROLLBACK") {
+                        log::error!("Error rolling back retried execute: {}", e);
+                    }
+                }
+                result
+            });
+            match result {
+                Err(Error::DatabaseLocked(_)) if attempt < retries => {
+                    attempt += 1;
+                    std::thread::sleep(BUSY_RETRY_DELAY * attempt);
+                }
+                result => break result,
+            }
+        }
+    } else {
+        with_connection(connections.inner(), db_alias, tx_id, run)
+    };
+    result.map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Runs one statement for `execute` and classifies its result.
+fn execute_statement(
+    conn: &Connection,
+    query: &str,
+    params: &[Box<dyn rusqlite::ToSql>],
+    allow_unused_params: bool,
+) -> Result<ExecuteResult, crate::Error> {
+    if conn.is_readonly(rusqlite::MAIN_DB)? {
+        return Err(Error::ReadOnlyTransaction);
+    }
+    let mut stmt = conn.prepare(query)?;
+    bind_positional(&mut stmt, params, allow_unused_params)?;
+    let readonly = stmt.readonly();
+    let rows = if is_pragma(query) {
+        // Pragmas such as `journal_mode = WAL` report their new value as a
+        // row; run them to completion and ignore it.
+        let mut rows = stmt.raw_query();
+        while rows.next()?.is_some() {}
+        0
+    } else {
+        stmt.raw_execute()? as u64
+    };
+
+    let keyword = if readonly {
+        String::new()
+    } else {
+        leading_keyword(query).to_ascii_uppercase()
+    };
+    Ok(match keyword.as_str() {
+        "CREATE" | "DROP" | "ALTER" => ExecuteResult::Ddl,
+        "INSERT" | "REPLACE" => ExecuteResult::Insert {
+            rows,
+            last_id: conn.last_insert_rowid(),
+        },
+        "UPDATE" => ExecuteResult::Update { rows },
+        "DELETE" => ExecuteResult::Delete { rows },
+        _ => ExecuteResult::Other { rows },
+    })
+}
+
+/// Runs a write statement with a `RETURNING` clause, e.g. `UPDATE ...
+/// RETURNING id` or `DELETE ... RETURNING *`, and returns its rows: one per
+/// row inserted, updated or deleted. `execute` rejects statements that return
+/// rows. Values are converted like `select`'s. Optionally runs within the
+/// transaction identified by `tx_id`.
+#[command]
+pub(crate) fn execute_returning<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        // Every row is stepped before the statement is finalized, so all of
+        // its changes are applied.
+        query_rows(conn, query, converted_params, config.column_case, config)
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Runs `ops` in order as one atomic batch for bulk loads of related tables,
+/// with `PRAGMA defer_foreign_keys` on so rows may reference rows inserted by
+/// later operations. Before committing, `PRAGMA foreign_key_check` runs: if
+/// it finds violations the batch is rolled back and they are returned, so an
+/// empty result means the batch was committed. A failing operation rolls the
+/// batch back and fails the call.
+///
+/// The check covers the whole database, so violations that existed before
+/// the batch also roll it back. Foreign key enforcement stays as configured
+/// outside the batch.
+#[command]
+pub(crate) fn with_foreign_keys_off<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    ops: Vec<Operation>,
+) -> Result<Vec<ForeignKeyViolation>, crate::Error> {
+    let config = &connections.inner().config;
+    let ops = ops
+        .into_iter()
+        .map(|op| {
+            check_sql_length(config, &op.sql)?;
+            let params_count = op.values.len();
+            Ok((
+                op.sql,
+                params_count,
+                convert::json_to_rusqlite_params(op.values)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, crate::Error>>()?;
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        conn.execute_batch("SAVEPOINT with_foreign_keys_off")?;
+        let run_batch = || {
+            conn.pragma_update(None, "defer_foreign_keys", true)?;
+            for (sql, params_count, params) in &ops {
+                let run_op = || {
+                    let mut stmt = conn.prepare(sql)?;
+                    bind_positional(&mut stmt, params, config.allow_unused_params)?;
+                    let mut rows = stmt.raw_query();
+                    while rows.next()?.is_some() {}
+                    Ok::<_, crate::Error>(())
+                };
+                run_op()
+                    .map_err(|e| e.with_query(sql, *params_count, config.include_sql_in_errors))?;
+            }
+            let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+            let violations = stmt
+                .query_map([], |row| {
+                    Ok(ForeignKeyViolation {
+                        table: row.get(0)?,
+                        rowid: row.get(1)?,
+                        parent: row.get(2)?,
+                        fkid: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, crate::Error>(violations)
+        };
+
+        match run_batch() {
+            Ok(violations) if violations.is_empty() => {
+                conn.execute_batch("RELEASE with_foreign_keys_off")?;
+                Ok(violations)
+            }
+            result => {
+                conn.execute_batch(
+                    "ROLLBACK TO with_foreign_keys_off; RELEASE with_foreign_keys_off",
+                )?;
+                result
+            }
+        }
+    })
+}
+
+/// Runs the SQL script at `src_path`, resolved under `app_data_dir`, one
+/// statement at a time as the file is read, so large dumps are never held in
+/// memory whole. The statements run under one savepoint: a failing statement
+/// rolls back everything the import did and fails the call. The dump's own
+/// `BEGIN`, `COMMIT` and `END` statements are skipped.
+///
+/// Emits `sql://import-progress` every 1000 statements and returns the number
+/// of statements executed.
+#[command]
+pub(crate) fn import_sql_file<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    src_path: &str,
+    tx_id: Option<String>,
+) -> Result<u64, crate::Error> {
+    use std::io::BufRead;
+
+    let config = &connections.inner().config;
+    let path = resolve_db_path(&app, src_path, false)?;
+    let io_error =
+        |e: std::io::Error| Error::Io(format!("Failed to read {}: {}", path.display(), e));
+    let mut reader = std::io::BufReader::new(std::fs::File::open(&path).map_err(io_error)?);
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        conn.execute_batch("SAVEPOINT import_sql_file")?;
+        let mut executed = 0u64;
+        let mut run_import = || {
+            let mut run_statement = |sql: &str| {
+                if ["BEGIN", "COMMIT", "END"]
+                    .iter()
+                    .any(|keyword| leading_keyword(sql).eq_ignore_ascii_case(keyword))
+                {
+                    return Ok(());
+                }
+                check_sql_length(config, sql)?;
+                let run = || {
+                    let mut stmt = conn.prepare(sql)?;
+                    let mut rows = stmt.raw_query();
+                    while rows.next()?.is_some() {}
+                    Ok::<_, crate::Error>(())
+                };
+                run().map_err(|e| e.with_query(sql, 0, config.include_sql_in_errors))?;
+                executed += 1;
+                if executed % IMPORT_PROGRESS_INTERVAL == 0 {
+                    let payload = ImportProgress {
+                        db_alias: db_alias.to_string(),
+                        statements: executed,
+                    };
+                    if let Err(e) = app.emit(IMPORT_PROGRESS_EVENT, payload) {
+                        error!("Failed to emit {IMPORT_PROGRESS_EVENT} event: {e}");
+                    }
+                }
+                Ok::<_, crate::Error>(())
+            };
+
+            let mut splitter = StatementSplitter::default();
+            let mut line = String::new();
+            while reader.read_line(&mut line).map_err(io_error)? > 0 {
+                for sql in splitter.push(&line) {
+                    run_statement(&sql)?;
+                }
+                line.clear();
+            }
+            for sql in splitter.finish() {
+                run_statement(&sql)?;
+            }
+            Ok::<_, crate::Error>(())
+        };
+
+        match run_import() {
+            Ok(()) => {
+                conn.execute_batch("RELEASE import_sql_file")?;
+                Ok(executed)
+            }
+            Err(e) => {
+                conn.execute_batch("ROLLBACK TO import_sql_file; RELEASE import_sql_file")?;
+                Err(e)
+            }
+        }
+    })
+}
+
+/// Runs `query` once per entry of `values`, preparing it only once.
+///
+/// By default the rows run under one savepoint, which outside a transaction
+/// is a single transaction around the whole batch: either every row is
+/// written or, when one fails, none is and the error is returned. This is
+/// atomic and much faster than committing each row.
+///
+/// With `per_row_commit` every row is committed on its own (outside a
+/// transaction), so a failure keeps the rows before it. The call then
+/// succeeds with the index and error of the failed row, and the rows after it
+/// are not run. Each commit waits for the disk according to the `synchronous`
+/// setting, so this is slower, and a crash mid-batch leaves a partial batch
+/// behind. Within transaction `tx_id` nothing is committed before the
+/// transaction is.
+#[command]
+pub(crate) fn execute_many<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<Vec<JsonValue>>,
+    tx_id: Option<String>,
+    per_row_commit: Option<bool>,
+) -> Result<ExecuteManyResult, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let converted_rows = values
+        .into_iter()
+        .map(convert::json_to_rusqlite_params)
+        .collect::<Result<Vec<_>, _>>()?;
+    // Errors that don't come from a row, e.g. preparing `query`, report the
+    // size of the first row.
+    let params_count = converted_rows.first().map_or(0, Vec::len);
+    let per_row_commit = per_row_commit.unwrap_or(false);
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        let mut stmt = conn.prepare(query)?;
+        let mut run_row = |params: &[Box<dyn rusqlite::ToSql>]| {
+            bind_positional(&mut stmt, params, config.allow_unused_params)
+                .and_then(|()| Ok(stmt.raw_execute()? as u64))
+                .map_err(|e| e.with_query(query, params.len(), config.include_sql_in_errors))
+        };
+
+        let mut rows = 0;
+        if per_row_commit {
+            for (index, params) in converted_rows.iter().enumerate() {
+                match run_row(params) {
+                    Ok(changed) => rows += changed,
+                    Err(e) => {
+                        return Ok(ExecuteManyResult {
+                            rows,
+                            failed_row: Some(index),
+                            error: Some(e.to_string()),
+                        })
+                    }
+                }
+            }
+        } else {
+            conn.execute_batch("SAVEPOINT execute_many")?;
+            for params in &converted_rows {
+                match run_row(params) {
+                    Ok(changed) => rows += changed,
+                    Err(e) => {
+                        if let Err(rollback) =
+                            conn.execute_batch("ROLLBACK TO execute_many; RELEASE execute_many")
+                        {
+                            log::error!("Error rolling back execute_many: {}", rollback);
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+            conn.execute_batch("RELEASE execute_many")?;
+        }
+        Ok(ExecuteManyResult {
+            rows,
+            failed_row: None,
+            error: None,
+        })
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Whether `query` is a `PRAGMA` statement.
+fn is_pragma(query: &str) -> bool {
+    leading_keyword(query).eq_ignore_ascii_case("PRAGMA")
+}
+
+/// The first word of `query`, e.g. `INSERT` for `INSERT INTO ...`.
+fn leading_keyword(query: &str) -> &str {
+    let query = query.trim_start();
+    let end = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    &query[..end]
+}
+
+/// Reads pragma `name` and returns its rows, e.g. `[{ "journal_mode": "wal" }]`
+/// for `journal_mode` or one row per column for `table_info`-style pragmas
+/// that take no argument. Use `execute` to set a pragma.
+#[command]
+pub(crate) fn pragma<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    name: &str,
+    tx_id: Option<String>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let query = format!("PRAGMA {}", quote_identifier(name)?);
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        query_rows(conn, &query, Vec::new(), config.column_case, config)
+    })
+}
+
+/// Lists the indexes of `table`, each as `{ name, unique, origin, partial,
+/// columns }`. `origin` is `"c"` for `CREATE INDEX`, `"u"` for a `UNIQUE`
+/// constraint and `"pk"` for a primary key; `columns` lists the indexed
+/// columns in key order, with `null` for an expression. Fails with
+/// `TableNotFound` if the table does not exist.
+#[command]
+pub(crate) fn indexes<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    tx_id: Option<String>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let list_query = format!("PRAGMA index_list({})", quote_identifier(table)?);
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+            [table],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(Error::TableNotFound(table.to_string()));
+        }
+
+        let mut list = conn.prepare(&list_query)?;
+        let listed = list
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>("name")?,
+                    row.get::<_, bool>("unique")?,
+                    row.get::<_, String>("origin")?,
+                    row.get::<_, bool>("partial")?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut result = Vec::with_capacity(listed.len());
+        for (name, unique, origin, partial) in listed {
+            // Index names come from the schema, so they are quoted rather than
+            // validated as plain identifiers.
+            let mut info = conn.prepare(&format!(
+                "PRAGMA index_info(\"{}\")",
+                name.replace('"', "\"\"")
+            ))?;
+            let columns = info
+                .query_map([], |row| row.get::<_, Option<String>>("name"))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut index = IndexMap::new();
+            index.insert("name".to_string(), JsonValue::String(name));
+            index.insert("unique".to_string(), JsonValue::Bool(unique));
+            index.insert("origin".to_string(), JsonValue::String(origin));
+            index.insert("partial".to_string(), JsonValue::Bool(partial));
+            index.insert(
+                "columns".to_string(),
+                JsonValue::Array(
+                    columns
+                        .into_iter()
+                        .map(|c| c.map_or(JsonValue::Null, JsonValue::String))
+                        .collect(),
+                ),
+            );
+            result.push(index);
+        }
+        Ok(result)
+    })
+}
+
+/// Runs `f` on the dedicated connection of transaction `tx_id` when given,
+/// otherwise on the pooled connection of `db_alias`.
+fn with_connection<R: Runtime, T>(
+    connections: &Rusqlite2Connections<R>,
+    db_alias: &str,
+    tx_id: Option<String>,
+    f: impl FnOnce(&Connection) -> Result<T, crate::Error>,
+) -> Result<T, crate::Error> {
+    let conn_arc = if let Some(tx_id_str) = tx_id {
+        // --- transactional path: use the transaction's dedicated connection ---
+        let tx_id_str = validate_tx_id(connections, &tx_id_str)?;
+        lock_mutex(&connections.transactions.0, "ConnectionManager")?
+            .get(&tx_id_str)
+            .cloned()
+            .ok_or_else(|| Error::TransactionNotFound(tx_id_str))?
+    } else {
+        // --- non-transactional path: use the pooled persistent connection ---
+        connections.get_conn(db_alias)?
+    };
+
+    let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    f(&conn)
+}
+
+/// Like `with_connection`, but without a transaction runs `query` on a reader
+/// connection of `db_alias` when `reader_connections` is set, so it does not
+/// wait for writes. Statements that write, e.g. `INSERT ... RETURNING`, or
+/// that the reader cannot prepare, e.g. over the writer's temp tables, still
+/// run on the pooled connection.
+fn with_read_connection<R: Runtime, T>(
+    connections: &Rusqlite2Connections<R>,
+    db_alias: &str,
+    tx_id: Option<String>,
+    query: &str,
+    f: impl FnOnce(&Connection) -> Result<T, crate::Error>,
+) -> Result<T, crate::Error> {
+    if tx_id.is_none() {
+        if let Some(reader) = connections.get_reader(db_alias)? {
+            let conn = lock_mutex(&reader, "ConnectionManager")?;
+            if conn.prepare_cached(query).is_ok_and(|stmt| stmt.readonly()) {
+                return f(&conn);
+            }
+        }
+    }
+    with_connection(connections, db_alias, tx_id, f)
+}
+
+/// Runs a query and returns its rows as column-name to value maps. With
+/// `skip_nulls`, NULL columns are left out of each row instead of being present
+/// as `null`, so rows may not all have the same keys.
+///
+/// Text values of the columns named in `json_columns` (after `column_case` is
+/// applied) are parsed and embedded as JSON. A value that is not valid JSON is
+/// kept as a string, or fails the query with `strict_json_columns`.
+///
+/// `limit` and `offset` page through the result without formatting numbers
+/// into the SQL: they are appended as a bound `LIMIT ? OFFSET ?`. The query
+/// must not have a `LIMIT` clause of its own then.
+///
+/// Without a transaction, read-only queries run on a reader connection when
+/// `reader_connections` is set.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn select<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+    skip_nulls: Option<bool>,
+    json_columns: Option<Vec<String>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let mut converted_params = convert::json_to_rusqlite_params(values)?;
+
+    let mut rows = with_read_connection(connections.inner(), db_alias, tx_id, query, |conn| {
+        let query = if limit.is_some() || offset.is_some() {
+            paginate(
+                conn,
+                query,
+                &mut converted_params,
+                limit,
+                offset,
+                connections.inner().config.allow_unused_params,
+            )?
+        } else {
+            query.to_string()
+        };
+        query_rows(
+            conn,
+            &query,
+            converted_params,
+            connections.inner().config.column_case,
+            &connections.inner().config,
+        )
+    })
+    .map_err(|e| {
+        e.with_query(
+            query,
+            params_count,
+            connections.inner().config.include_sql_in_errors,
+        )
+    })?;
+
+    if let Some(json_columns) = json_columns {
+        let strict = connections.inner().config.strict_json_columns;
+        for row in &mut rows {
+            parse_json_columns(row, &json_columns, strict)?;
+        }
+    }
+    if skip_nulls.unwrap_or(false) {
+        for row in &mut rows {
+            row.retain(|_, value| !value.is_null());
+        }
+    }
+    Ok(rows)
+}
+
+/// Appends a bound `LIMIT ? OFFSET ?` to `query` and pushes their values onto
+/// `params`. An `offset` without a `limit` uses `LIMIT -1`, i.e. no limit.
+///
+/// The query is prepared on its own first so its parameters are checked as
+/// usual and the new placeholders take the indexes right after them, numbered
+/// `?NNN` when the query uses numbered placeholders.
+fn paginate(
+    conn: &Connection,
+    query: &str,
+    params: &mut Vec<Box<dyn rusqlite::ToSql>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    allow_unused: bool,
+) -> Result<String, crate::Error> {
+    let query = query.trim_end().trim_end_matches(';').trim_end();
+    if has_limit_clause(query) {
+        return Err(Error::LimitAlreadyPresent);
+    }
+    let mut stmt = conn.prepare(query)?;
+    bind_positional(&mut stmt, params, allow_unused)?;
+    let count = stmt.parameter_count();
+    let numbered = (1..=count).any(|i| stmt.parameter_name(i).is_some_and(|n| n.starts_with('?')));
+    params.truncate(count);
+    params.push(Box::new(limit.map_or(-1, i64::from)));
+    params.push(Box::new(i64::from(offset.unwrap_or(0))));
+
+    Ok(if numbered {
+        format!("{} LIMIT ?{} OFFSET ?{}", query, count + 1, count + 2)
+    } else {
+        format!("{} LIMIT ? OFFSET ?", query)
+    })
+}
+
+/// Whether `query` has a `LIMIT` clause outside of parentheses, string
+/// literals, quoted identifiers and comments, i.e. one applying to the whole
+/// result rather than to a subquery.
+fn has_limit_clause(query: &str) -> bool {
+    let bytes = query.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'[' => {
+                while i < bytes.len() && bytes[i] != b']' {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !bytes[i..].starts_with(b"*/") {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if depth == 0 && query[start..i].eq_ignore_ascii_case("LIMIT") {
+                    return true;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Rejects `sql` longer than `max_sql_length` before SQLite parses it.
+fn check_sql_length(config: &PluginConfig, sql: &str) -> Result<(), crate::Error> {
+    match config.max_sql_length {
+        Some(max) if sql.len() > max => Err(Error::SqlTooLarge {
+            length: sql.len(),
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Counts the statements of a script without preparing them, splitting it
+/// like `StatementSplitter`. Empty statements are not counted.
+fn statement_count(sql: &str) -> usize {
+    let mut splitter = StatementSplitter::default();
+    let mut statements = splitter.push(sql);
+    statements.extend(splitter.finish());
+    statements.len()
+}
+
+/// Splits SQL text into statements as it is fed, without preparing them: `;`
+/// outside string literals, quoted identifiers and comments ends a statement,
+/// except inside the body of a `CREATE TRIGGER`, which ends with `END;`.
+/// Statements are returned from their first token through their `;`; empty
+/// statements are skipped.
+#[derive(Default)]
+struct StatementSplitter {
+    buf: String,
+    /// Bytes of `buf` already scanned.
+    pos: usize,
+    /// Start of the current statement in `buf`, once it has a token.
+    start: Option<usize>,
+    /// Closing quote of the literal or identifier being scanned.
+    quote: Option<u8>,
+    block_comment: bool,
+    /// Tokens of the current statement, and where its trigger body stands.
+    tokens: usize,
+    create: bool,
+    trigger: bool,
+    in_trigger_body: bool,
+    after_end: bool,
+}
+
+impl StatementSplitter {
+    /// Appends `text` and returns the statements it completes.
+    fn push(&mut self, text: &str) -> Vec<String> {
+        self.buf.push_str(text);
+        // No token spans a line break, so scanning up to the last one never
+        // cuts a token in two; literals and block comments carry over.
+        match self.buf.rfind('\n') {
+            Some(end) => self.scan(end + 1),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the statements left at the end of the input, the last one
+    /// possibly without a `;`.
+    fn finish(mut self) -> Vec<String> {
+        let mut statements = self.scan(self.buf.len());
+        if let Some(start) = self.start {
+            statements.push(self.buf[start..].to_string());
+        }
+        statements
+    }
+
+    fn scan(&mut self, end: usize) -> Vec<String> {
+        let bytes = self.buf.as_bytes();
+        let mut statements = Vec::new();
+        let mut i = self.pos;
+        while i < end {
+            if let Some(quote) = self.quote {
+                if bytes[i] == quote {
+                    self.quote = None;
+                }
+                i += 1;
+                continue;
+            }
+            if self.block_comment {
+                if bytes[i..end].starts_with(b"*/") {
+                    self.block_comment = false;
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+            match bytes[i] {
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    while i < end && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    self.block_comment = true;
+                    i += 2;
+                    continue;
+                }
+                b';' if self.tokens > 0 && (!self.in_trigger_body || self.after_end) => {
+                    if let Some(start) = self.start.take() {
+                        statements.push(self.buf[start..=i].to_string());
+                    }
+                    self.tokens = 0;
+                    self.create = false;
+                    self.trigger = false;
+                    self.in_trigger_body = false;
+                    self.after_end = false;
+                }
+                // An empty statement, or the end of a statement in a trigger body.
+                b';' => self.after_end = false,
+                c if c.is_ascii_whitespace() => {}
+                c => {
+                    self.start.get_or_insert(i);
+                    if c.is_ascii_alphabetic() || c == b'_' {
+                        let word_start = i;
+                        while i < end && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                            i += 1;
+                        }
+                        let word = &self.buf[word_start..i];
+                        if self.tokens == 0 {
+                            self.create = word.eq_ignore_ascii_case("CREATE");
+                        } else if self.create
+                            && self.tokens <= 2
+                            && word.eq_ignore_ascii_case("TRIGGER")
+                        {
+                            // `CREATE [TEMP] TRIGGER`
+                            self.trigger = true;
+                        } else if self.trigger && word.eq_ignore_ascii_case("BEGIN") {
+                            self.in_trigger_body = true;
+                        }
+                        self.tokens += 1;
+                        self.after_end = word.eq_ignore_ascii_case("END");
+                        continue;
+                    }
+                    self.quote = match c {
+                        b'\'' | b'"' | b'`' => Some(c),
+                        b'[' => Some(b']'),
+                        _ => None,
+                    };
+                    self.tokens += 1;
+                    self.after_end = false;
+                }
+            }
+            i += 1;
+        }
+
+        // Drop what was returned, and comments and whitespace between statements.
+        let consumed = self.start.unwrap_or(i);
+        self.buf.drain(..consumed);
+        self.pos = i - consumed;
+        if let Some(start) = self.start.as_mut() {
+            *start = 0;
+        }
+        statements
+    }
+}
+
+/// Replaces the string values of `columns` in `row` with the JSON they contain.
+fn parse_json_columns(
+    row: &mut IndexMap<String, JsonValue>,
+    columns: &[String],
+    strict: bool,
+) -> Result<(), crate::Error> {
+    for column in columns {
+        let Some(value) = row.get_mut(column) else {
+            continue;
+        };
+        let JsonValue::String(text) = value else {
+            continue;
+        };
+        match serde_json::from_str(text) {
+            Ok(parsed) => *value = parsed,
+            Err(e) if strict => {
+                return Err(Error::ValueConversionError(format!(
+                    "column \"{}\" is not valid JSON: {}",
+                    column, e
+                )))
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Like `select`, but also returns the table and table column each result
+/// column comes from, to tell apart same-named columns of a join. Rows are
+/// returned as arrays in column order, so such columns don't overwrite each
+/// other.
+///
+/// The origin is read with SQLite's column metadata API, which needs the
+/// `column_metadata` feature. Without it, the query still runs and every
+/// origin is `None`.
+#[command]
+pub(crate) fn select_with_origin<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<OriginResult, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        let mut stmt = conn.prepare(query)?;
+        let names = convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+        let columns = column_origins(&stmt, names);
+        let formats = column_formats(&stmt, config);
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+
+        let mut rows = Vec::new();
+        let mut raw_rows = stmt.raw_query();
+        while let Some(row) = raw_rows.next()? {
+            let mut values = Vec::with_capacity(columns.len());
+            for (i, column) in columns.iter().enumerate() {
+                let column_format = formats.get(i).copied().unwrap_or_default();
+                values.push(column_value_to_json(
+                    row,
+                    i,
+                    &column.name,
+                    column_format,
+                    config.value_format(),
+                )?);
+            }
+            rows.push(values);
+        }
+        Ok(OriginResult { columns, rows })
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Pairs the result column `names` of `stmt` with their origin.
+#[cfg(feature = "column_metadata")]
+fn column_origins(stmt: &rusqlite::Statement<'_>, names: Vec<String>) -> Vec<ColumnOrigin> {
+    names
+        .into_iter()
+        .zip(stmt.columns_with_metadata())
+        .map(|(name, metadata)| ColumnOrigin {
+            name,
+            database_name: metadata.database_name().map(String::from),
+            table_name: metadata.table_name().map(String::from),
+            origin_name: metadata.origin_name().map(String::from),
+        })
+        .collect()
+}
+
+/// Without the `column_metadata` feature SQLite exposes no column origin.
+#[cfg(not(feature = "column_metadata"))]
+fn column_origins(_stmt: &rusqlite::Statement<'_>, names: Vec<String>) -> Vec<ColumnOrigin> {
+    names
+        .into_iter()
+        .map(|name| ColumnOrigin {
+            name,
+            database_name: None,
+            table_name: None,
+            origin_name: None,
+        })
+        .collect()
+}
+
+/// Runs every statement of a multi-statement script and returns one result set
+/// per statement, in order. Statements that return no rows (e.g. `INSERT`)
+/// yield an empty result set.
+///
+/// The script is split with SQLite's own parser, one prepared statement at a
+/// time, so semicolons inside string literals, quoted identifiers and comments
+/// do not split statements. `values_per_statement[i]` is bound to the i-th
+/// statement; statements without an entry are run without parameters.
+///
+/// Fails before running anything if the script has more statements than
+/// `max_statements_per_batch`, or fewer statements than parameter lists.
+#[command]
+pub(crate) fn select_multi<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    sql: &str,
+    values_per_statement: Vec<Vec<JsonValue>>,
+    tx_id: Option<String>,
+) -> Result<Vec<Vec<IndexMap<String, JsonValue>>>, crate::Error> {
+    use rusqlite::fallible_iterator::FallibleIterator;
+
+    let config = &connections.inner().config;
+    check_sql_length(config, sql)?;
+    let count = statement_count(sql);
+    if let Some(max) = config.max_statements_per_batch {
+        if count > max {
+            return Err(Error::TooManyStatements { count, max });
+        }
+    }
+    if values_per_statement.len() > count {
+        return Err(Error::ParameterMismatch(format!(
+            "{} parameter lists given for a script of {} statements",
+            values_per_statement.len(),
+            count
+        )));
+    }
+
+    let params_count = values_per_statement.iter().map(Vec::len).sum();
+    let mut converted_params = values_per_statement
+        .into_iter()
+        .map(convert::json_to_rusqlite_params)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        let mut batch = rusqlite::Batch::new(conn, sql);
+        let mut result_sets = Vec::new();
+        while let Some(mut stmt) = batch.next()? {
+            let col_names =
+                convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+            let formats = column_formats(&stmt, config);
+            let params = converted_params.next().unwrap_or_default();
+            bind_positional(&mut stmt, &params, config.allow_unused_params)?;
+            result_sets.push(rows_to_maps(
+                &col_names,
+                &formats,
+                stmt.raw_query(),
+                config.value_format(),
+            )?);
+        }
+        Ok(result_sets)
+    })
+    .map_err(|e| e.with_query(sql, params_count, config.include_sql_in_errors))
+}
+
+/// Like `execute`, but runs the statement on the async runtime's blocking
+/// thread pool, so a long-running statement does not hold up other commands.
+#[command]
+pub(crate) async fn execute_async<R: Runtime>(
+    app: AppHandle<R>,
+    db_alias: String,
+    query: String,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<ExecuteResult, crate::Error> {
+    run_blocking(move || {
+        execute(
+            app.clone(),
+            app.state(),
+            &db_alias,
+            &query,
+            values,
+            tx_id,
+            None,
+            None,
+        )
+    })
+    .await
+}
+
+/// Like `select`, but runs the query on the async runtime's blocking thread
+/// pool, so a heavy query does not hold up other commands.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn select_async<R: Runtime>(
+    app: AppHandle<R>,
+    db_alias: String,
+    query: String,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+    skip_nulls: Option<bool>,
+    json_columns: Option<Vec<String>>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    run_blocking(move || {
+        select(
+            app.clone(),
+            app.state(),
+            &db_alias,
+            &query,
+            values,
+            tx_id,
+            skip_nulls,
+            json_columns,
+            limit,
+            offset,
+        )
+    })
+    .await
+}
+
+/// Runs `f` on the blocking thread pool of the Tauri async runtime.
+/// Statements on the same connection still run one at a time.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, crate::Error> + Send + 'static,
+) -> Result<T, crate::Error> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Io(format!("Blocking task failed: {}", e)))?
+}
+
+/// Deletes the rows of `table` whose `id_column` is in `ids` and returns the
+/// number of rows deleted. An empty `ids` list deletes nothing and runs no SQL.
+/// Optionally runs within the transaction identified by `tx_id`.
+///
+/// Ids beyond the connection's limit on bound parameters are deleted in
+/// batches under one savepoint, so either every batch is applied or none is.
+#[command]
+pub(crate) fn delete_by_ids<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    id_column: &str,
+    ids: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<u64, crate::Error> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let table = quote_identifier(table)?;
+    let id_column = quote_identifier(id_column)?;
+    let converted_params = convert::json_to_rusqlite_params(ids)?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        let batch_size = variable_limit(conn)?;
+        conn.execute_batch("SAVEPOINT delete_by_ids")?;
+        let mut changes = 0;
+        for batch in converted_params.chunks(batch_size) {
+            let query = format!(
+                "DELETE FROM {} WHERE {} IN ({})",
+                table,
+                id_column,
+                vec!["?"; batch.len()].join(", ")
+            );
+            match conn.execute(&query, rusqlite::params_from_iter(batch)) {
+                Ok(changed) => changes += changed as u64,
+                Err(e) => {
+                    if let Err(rollback) =
+                        conn.execute_batch("ROLLBACK TO delete_by_ids; RELEASE delete_by_ids")
+                    {
+                        log::error!("Error rolling back delete_by_ids: {}", rollback);
+                    }
+                    return Err(Error::from(e).with_query(
+                        &query,
+                        batch.len(),
+                        config.include_sql_in_errors,
+                    ));
+                }
+            }
+        }
+        conn.execute_batch("RELEASE delete_by_ids")?;
+        Ok(changes)
+    })
+}
+
+/// The most parameters a statement on `conn` may bind. Without the `limits`
+/// feature this is 999, SQLite's lowest default.
+fn variable_limit(conn: &Connection) -> Result<usize, crate::Error> {
+    #[cfg(feature = "limits")]
+    {
+        let limit = conn.limit(rusqlite::limits::Limit::SQLITE_LIMIT_VARIABLE_NUMBER)?;
+        Ok(limit.max(1) as usize)
+    }
+
+    #[cfg(not(feature = "limits"))]
+    {
+        let _ = conn;
+        Ok(999)
+    }
+}
+
+/// Inserts a row into `table` from `values`, a map of column names to values,
+/// and returns its rowid. The statement is built as `INSERT INTO "table"
+/// ("col1", "col2") VALUES (?, ?)` with the names validated and quoted and the
+/// values bound in the map's order; an empty map inserts `DEFAULT VALUES`.
+///
+/// `on_conflict` makes it an `INSERT OR IGNORE` or `INSERT OR REPLACE`.
+/// Returns `None` if the row was ignored. Optionally runs within the
+/// transaction identified by `tx_id`.
+#[command]
+pub(crate) fn insert<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    values: IndexMap<String, JsonValue>,
+    on_conflict: Option<OnConflict>,
+    tx_id: Option<String>,
+) -> Result<Option<i64>, crate::Error> {
+    let verb = match on_conflict {
+        None => "INSERT",
+        Some(OnConflict::Ignore) => "INSERT OR IGNORE",
+        Some(OnConflict::Replace) => "INSERT OR REPLACE",
+    };
+    let query = if values.is_empty() {
+        format!("{} INTO {} DEFAULT VALUES", verb, quote_identifier(table)?)
+    } else {
+        format!(
+            "{} INTO {} ({}) VALUES ({})",
+            verb,
+            quote_identifier(table)?,
+            values
+                .keys()
+                .map(|column| quote_identifier(column))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", "),
+            vec!["?"; values.len()].join(", ")
+        )
+    };
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values.into_values().collect())?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        let changes = conn.execute(&query, rusqlite::params_from_iter(converted_params))?;
+        Ok((changes > 0).then(|| conn.last_insert_rowid()))
+    })
+    .map_err(|e| e.with_query(&query, params_count, config.include_sql_in_errors))
+}
+
+/// Updates the rows of `table` matching `where_eq` with `set` and returns the
+/// number of rows updated. Both are maps of column names to values; the
+/// statement is built as `UPDATE "table" SET "a" = ?, "b" = ? WHERE "x" = ?
+/// AND "y" = ?` with the names validated and quoted, binding the `set` values
+/// and then the `where_eq` values. As with `=`, a NULL in `where_eq` matches
+/// no row. An empty `set` updates nothing and runs no SQL.
+///
+/// An empty `where_eq` would update the whole table, so it fails with
+/// `FullTableUpdate` unless `allow_full_table` is set. Optionally runs within
+/// the transaction identified by `tx_id`.
+#[command]
+pub(crate) fn update<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    set: IndexMap<String, JsonValue>,
+    where_eq: IndexMap<String, JsonValue>,
+    allow_full_table: Option<bool>,
+    tx_id: Option<String>,
+) -> Result<u64, crate::Error> {
+    let assignments = |columns: &IndexMap<String, JsonValue>, separator: &str| {
+        Ok::<_, crate::Error>(
+            columns
+                .keys()
+                .map(|column| Ok(format!("{} = ?", quote_identifier(column)?)))
+                .collect::<Result<Vec<_>, crate::Error>>()?
+                .join(separator),
+        )
+    };
+    let mut query = format!(
+        "UPDATE {} SET {}",
+        quote_identifier(table)?,
+        assignments(&set, ", ")?
+    );
+    if where_eq.is_empty() {
+        if !allow_full_table.unwrap_or(false) {
+            return Err(Error::FullTableUpdate(table.to_string()));
+        }
+    } else {
+        query.push_str(" WHERE ");
+        query.push_str(&assignments(&where_eq, " AND ")?);
+    }
+    if set.is_empty() {
+        return Ok(0);
+    }
+    let params_count = set.len() + where_eq.len();
+    let converted_params = convert::json_to_rusqlite_params(
+        set.into_values().chain(where_eq.into_values()).collect(),
+    )?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        let changes = conn.execute(&query, rusqlite::params_from_iter(converted_params))?;
+        Ok(changes as u64)
+    })
+    .map_err(|e| e.with_query(&query, params_count, config.include_sql_in_errors))
+}
+
+/// Runs a query like `select` but returns its rows as one JSON array string,
+/// written row by row without building a map per row first. For large results
+/// this skips the intermediate rows and their second serialization for IPC;
+/// the frontend parses the string once with `JSON.parse`. Values are
+/// formatted like `select`'s, including on a reader connection.
+#[command]
+pub(crate) fn select_json<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<String, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, query, |conn| {
+        let mut stmt = conn.prepare(query)?;
+        let col_names = convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+        let formats = column_formats(&stmt, config);
+        // Keys are escaped once rather than for every row.
+        let keys: Vec<String> = col_names
+            .iter()
+            .map(|name| JsonValue::from(name.as_str()).to_string())
+            .collect();
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+
+        let mut json = String::from("[");
+        let mut rows = stmt.raw_query();
+        while let Some(row) = rows.next()? {
+            if json.len() > 1 {
+                json.push(',');
+            }
+            json.push('{');
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                json.push_str(key);
+                json.push(':');
+                let column_format = formats.get(i).copied().unwrap_or_default();
+                let value = column_value_to_json(
+                    row,
+                    i,
+                    &col_names[i],
+                    column_format,
+                    config.value_format(),
+                )?;
+                // Writing to a `String` can't fail.
+                let _ = write!(json, "{value}");
+            }
+            json.push('}');
+        }
+        json.push(']');
+        Ok(json)
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Runs `query` like `select`, but returns the values grouped by column
+/// rather than by row: `data` maps each of `columns` to its values in row
+/// order, the shape charting libraries expect for series. A query without
+/// rows returns every column with no values.
+#[command]
+pub(crate) fn select_columnar<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<ColumnarResult, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, query, |conn| {
+        let mut stmt = conn.prepare(query)?;
+        let columns = convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+        let formats = column_formats(&stmt, config);
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+
+        let mut series: Vec<Vec<JsonValue>> = vec![Vec::new(); columns.len()];
+        let mut rows = stmt.raw_query();
+        while let Some(row) = rows.next()? {
+            for (i, values) in series.iter_mut().enumerate() {
+                let column_format = formats.get(i).copied().unwrap_or_default();
+                values.push(column_value_to_json(
+                    row,
+                    i,
+                    &columns[i],
+                    column_format,
+                    config.value_format(),
+                )?);
+            }
+        }
+        let data = columns.iter().cloned().zip(series).collect();
+        Ok(ColumnarResult { columns, data })
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Runs a query and returns its first row, or `None` if it has none. Rows
+/// after the first are never read, so the query stops as soon as one row is
+/// found. Runs like `select`, including on a reader connection.
+#[command]
+pub(crate) fn select_one<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<Option<IndexMap<String, JsonValue>>, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, query, |conn| {
+        let mut stmt = conn.prepare(query)?;
+        let col_names = convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+        let formats = column_formats(&stmt, config);
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+        let mut rows = stmt.raw_query();
+        match rows.next()? {
+            Some(row) => Ok(Some(row_to_map(
+                &col_names,
+                &formats,
+                row,
+                config.value_format(),
+            )?)),
+            None => Ok(None),
+        }
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Returns whether `table` has a row matching `where_clause`, bound with
+/// `values`, using `SELECT EXISTS(...)` so SQLite stops at the first match.
+/// An empty `where_clause` checks whether the table has any row.
+///
+/// `table` is validated and quoted, but `where_clause` is raw SQL: build it
+/// from placeholders and pass user input only in `values`.
+#[command]
+pub(crate) fn exists_where<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    where_clause: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<bool, crate::Error> {
+    let query = if where_clause.trim().is_empty() {
+        format!("SELECT EXISTS(SELECT 1 FROM {})", quote_identifier(table)?)
+    } else {
+        format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE {})",
+            quote_identifier(table)?,
+            where_clause
+        )
+    };
+    check_sql_length(&connections.inner().config, &query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, &query, |conn| {
+        let mut stmt = conn.prepare(&query)?;
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+        let mut rows = stmt.raw_query();
+        match rows.next()? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(false),
+        }
+    })
+    .map_err(|e| e.with_query(&query, params_count, config.include_sql_in_errors))
+}
+
+/// Returns the length of `column` in the row of `table` with `rowid`, in bytes
+/// for a BLOB (characters for TEXT), using `length()` so the value itself is
+/// not sent back, e.g. to list attachment sizes. `None` if the row doesn't
+/// exist or the value is NULL.
+#[command]
+pub(crate) fn blob_size<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    column: &str,
+    rowid: i64,
+    tx_id: Option<String>,
+) -> Result<Option<i64>, crate::Error> {
+    let query = format!(
+        "SELECT length({}) FROM {} WHERE rowid = ?1",
+        quote_identifier(column)?,
+        quote_identifier(table)?
+    );
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, &query, |conn| {
+        Ok(conn
+            .query_row(&query, [rowid], |row| row.get::<_, Option<i64>>(0))
+            .optional()?
+            .flatten())
+    })
+    .map_err(|e| e.with_query(&query, 1, config.include_sql_in_errors))
+}
+
+/// Returns how many rows `query` returns, bound with `values`, by running it
+/// as `SELECT COUNT(*) FROM (query)` so no row data is sent back, e.g. for the
+/// total of a paginated list.
+///
+/// `query` must be a single `SELECT` without a `LIMIT` of its own: pass the
+/// query given to `select` with `limit` and `offset`. An `ORDER BY` is
+/// allowed and ignored. Runs like `select`, including on a reader connection.
+#[command]
+pub(crate) fn select_count<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    tx_id: Option<String>,
+) -> Result<u64, crate::Error> {
+    check_sql_length(&connections.inner().config, query)?;
+    let inner = query.trim_end().trim_end_matches(';').trim_end();
+    if has_limit_clause(inner) {
+        return Err(Error::LimitAlreadyPresent);
+    }
+    // On their own lines so a trailing `--` comment can't swallow the `)`.
+    let count_query = format!("SELECT COUNT(*) FROM (\n{inner}\n)");
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_read_connection(connections.inner(), db_alias, tx_id, &count_query, |conn| {
+        let mut stmt = conn.prepare(&count_query)?;
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+        let mut rows = stmt.raw_query();
+        match rows.next()? {
+            Some(row) => Ok(row.get::<_, i64>(0)? as u64),
+            None => Ok(0),
+        }
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+}
+
+/// Compiles `sql` on the pooled connection of `db_alias` and returns a handle
+/// for `run`, so a statement executed many times is parsed only once.
+///
+/// The compiled statement lives in the connection's statement cache; the
+/// handle only names it. If the connection is reopened, e.g. after `release`,
+/// the first `run` compiles it again, and a statement evicted from the cache
+/// is recompiled the same way. Handles are not tied to a window or thread:
+/// `run` calls on one database are serialized with its other calls. A handle
+/// stays valid until `finalize`, or until its database is closed or the
+/// plugin `reset`.
+#[command]
+pub(crate) fn prepare<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    sql: &str,
+) -> Result<String, crate::Error> {
+    check_sql_length(&connections.inner().config, sql)?;
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.prepare_cached(sql)?;
+        Ok(())
+    })
+    .map_err(|e| e.with_query(sql, 0, connections.inner().config.include_sql_in_errors))?;
+
+    let handle = Uuid::new_v4().to_string();
+    lock_mutex(&connections.inner().prepared.0, "PreparedStatementManager")?.insert(
+        handle.clone(),
+        PreparedStatement {
+            db_alias: db_alias.to_string(),
+            sql: sql.to_string(),
+        },
+    );
+    Ok(handle)
+}
+
+/// Runs the statement of `handle` with `values` bound positionally and
+/// returns its rows, if any, along with the rows it changed.
+#[command]
+pub(crate) fn run<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    handle: &str,
+    values: Vec<JsonValue>,
+) -> Result<RunResult, crate::Error> {
+    let prepared = lock_mutex(&connections.inner().prepared.0, "PreparedStatementManager")?
+        .get(handle)
+        .cloned()
+        .ok_or_else(|| Error::StatementNotFound(handle.to_string()))?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), &prepared.db_alias, None, |conn| {
+        let mut stmt = conn.prepare_cached(&prepared.sql)?;
+        let col_names = convert::normalize_column_names(stmt.column_names(), config.column_case)?;
+        let formats = column_formats(&stmt, config);
+        bind_positional(&mut stmt, &converted_params, config.allow_unused_params)?;
+        let readonly = stmt.readonly();
+        let rows = rows_to_maps(
+            &col_names,
+            &formats,
+            stmt.raw_query(),
+            config.value_format(),
+        )?;
+
+        let keyword = if readonly {
+            String::new()
+        } else {
+            leading_keyword(&prepared.sql).to_ascii_uppercase()
+        };
+        let rows_affected = match keyword.as_str() {
+            "INSERT" | "REPLACE" | "UPDATE" | "DELETE" => conn.changes(),
+            _ => 0,
+        };
+        Ok(RunResult {
+            rows,
+            rows_affected,
+            last_insert_id: conn.last_insert_rowid(),
+        })
+    })
+    .map_err(|e| e.with_query(&prepared.sql, params_count, config.include_sql_in_errors))
+}
+
+/// Drops the statement handle returned by `prepare`. Returns whether it
+/// existed.
+#[command]
+pub(crate) fn finalize<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    handle: &str,
+) -> Result<bool, crate::Error> {
+    Ok(
+        lock_mutex(&connections.inner().prepared.0, "PreparedStatementManager")?
+            .remove(handle)
+            .is_some(),
+    )
+}
+
+/// Drops `table` and returns whether it existed. With `if_exists` a missing
+/// table is not an error and `false` is returned. Optionally runs within the
+/// transaction identified by `tx_id`.
+#[command]
+pub(crate) fn drop_table<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    if_exists: bool,
+    tx_id: Option<String>,
+) -> Result<bool, crate::Error> {
+    let query = format!(
+        "DROP TABLE {}{}",
+        if if_exists { "IF EXISTS " } else { "" },
+        quote_identifier(table)?
+    );
+
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        if conn.is_readonly(rusqlite::MAIN_DB)? {
+            return Err(Error::ReadOnlyTransaction);
+        }
+        let existed = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+            [table],
+            |row| row.get(0),
+        )?;
+        conn.execute(&query, [])?;
+        Ok(existed)
+    })
+}
+
+/// Deletes every row of `table` with `DELETE FROM`, which SQLite optimizes
+/// into a truncate, and returns the number of rows deleted. With
+/// `reset_autoincrement` the table's `sqlite_sequence` entry is removed too,
+/// so `AUTOINCREMENT` ids start over.
+#[command]
+pub(crate) fn truncate_table<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    reset_autoincrement: bool,
+) -> Result<u64, crate::Error> {
+    let query = format!("DELETE FROM {}", quote_identifier(table)?);
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let tx = conn.unchecked_transaction()?;
+        let deleted = tx.execute(&query, [])?;
+        if reset_autoincrement {
+            let has_sequence: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'sqlite_sequence')",
+                [],
+                |row| row.get(0),
+            )?;
+            if has_sequence {
+                tx.execute("DELETE FROM sqlite_sequence WHERE name = ?", [table])?;
+            }
+        }
+        tx.commit()?;
+        Ok(deleted as u64)
+    })
+}
+
+/// Creates `table` with `create_sql` unless it exists, and returns whether it
+/// was created. An existing table must have been created with the same SQL,
+/// otherwise `SchemaMismatch` is returned with both definitions. This is a
+/// lightweight alternative to migrations for tables whose schema never
+/// changes.
+///
+/// Definitions are compared without the `CREATE TABLE [IF NOT EXISTS]`
+/// prefix, which SQLite rewrites when storing it, a trailing `;` and
+/// whitespace differences around parentheses and commas. The check and the creation
+/// run in one immediate transaction, so two callers can't both create the
+/// table.
+#[command]
+pub(crate) fn ensure_table<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    create_sql: &str,
+) -> Result<bool, crate::Error> {
+    quote_identifier(table)?;
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let tx =
+            rusqlite::Transaction::new_unchecked(conn, rusqlite::TransactionBehavior::Immediate)?;
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?",
+                [table],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match existing {
+            Some(actual) if normalized_create_sql(&actual) == normalized_create_sql(create_sql) => {
+                Ok(false)
+            }
+            Some(actual) => Err(Error::SchemaMismatch {
+                table: table.to_string(),
+                expected: create_sql.trim().to_string(),
+                actual,
+            }),
+            None => {
+                tx.execute(create_sql, [])?;
+                let created: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+                    [table],
+                    |row| row.get(0),
+                )?;
+                if !created {
+                    return Err(Error::TableNotFound(table.to_string()));
+                }
+                tx.commit()?;
+                Ok(true)
+            }
+        }
+    })
+}
+
+/// The part of a `CREATE TABLE` statement after `CREATE TABLE [IF NOT EXISTS]`,
+/// without a trailing `;`, with whitespace collapsed and removed around
+/// parentheses and commas, for comparing table definitions.
+fn normalized_create_sql(sql: &str) -> String {
+    fn keywords(words: &[&str], expected: &[&str]) -> bool {
+        words.len() >= expected.len()
+            && words
+                .iter()
+                .zip(expected)
+                .all(|(word, keyword)| word.eq_ignore_ascii_case(keyword))
+    }
+
+    let words: Vec<&str> = sql
+        .trim()
+        .trim_end_matches(';')
+        .split_whitespace()
+        .collect();
+    let mut rest = &words[..];
+    if keywords(rest, &["CREATE", "TABLE"]) {
+        rest = &rest[2..];
+        if keywords(rest, &["IF", "NOT", "EXISTS"]) {
+            rest = &rest[3..];
+        }
+    }
+
+    let joined = rest.join(" ");
+    let is_punct = |c: char| matches!(c, '(' | ')' | ',');
+    let mut normalized = String::with_capacity(joined.len());
+    let mut chars = joined.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next_punct = chars.peek().is_some_and(|&n| is_punct(n));
+        let prev_punct = normalized.ends_with(is_punct);
+        if c != ' ' || !(next_punct || prev_punct) {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// Returns the hex SHA-256 digest of the contents of `table`, for detecting
+/// whether two databases diverged without comparing every row.
+///
+/// Rows are read ordered by the `order_by` columns, or by every column when
+/// not given, so the digest does not depend on insertion order. The column
+/// names and then every value are hashed with a type tag and, for text and
+/// blobs, a length prefix, so NULL, `''`, `0` and an empty blob all hash
+/// differently.
+#[command]
+pub(crate) fn table_hash<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    order_by: Option<Vec<String>>,
+) -> Result<String, crate::Error> {
+    let quoted_table = quote_identifier(table)?;
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let columns = column_names(conn, table)?;
+        if columns.is_empty() {
+            return Err(Error::TableNotFound(table.to_string()));
+        }
+        let order_by = order_by
+            .unwrap_or_else(|| columns.clone())
+            .iter()
+            .map(|c| quote_identifier(c))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(", ");
+
+        let mut hasher = Sha256::new();
+        for column in &columns {
+            hash_bytes(&mut hasher, column.as_bytes());
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM {} ORDER BY {}",
+            quoted_table, order_by
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            for i in 0..columns.len() {
+                match row.get_ref(i)? {
+                    ValueRef::Null => hasher.update([0]),
+                    ValueRef::Integer(n) => {
+                        hasher.update([1]);
+                        hasher.update(n.to_be_bytes());
+                    }
+                    ValueRef::Real(f) => {
+                        hasher.update([2]);
+                        hasher.update(f.to_bits().to_be_bytes());
+                    }
+                    ValueRef::Text(t) => {
+                        hasher.update([3]);
+                        hash_bytes(&mut hasher, t);
+                    }
+                    ValueRef::Blob(b) => {
+                        hasher.update([4]);
+                        hash_bytes(&mut hasher, b);
+                    }
+                }
+            }
+        }
+        Ok(hex_digest(hasher))
+    })
+}
+
+/// Returns the hex SHA-256 digest of the database file of `db_alias`, after
+/// checkpointing the WAL so committed changes are included. The digest covers
+/// the raw file, so databases with the same rows but a different history
+/// (e.g. page layout or free pages) hash differently; use `table_hash` to
+/// compare contents. Fails for in-memory databases.
+#[command]
+pub(crate) fn db_hash<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    let path = {
+        let connection_map = lock_mutex(&connections.inner().connections.0, "ConnectionManager")?;
+        let db_info = connection_map
+            .get(db_alias)
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?;
+        if display_path(db_info) == ":memory:" {
+            return Err(Error::Io(format!(
+                "{} is an in-memory database and has no file",
+                db_alias
+            )));
+        }
+        db_info.path.clone()
+    };
+
+    // Hold the pooled connection so no write lands between the checkpoint
+    // and the read.
+    let conn_arc = connections.inner().get_conn(db_alias)?;
+    let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    conn.query_row("PRAGMA wal_checkpoint(FULL)", [], |_| Ok(()))?;
+
+    let io_error =
+        |e: std::io::Error| Error::Io(format!("Failed to read {}: {}", path.display(), e));
+    let mut file = std::fs::File::open(&path).map_err(io_error)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(io_error)?;
+    Ok(hex_digest(hasher))
+}
+
+/// Returns the hex SHA-256 digest of the schema of `db_alias`, to check with
+/// `assert_schema` that a database is exactly at the schema an app version
+/// expects.
+///
+/// Every `sql` entry of `sqlite_master` except SQLite's internal `sqlite_*`
+/// objects is hashed with its whitespace collapsed, in sorted order, so the
+/// digest depends neither on formatting nor on creation order.
+#[command]
+pub(crate) fn schema_hash<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT sql FROM sqlite_master
+             WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'",
+        )?;
+        let mut entries = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|sql| Ok(sql?.split_whitespace().collect::<Vec<_>>().join(" ")))
+            .collect::<Result<Vec<_>, crate::Error>>()?;
+        entries.sort();
+
+        let mut hasher = Sha256::new();
+        for sql in &entries {
+            hash_bytes(&mut hasher, sql.as_bytes());
+        }
+        Ok(hex_digest(hasher))
+    })
+}
+
+/// Checks that the schema of `db_alias` hashes to `expected_hash`, as
+/// returned by `schema_hash`, e.g. before an upgraded app starts using a
+/// database that may have been modified out-of-band or missed a migration.
+/// Fails with `SchemaHashMismatch` carrying both hashes otherwise.
+#[command]
+pub(crate) fn assert_schema<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    expected_hash: &str,
+) -> Result<(), crate::Error> {
+    let actual = schema_hash(app, connections, db_alias)?;
+    if actual.eq_ignore_ascii_case(expected_hash.trim()) {
+        Ok(())
+    } else {
+        Err(Error::SchemaHashMismatch {
+            expected: expected_hash.to_string(),
+            actual,
+        })
+    }
+}
+
+/// Hashes `bytes` prefixed with their length, so adjacent values can't run
+/// into each other.
+fn hash_bytes(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Returns `query` with `values` bound and inlined, via `sqlite3_expanded_sql`.
+/// Diagnostic only: the statement is prepared but never run, and the result is
+/// not meant to be executed. The output contains the bound values verbatim,
+/// so it may expose sensitive data. Requires the `expand_sql` feature.
+#[command]
+pub(crate) fn expand_sql<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+) -> Result<String, crate::Error> {
+    #[cfg(feature = "expand_sql")]
+    {
+        let converted_params = convert::json_to_rusqlite_params(values)?;
+        with_connection(connections.inner(), db_alias, None, |conn| {
+            let mut stmt = conn.prepare(query)?;
+            for (index, value) in converted_params.iter().enumerate() {
+                stmt.raw_bind_parameter(index + 1, value)?;
+            }
+            stmt.expanded_sql()
+                .ok_or_else(|| Error::ValueConversionError("Failed to expand SQL".to_string()))
+        })
+    }
+    #[cfg(not(feature = "expand_sql"))]
+    {
+        let _ = (connections, db_alias, query, values);
+        Err(Error::FeatureNotEnabled("expand_sql".to_string()))
+    }
+}
+
+/// Returns the schema cookie (`PRAGMA schema_version`), which SQLite changes
+/// whenever the schema is modified. Useful for invalidating cached schema info.
+#[command]
+pub(crate) fn schema_version<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    tx_id: Option<String>,
+) -> Result<i64, crate::Error> {
+    with_connection(connections.inner(), db_alias, tx_id, |conn| {
+        Ok(conn.pragma_query_value(None, "schema_version", |row| row.get(0))?)
+    })
+}
+
+/// Returns the schema of `db_alias` as one SQL script: the `CREATE`
+/// statements of its tables, then views, indexes and triggers, each in
+/// creation order and ended with `;`. SQLite's internal `sqlite_*` objects,
+/// automatic indexes and the shadow tables of virtual tables are left out, so
+/// the script recreates the schema on an empty database.
+#[command]
+pub(crate) fn dump_schema<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT sql FROM sqlite_master AS m
+             WHERE sql IS NOT NULL
+               AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+               AND NOT (type = 'table' AND EXISTS (
+                   SELECT 1 FROM sqlite_master AS v
+                   WHERE v.type = 'table'
+                     AND v.sql LIKE 'CREATE VIRTUAL TABLE%'
+                     AND m.name LIKE v.name || '\\_%' ESCAPE '\\'))
+             ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 WHEN 'index' THEN 2 ELSE 3 END,
+                      rowid",
+        )?;
+        let mut script = String::new();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            script.push_str(&row.get::<_, String>(0)?);
+            script.push_str(";\n");
+        }
+        Ok(script)
+    })
+}
+
+/// Rebuilds indexes with `REINDEX`. Without `target` every index in the
+/// database is rebuilt; otherwise only the named index, the indexes of the
+/// named table, or the indexes using the named collation.
+#[command]
+pub(crate) fn reindex<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    target: Option<String>,
+) -> Result<(), crate::Error> {
+    let query = match target {
+        Some(target) => format!("REINDEX {}", quote_identifier(&target)?),
+        None => "REINDEX".to_string(),
+    };
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.execute_batch(&query)?;
+        Ok(())
+    })
+}
+
+/// Runs `ANALYZE` on the whole database, or on `target` when given, a table
+/// or index name, so the query planner picks indexes from fresh statistics.
+/// The statistics are stored in the database; see `query_stats`.
+#[command]
+pub(crate) fn analyze<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    target: Option<String>,
+) -> Result<(), crate::Error> {
+    let query = match target {
+        Some(target) => format!("ANALYZE {}", quote_identifier(&target)?),
+        None => "ANALYZE".to_string(),
+    };
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.execute_batch(&query)?;
+        Ok(())
+    })
+}
+
+/// Returns the rows of `sqlite_stat1` and `sqlite_stat4`, the statistics
+/// `analyze` collects for the query planner. Tables that do not exist, e.g.
+/// before the first `ANALYZE`, are returned empty.
+#[command]
+pub(crate) fn query_stats<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<QueryStats, crate::Error> {
+    let config = &connections.inner().config;
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let read = |table: &str| -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+                [table],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Ok(Vec::new());
+            }
+            query_rows(
+                conn,
+                &format!("SELECT * FROM {table}"),
+                Vec::new(),
+                ColumnCase::AsIs,
+                config,
+            )
+        };
+        Ok(QueryStats {
+            stat1: read("sqlite_stat1")?,
+            stat4: read("sqlite_stat4")?,
+        })
+    })
+}
+
+/// Frees up to `pages` pages of the freelist, or all of them without
+/// `pages`, with `PRAGMA incremental_vacuum`, reclaiming space gradually
+/// instead of locking the database for a full `VACUUM`. Returns the
+/// freelist count before and after.
+///
+/// Only works on databases with `PRAGMA auto_vacuum = INCREMENTAL`; fails
+/// with `AutoVacuumNotIncremental` otherwise.
+#[command]
+pub(crate) fn incremental_vacuum<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    pages: Option<u32>,
+) -> Result<IncrementalVacuumReport, crate::Error> {
+    let query = match pages {
+        Some(pages) => format!("PRAGMA incremental_vacuum({pages})"),
+        None => "PRAGMA incremental_vacuum".to_string(),
+    };
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        let auto_vacuum: i64 = conn.pragma_query_value(None, "auto_vacuum", |row| row.get(0))?;
+        // 2 is INCREMENTAL; 0 is NONE and 1 FULL.
+        if auto_vacuum != 2 {
+            return Err(Error::AutoVacuumNotIncremental);
+        }
+        let freelist = || -> Result<u64, crate::Error> {
+            Ok(conn.pragma_query_value(None, "freelist_count", |row| row.get(0))?)
+        };
+        let freelist_before = freelist()?;
+        conn.execute_batch(&query)?;
+        Ok(IncrementalVacuumReport {
+            freelist_before,
+            freelist_after: freelist()?,
+        })
+    })
+}
+
+/// Returns `PRAGMA application_id` of `db_alias`, after setting it to `set`
+/// if given. Apps can set it on their databases to recognize them later.
+#[command]
+pub(crate) fn application_id<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    set: Option<i32>,
+) -> Result<i32, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        if let Some(application_id) = set {
+            conn.pragma_update(None, "application_id", application_id)?;
+        }
+        Ok(conn.pragma_query_value(None, "application_id", |row| row.get(0))?)
+    })
+}
+
+/// Forces committed data of `db_alias` onto disk: flushes the pooled
+/// connection's dirty pages with `cache_flush` and, in WAL mode, copies the
+/// WAL into the database file with `PRAGMA wal_checkpoint(FULL)`, which syncs
+/// it. Returns `false` if the checkpoint could not complete because another
+/// connection is reading or writing; committed data is still durable in the
+/// WAL in that case. Uncommitted transactions are not affected, and for
+/// in-memory databases this does nothing.
+#[command]
+pub(crate) fn flush<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<bool, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.cache_flush()?;
+        let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(FULL)", [], |row| row.get(0))?;
+        Ok(busy == 0)
+    })
+}
+
+/// Writes a compacted copy of the database to `dest_path` (resolved under
+/// `app_data_dir`) with `VACUUM INTO`, leaving the original untouched, and
+/// returns the size of the new file in bytes. Fails if `dest_path` exists.
+#[command]
+pub(crate) fn vacuum_into<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    dest_path: &str,
+) -> Result<u64, crate::Error> {
+    let dest = resolve_db_path(&app, dest_path, connections.config.create_missing_dirs)?;
+    if dest.exists() {
+        return Err(Error::DestinationExists(dest.display().to_string()));
+    }
+
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        conn.execute("VACUUM INTO ?", [dest.to_string_lossy()])?;
+        Ok(())
+    })?;
+
+    let metadata = std::fs::metadata(&dest)
+        .map_err(|e| Error::Io(format!("Failed to read {}: {}", dest.display(), e)))?;
+    Ok(metadata.len())
+}
+
+fn query_rows(
+    conn: &Connection,
+    query: &str,
+    params: Vec<Box<dyn rusqlite::ToSql>>,
+    case: ColumnCase,
+    config: &PluginConfig,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let mut stmt = conn.prepare(query).map_err(Error::from)?;
+    let col_names = convert::normalize_column_names(stmt.column_names(), case)?;
+    let formats = column_formats(&stmt, config);
+    bind_positional(&mut stmt, &params, config.allow_unused_params)?;
+
+    rows_to_maps(
+        &col_names,
+        &formats,
+        stmt.raw_query(),
+        config.value_format(),
+    )
+}
+
+/// How each result column of `stmt` is read: as exact decimal strings for
+/// those listed in `decimal_columns` and, with the `column_decltype` feature,
+/// those declared `DECIMAL` or `NUMERIC`; as ISO timestamps for those listed
+/// in `unix_time_columns`; and, with `coerce_to_affinity`, coerced to the
+/// affinity of their declared type otherwise.
+fn column_formats(
+    stmt: &rusqlite::Statement<'_>,
+    config: &PluginConfig,
+) -> Vec<convert::ColumnFormat> {
+    let listed =
+        |columns: &[String], name: &str| columns.iter().any(|c| c.eq_ignore_ascii_case(name));
+    (0..stmt.column_count())
+        .map(|i| {
+            let name = stmt.column_name(i).unwrap_or_default();
+            #[cfg(feature = "column_decltype")]
+            let declared = stmt.columns()[i].decl_type().is_some_and(|decl| {
+                let decl = decl.to_ascii_uppercase();
+                decl.starts_with("DECIMAL") || decl.starts_with("NUMERIC")
+            });
+            #[cfg(not(feature = "column_decltype"))]
+            let declared = false;
+            if listed(&config.decimal_columns, name) || declared {
+                convert::ColumnFormat::Decimal
+            } else if listed(&config.unix_time_columns, name) {
+                convert::ColumnFormat::UnixTime
+            } else {
+                #[cfg(feature = "column_decltype")]
+                if config.coerce_to_affinity {
+                    return convert::ColumnFormat::Affinity(convert::Affinity::from_decl_type(
+                        stmt.columns()[i].decl_type(),
+                    ));
+                }
+                convert::ColumnFormat::Plain
+            }
+        })
+        .collect()
+}
+
+/// Binds `params` to `stmt` by 1-based index, so numbered `?NNN` placeholders
+/// may be reused, appear out of order or skip indexes. Fails if a placeholder
+/// has no value, or, unless `allow_unused`, if a value is not referenced by any
+/// placeholder.
+///
+/// In a statement using `?NNN` placeholders, indexes without one are treated
+/// as gaps, so plain `?` placeholders should not be mixed with numbered ones.
+fn bind_positional(
+    stmt: &mut rusqlite::Statement<'_>,
+    params: &[Box<dyn rusqlite::ToSql>],
+    allow_unused: bool,
+) -> Result<(), crate::Error> {
+    let count = stmt.parameter_count();
+    let numbered = (1..=count).any(|i| stmt.parameter_name(i).is_some_and(|n| n.starts_with('?')));
+    let referenced = |stmt: &rusqlite::Statement<'_>, i: usize| {
+        i <= count && (!numbered || stmt.parameter_name(i).is_some())
+    };
+
+    for i in 1..=count.max(params.len()) {
+        match params.get(i - 1) {
+            Some(value) if referenced(stmt, i) => stmt.raw_bind_parameter(i, value)?,
+            Some(_) if !allow_unused => {
+                return Err(Error::ParameterMismatch(format!(
+                    "value {} is not referenced by any placeholder",
+                    i
+                )))
+            }
+            None if referenced(stmt, i) => {
+                return Err(Error::ParameterMismatch(format!(
+                    "placeholder {} has no value ({} given)",
+                    stmt.parameter_name(i).unwrap_or("?"),
+                    params.len()
+                )))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Like `query_rows`, binding parameters by name. Parameters the statement
+/// does not reference are ignored.
+pub(crate) fn query_rows_named(
+    conn: &Connection,
+    query: &str,
+    params: convert::NamedParams,
+    case: ColumnCase,
+    config: &PluginConfig,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let mut stmt = conn.prepare(query).map_err(Error::from)?;
+    let col_names = convert::normalize_column_names(stmt.column_names(), case)?;
+    let formats = column_formats(&stmt, config);
+    for (name, value) in params {
+        if let Some(index) = stmt.parameter_index(&name)? {
+            stmt.raw_bind_parameter(index, value)?;
+        }
+    }
+
+    rows_to_maps(
+        &col_names,
+        &formats,
+        stmt.raw_query(),
+        config.value_format(),
+    )
+}
+
+fn rows_to_maps(
+    col_names: &[String],
+    formats: &[convert::ColumnFormat],
+    mut rows: rusqlite::Rows<'_>,
+    format: convert::ValueFormat,
+) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+    let mut results = Vec::new();
+    while let Some(row) = rows.next().map_err(Error::from)? {
+        results.push(row_to_map(col_names, formats, row, format)?);
+    }
+    Ok(results)
+}
+
+fn row_to_map(
+    col_names: &[String],
+    formats: &[convert::ColumnFormat],
+    row: &rusqlite::Row<'_>,
+    format: convert::ValueFormat,
+) -> Result<IndexMap<String, JsonValue>, crate::Error> {
+    let mut row_map = IndexMap::new();
+    for (i, col_name) in col_names.iter().enumerate() {
+        let column_format = formats.get(i).copied().unwrap_or_default();
+        let value_json = column_value_to_json(row, i, col_name, column_format, format)?;
+        row_map.insert(col_name.clone(), value_json);
+    }
+    Ok(row_map)
+}
+
+/// Converts column `i` of `row` to JSON, naming `col_name` in conversion
+/// errors.
+fn column_value_to_json(
+    row: &rusqlite::Row<'_>,
+    i: usize,
+    col_name: &str,
+    column_format: convert::ColumnFormat,
+    format: convert::ValueFormat,
+) -> Result<JsonValue, crate::Error> {
+    let value_ref = row.get_ref(i).map_err(Error::from)?;
+    let value_json = match column_format {
+        convert::ColumnFormat::Plain => convert::rusqlite_value_to_json(value_ref, format),
+        convert::ColumnFormat::Decimal => convert::decimal_value_to_json(value_ref, format),
+        convert::ColumnFormat::UnixTime => convert::unix_time_value_to_json(value_ref, format),
+        convert::ColumnFormat::Affinity(affinity) => {
+            convert::affinity_value_to_json(value_ref, affinity, format)
+        }
+    };
+    value_json.map_err(|e| match e {
+        Error::ValueConversionError(message) => {
+            Error::ValueConversionError(format!("column \"{}\": {}", col_name, message))
+        }
+        e => e,
+    })
+}
+
+/// Returns the current time according to the database, as an ISO 8601 UTC
+/// string with milliseconds, e.g. `"2024-01-31T12:00:00.000Z"`. This is the
+/// format `unix_time_columns` are read in, and it sorts and compares as text
+/// like SQLite's own date functions expect.
+#[command]
+pub(crate) fn now_iso<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<String, crate::Error> {
+    with_connection(connections.inner(), db_alias, None, |conn| {
+        Ok(
+            conn.query_row("SELECT strftime('%Y-%m-%dT%H:%M:%fZ', 'now')", [], |row| {
+                row.get(0)
+            })?,
+        )
+    })
+}
+
+/// Writes every row of `query` to `dest_path` (resolved under `app_data_dir`)
+/// as newline-delimited JSON, one object per line, and returns the number of
+/// rows written. Rows are written as they are read, so memory use does not
+/// grow with the result size.
+///
+/// Rows go to a temporary file next to `dest_path` that replaces it only once
+/// every row is written, so an unknown alias, a failing query or a write error
+/// leaves an existing export untouched.
+#[command]
+pub(crate) fn export_ndjson<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    dest_path: &str,
+) -> Result<u64, crate::Error> {
+    let config = &connections.inner().config;
+    check_sql_length(config, query)?;
+    let params_count = values.len();
+    let converted_params = convert::json_to_rusqlite_params(values)?;
+    let dest = resolve_db_path(&app, dest_path, config.create_missing_dirs)?;
+    let io_error =
+        |e: std::io::Error| Error::Io(format!("Failed to write {}: {}", dest.display(), e));
+    let mut tmp = dest.clone().into_os_string();
+    tmp.push(format!(".{}.tmp", Uuid::new_v4()));
+    let tmp = PathBuf::from(tmp);
+
+    let file = std::fs::File::create(&tmp).map_err(io_error)?;
+    let mut writer = std::io::BufWriter::new(file);
+    let written = with_connection(connections.inner(), db_alias, None, |conn| {
+        let mut stmt = conn.prepare(query)?;
+        let col_names =
+            convert::normalize_column_names(stmt.column_names(), connections.config.column_case)?;
+        let formats = column_formats(&stmt, &connections.config);
+        bind_positional(
+            &mut stmt,
+            &converted_params,
+            connections.config.allow_unused_params,
+        )?;
+        let mut rows = stmt.raw_query();
+
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let line = serde_json::to_string(&row_to_map(
+                &col_names,
+                &formats,
+                row,
+                connections.config.value_format(),
+            )?)
+            .map_err(|e| Error::ValueConversionError(e.to_string()))?;
+            writeln!(writer, "{}", line).map_err(io_error)?;
+            count += 1;
+        }
+        Ok(count)
+    })
+    .map_err(|e| e.with_query(query, params_count, config.include_sql_in_errors))
+    .and_then(|count| {
+        writer.flush().map_err(io_error)?;
+        Ok(count)
+    });
+    // Closed before the rename, which some platforms require.
+    drop(writer);
+
+    let written = written.and_then(|count| {
+        std::fs::rename(&tmp, &dest).map_err(io_error)?;
+        Ok(count)
+    });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Extracts `path` from the JSON stored in `json_column` of every matching row.
+///
+/// Builds `SELECT json_extract(json_column, ?) FROM table [WHERE where_clause]`,
+/// binding `path` as the first parameter; `?` placeholders in `where_clause`
+/// are bound from `values`. Objects and arrays are returned as JSON text.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn json_get<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    table: &str,
+    json_column: &str,
+    path: &str,
+    where_clause: Option<String>,
+    values: Vec<JsonValue>,
+) -> Result<Vec<JsonValue>, crate::Error> {
+    let mut query = format!(
+        "SELECT json_extract({}, ?) AS value FROM {}",
+        quote_identifier(json_column)?,
+        quote_identifier(table)?
+    );
+    if let Some(where_clause) = where_clause.filter(|w| !w.trim().is_empty()) {
+        query.push_str(" WHERE ");
+        query.push_str(&where_clause);
+    }
+
+    let mut params = vec![JsonValue::String(path.to_string())];
+    params.extend(values);
+    let converted_params = convert::json_to_rusqlite_params(params)?;
+
+    let conn_arc = connections.inner().get_conn(db_alias)?;
+    let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+
+    conn.query_row("SELECT json_valid('{}')", [], |_| Ok(()))
+        .map_err(|_| Error::JsonUnsupported)?;
+
+    let rows = query_rows(
+        &conn,
+        &query,
+        converted_params,
+        ColumnCase::AsIs,
+        &connections.config,
+    )?;
+    Ok(rows
+        .into_iter()
+        .map(|mut row| row.shift_remove("value").unwrap_or(JsonValue::Null))
+        .collect())
+}
+
+/// Column names of `table`, in declaration order.
+fn column_names(conn: &Connection, table: &str) -> Result<Vec<String>, crate::Error> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?)")?;
+    let names = stmt
+        .query_map([table], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(names)
+}
+
+/// Whether two column lists contain the same names, ignoring order.
+fn same_columns(a: &[String], b: &[String]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a == b
+}
+
+/// Copies every row of `table` from `src_alias` into the same table of `dest_alias`.
+///
+/// Source rows are read first and then inserted in a single transaction on the
+/// destination, so the two pooled connections are never locked at the same time.
+/// When the destination table is missing it is created from the source's
+/// `CREATE TABLE` statement if `create_if_missing` is set. Both tables must have
+/// the same columns. Returns the number of rows copied.
+#[command]
+pub(crate) fn copy_table<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    src_alias: &str,
+    dest_alias: &str,
+    table: &str,
+    create_if_missing: bool,
+) -> Result<usize, crate::Error> {
+    let quoted_table = quote_identifier(table)?;
+
+    let (create_sql, columns, rows) = {
+        let conn_arc = connections.inner().get_conn(src_alias)?;
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+
+        let create_sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Error::TableNotFound(table.to_string()),
+                e => Error::from(e),
+            })?;
+        let columns = column_names(&conn, table)?;
+
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quoted_table))?;
+        let rows = stmt
+            .query_map([], |row| {
+                (0..columns.len())
+                    .map(|i| row.get::<_, rusqlite::types::Value>(i))
+                    .collect::<Result<Vec<_>, _>>()
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        (create_sql, columns, rows)
+    };
+
+    let conn_arc = connections.inner().get_conn(dest_alias)?;
+    let mut conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    let tx = conn.transaction()?;
+
+    let dest_columns = column_names(&tx, table)?;
+    if dest_columns.is_empty() {
+        if !create_if_missing {
+            return Err(Error::TableNotFound(format!("{} in {}", table, dest_alias)));
+        }
+        tx.execute_batch(&create_sql)?;
+    } else if !same_columns(&dest_columns, &columns) {
+        return Err(Error::ColumnMismatch(format!(
+            "source has ({}), destination has ({})",
+            columns.join(", "),
+            dest_columns.join(", ")
+        )));
+    }
+
+    let column_list = columns
+        .iter()
+        .map(|c| format!("\"{}\"", c.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    {
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quoted_table, column_list, placeholders
+        ))?;
+        for row in &rows {
+            insert.execute(rusqlite::params_from_iter(row))?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(rows.len())
+}
+
+/// Enables or disables `sql://progress` events for `db_alias`.
+///
+/// The handler is installed on the pooled connection immediately and on every
+/// dedicated connection (transactions, migrations, readers) opened afterwards. Events
+/// are emitted every `progressInterval` VM instructions (plugin config).
+/// Requires the `hooks` feature.
+#[command]
+pub(crate) fn set_progress_handler<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    enabled: bool,
+) -> Result<(), crate::Error> {
+    #[cfg(feature = "hooks")]
+    {
+        lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .get_mut(db_alias)
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?
+            .report_progress = enabled;
+
+        let conn_arc = connections.inner().get_conn(db_alias)?;
+        // Readers are reopened with the new setting.
+        if let Some(pooled) =
+            lock_mutex(&connections.inner().pool.0, "ConnectionManager")?.get_mut(db_alias)
+        {
+            pooled.readers.clear();
+        }
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+        if enabled {
+            install_progress_handler(&conn, connections.inner(), db_alias);
+        } else {
+            conn.progress_handler(0, None::<fn() -> bool>);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "hooks"))]
+    {
+        let _ = (connections, db_alias, enabled);
+        Err(Error::FeatureNotEnabled("hooks".to_string()))
+    }
+}
+
+/// Sets SQLite limit `category` of `db_alias` to `value`, e.g. raising
+/// `variable_number` for bulk inserts binding many values, and returns the
+/// value in effect: SQLite caps it at the maximum it was compiled with.
+///
+/// The limit is set on the pooled connection now and on every connection
+/// opened for the alias afterwards, including transactions and readers,
+/// overriding the `limits` plugin config. Requires the `limits` feature.
+#[command]
+pub(crate) fn set_limit<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    category: SqliteLimit,
+    value: i32,
+) -> Result<i32, crate::Error> {
+    #[cfg(feature = "limits")]
+    {
+        lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .get_mut(db_alias)
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?
+            .limits
+            .insert(category, value);
+        // Readers are reopened with the new limit.
+        if let Some(pooled) =
+            lock_mutex(&connections.inner().pool.0, "ConnectionManager")?.get_mut(db_alias)
+        {
+            pooled.readers.clear();
+        }
+
+        with_connection(connections.inner(), db_alias, None, |conn| {
+            conn.set_limit(category.into(), value)?;
+            Ok(conn.limit(category.into())?)
+        })
+    }
+
+    #[cfg(not(feature = "limits"))]
+    {
+        let _ = (connections, db_alias, category, value);
+        Err(Error::FeatureNotEnabled("limits".to_string()))
+    }
+}
+
+/// Interrupts the queries currently running on the pooled connection of
+/// `db_alias` and on its reader connections. Does nothing to idle connections.
+#[command]
+pub(crate) fn interrupt<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<(), crate::Error> {
+    lock_mutex(&connections.inner().interrupts.0, "InterruptManager")?
+        .get(db_alias)
+        .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?
+        .interrupt();
+    if let Some(pooled) =
+        lock_mutex(&connections.inner().pool.0, "ConnectionManager")?.get(db_alias)
+    {
+        for (_, reader_interrupt) in &pooled.readers {
+            reader_interrupt.interrupt();
+        }
+    }
+
+    Ok(())
+}
+
+/// Snapshot of the column names of every table, used to label preupdate values.
+#[cfg(feature = "preupdate_hook")]
+fn table_columns(conn: &Connection) -> Result<HashMap<String, Vec<String>>, crate::Error> {
+    let mut tables_stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+    let tables = tables_stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut columns_stmt = conn.prepare("SELECT name FROM pragma_table_info(?)")?;
+    let mut columns = HashMap::new();
+    for table in tables {
+        let names = columns_stmt
+            .query_map([&table], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        columns.insert(table, names);
+    }
+    Ok(columns)
+}
+
+#[cfg(feature = "preupdate_hook")]
+fn preupdate_old_row(
+    accessor: &PreUpdateOldValueAccessor,
+    column_name: &dyn Fn(i32) -> String,
+    format: convert::ValueFormat,
+) -> IndexMap<String, JsonValue> {
+    (0..accessor.get_column_count())
+        .map(|i| {
+            let value = accessor
+                .get_old_column_value(i)
+                .map_err(Error::from)
+                .and_then(|value| convert::rusqlite_value_to_json(value, format))
+                .unwrap_or(JsonValue::Null);
+            (column_name(i), value)
+        })
+        .collect()
+}
+
+#[cfg(feature = "preupdate_hook")]
+fn preupdate_new_row(
+    accessor: &PreUpdateNewValueAccessor,
+    column_name: &dyn Fn(i32) -> String,
+    format: convert::ValueFormat,
+) -> IndexMap<String, JsonValue> {
+    (0..accessor.get_column_count())
+        .map(|i| {
+            let value = accessor
+                .get_new_column_value(i)
+                .map_err(Error::from)
+                .and_then(|value| convert::rusqlite_value_to_json(value, format))
+                .unwrap_or(JsonValue::Null);
+            (column_name(i), value)
+        })
+        .collect()
+}
+
+/// Installs a preupdate hook on the pooled connection of `db_alias` that emits a
+/// `sql://change` event for every inserted, updated or deleted row.
+///
+/// The hook stays installed until `unwatch_changes`: connections opened for the
+/// alias afterwards, including a pooled connection reopened after
+/// `release_connection` or recycling and the dedicated connections of
+/// transactions, get it too. Column names are resolved from a snapshot of the
+/// schema taken when each connection gets the hook; columns of tables created
+/// afterwards are reported by index. The hook fires before the change is
+/// written, so a change that is later rolled back is still reported.
+#[command]
+pub(crate) fn watch_changes<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<(), crate::Error> {
+    #[cfg(feature = "preupdate_hook")]
+    {
+        lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .get_mut(db_alias)
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?
+            .watch_changes = true;
+
+        let conn_arc = connections.inner().get_conn(db_alias)?;
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+        install_change_hook(&conn, connections.inner(), db_alias)
+    }
+
+    #[cfg(not(feature = "preupdate_hook"))]
+    {
+        let _ = (connections, db_alias);
+        Err(Error::FeatureNotEnabled("preupdate_hook".to_string()))
+    }
+}
+
+/// Emits a `sql://change` event for every row `conn` inserts, updates or
+/// deletes.
+#[cfg(feature = "preupdate_hook")]
+fn install_change_hook<R: Runtime>(
+    conn: &Connection,
+    connections: &Rusqlite2Connections<R>,
+    db_alias: &str,
+) -> Result<(), crate::Error> {
+    let columns = table_columns(conn)?;
+    let alias = db_alias.to_string();
+    let format = connections.config.value_format();
+    let app = connections.app.clone();
+
+    conn.preupdate_hook(Some(
+        move |_action: Action, _db: &str, table: &str, case: &PreUpdateCase| {
+            let names = columns.get(table);
+            let column_name = |i: i32| {
+                names
+                    .and_then(|n| n.get(i as usize))
+                    .cloned()
+                    .unwrap_or_else(|| i.to_string())
+            };
+
+            let (action, rowid, old_values, new_values) = match case {
+                PreUpdateCase::Insert(new) => (
+                    "insert",
+                    new.get_new_row_id(),
+                    None,
+                    Some(preupdate_new_row(new, &column_name, format)),
+                ),
+                PreUpdateCase::Delete(old) => (
+                    "delete",
+                    old.get_old_row_id(),
+                    Some(preupdate_old_row(old, &column_name, format)),
+                    None,
+                ),
+                PreUpdateCase::Update {
+                    old_value_accessor,
+                    new_value_accessor,
+                } => (
+                    "update",
+                    new_value_accessor.get_new_row_id(),
+                    Some(preupdate_old_row(old_value_accessor, &column_name, format)),
+                    Some(preupdate_new_row(new_value_accessor, &column_name, format)),
+                ),
+                PreUpdateCase::Unknown => return,
+            };
+
+            let changed_columns = match (&old_values, &new_values) {
+                (Some(old), Some(new)) => old
+                    .iter()
+                    .filter(|(name, value)| new.get(name.as_str()) != Some(*value))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                (Some(row), None) | (None, Some(row)) => row.keys().cloned().collect(),
+                (None, None) => Vec::new(),
+            };
+
+            let change = RowChange {
+                db_alias: alias.clone(),
+                action: action.to_string(),
+                table: table.to_string(),
+                rowid,
+                old_values,
+                new_values,
+                changed_columns,
+            };
+            if let Err(e) = app.emit(CHANGE_EVENT, change) {
+                log::error!("Failed to emit change event: {}", e);
+            }
+        },
+    ));
+
+    Ok(())
+}
+
+/// Removes the preupdate hook installed by `watch_changes`.
+#[command]
+pub(crate) fn unwatch_changes<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<(), crate::Error> {
+    #[cfg(feature = "preupdate_hook")]
+    {
+        lock_mutex(&connections.inner().connections.0, "ConnectionManager")?
+            .get_mut(db_alias)
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))?
+            .watch_changes = false;
+
+        let conn_arc = connections.inner().get_conn(db_alias)?;
+        lock_mutex(&conn_arc, "ConnectionManager")?
+            .preupdate_hook(None::<fn(Action, &str, &str, &PreUpdateCase)>);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "preupdate_hook"))]
+    {
+        let _ = (connections, db_alias);
+        Err(Error::FeatureNotEnabled("preupdate_hook".to_string()))
+    }
+}
+
+/// Watches the result of `query` by re-running it every `interval_ms` on a
+/// background thread and emits a `sql://changed` event whenever it differs
+/// from the previous run. Returns the watch id to pass to `poll_unwatch`.
+///
+/// With `key_column`, rows are matched by that column, so a row whose other
+/// values changed is reported in `changed`. Without it, rows are compared as a
+/// whole and an updated row shows up as removed and added. Unlike
+/// `watch_changes`, this needs no SQLite hooks and also sees writes made by
+/// transactions and other processes. The watch stops when the database is
+/// closed.
+#[command]
+pub(crate) fn poll_watch<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    query: &str,
+    values: Vec<JsonValue>,
+    interval_ms: u64,
+    key_column: Option<String>,
+) -> Result<String, crate::Error> {
+    let connections = connections.inner().clone();
+    let run = {
+        let connections = connections.clone();
+        let db_alias = db_alias.to_string();
+        let query = query.to_string();
+        move || {
+            let params = convert::json_to_rusqlite_params(values.clone())?;
+            with_connection(&connections, &db_alias, None, |conn| {
+                query_rows(
+                    conn,
+                    &query,
+                    params,
+                    connections.config.column_case,
+                    &connections.config,
+                )
+            })
+        }
+    };
+    // The first run validates the query and is the baseline for the diffs.
+    let mut previous = run()?;
+
+    let watch_id = Uuid::new_v4().to_string();
+    let (stop, stopped) = std::sync::mpsc::channel::<()>();
+    lock_mutex(&connections.background_tasks.0, "BackgroundTaskManager")?.insert(
+        watch_id.clone(),
+        BackgroundTask {
+            kind: "poll_watch",
+            db_alias: db_alias.to_string(),
+            stop,
+        },
+    );
+
+    let id = watch_id.clone();
+    let alias = db_alias.to_string();
+    let interval = Duration::from_millis(interval_ms.max(1));
+    std::thread::spawn(move || {
+        // Dropping the sender in `poll_unwatch` disconnects the channel.
+        while let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            let rows = match run() {
+                Ok(rows) => rows,
+                Err(Error::DatabaseNotLoaded(_)) => break,
+                Err(e) => {
+                    log::warn!("Poll watch {} on {} failed: {}", id, alias, e);
+                    continue;
+                }
+            };
+            let change = diff_rows(&id, &alias, &previous, &rows, key_column.as_deref());
+            if !(change.added.is_empty() && change.removed.is_empty() && change.changed.is_empty())
+            {
+                if let Err(e) = app.emit(POLL_CHANGE_EVENT, change) {
+                    log::error!("Failed to emit poll change event: {}", e);
+                }
+            }
+            previous = rows;
+        }
+        if let Ok(mut tasks) = lock_mutex(&connections.background_tasks.0, "BackgroundTaskManager")
+        {
+            tasks.remove(&id);
+        }
+    });
+
+    Ok(watch_id)
+}
+
+/// Stops the watch started by `poll_watch`. Returns `false` if no watch with
+/// that id is running.
+#[command]
+pub(crate) fn poll_unwatch<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    watch_id: &str,
+) -> Result<bool, crate::Error> {
+    cancel_background_task(app, connections, watch_id)
+}
+
+/// Returns the plugin configuration in effect, with the defaults of unset
+/// options filled in, e.g. to check that a setting was picked up. The
+/// encryption keys of `preload` connection strings are redacted.
+#[command]
+pub(crate) fn get_config<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+) -> Result<PluginConfig, crate::Error> {
+    Ok(connections.inner().config.clone())
+}
+
+/// Lists the tasks the plugin runs in the background, such as `poll_watch`
+/// threads, with their id, kind and database, e.g. to find watches left
+/// running.
+#[command]
+pub(crate) fn list_background_tasks<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+) -> Result<Vec<BackgroundTaskInfo>, crate::Error> {
+    let mut tasks: Vec<BackgroundTaskInfo> = lock_mutex(
+        &connections.inner().background_tasks.0,
+        "BackgroundTaskManager",
+    )?
+    .iter()
+    .map(|(id, task)| BackgroundTaskInfo {
+        id: id.clone(),
+        kind: task.kind.to_string(),
+        db_alias: task.db_alias.clone(),
+    })
+    .collect();
+    tasks.sort_by(|a, b| (&a.db_alias, &a.kind, &a.id).cmp(&(&b.db_alias, &b.kind, &b.id)));
+    Ok(tasks)
+}
+
+/// Stops the background task `task_id`, as listed by `list_background_tasks`.
+/// Returns `false` if no task with that id is running.
+#[command]
+pub(crate) fn cancel_background_task<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    task_id: &str,
+) -> Result<bool, crate::Error> {
+    Ok(lock_mutex(
+        &connections.inner().background_tasks.0,
+        "BackgroundTaskManager",
+    )?
+    .remove(task_id)
+    .is_some())
+}
+
+/// Compares two results of a watched query, matching rows by `key_column`
+/// when given and by their whole content otherwise.
+fn diff_rows(
+    watch_id: &str,
+    db_alias: &str,
+    previous: &[IndexMap<String, JsonValue>],
+    current: &[IndexMap<String, JsonValue>],
+    key_column: Option<&str>,
+) -> PollChange {
+    let key = |row: &IndexMap<String, JsonValue>| match key_column {
+        Some(column) => row.get(column).unwrap_or(&JsonValue::Null).to_string(),
+        None => serde_json::to_string(row).unwrap_or_default(),
+    };
+
+    // Previous rows not matched yet, grouped by key so duplicate rows are
+    // matched one for one.
+    let mut remaining: IndexMap<String, Vec<&IndexMap<String, JsonValue>>> = IndexMap::new();
+    for row in previous {
+        remaining.entry(key(row)).or_default().push(row);
+    }
+
+    let mut change = PollChange {
+        watch_id: watch_id.to_string(),
+        db_alias: db_alias.to_string(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for row in current {
+        match remaining.get_mut(&key(row)).and_then(|rows| rows.pop()) {
+            Some(old) if old != row => change.changed.push(row.clone()),
+            Some(_) => {}
+            None => change.added.push(row.clone()),
+        }
+    }
+    change.removed = remaining.into_values().flatten().cloned().collect();
+    change
+}
+
+/// Execute a command against the database
+/// db is the database in sqlite:xyz.db
+/// Migrate both up and down using the migration version number
+///
+/// The migrations are those defined for `db`, see `defined_migrations`. They
+/// run on its pooled connection, locked for the duration, so in-memory
+/// databases are migrated too.
+#[command]
+pub(crate) fn migrate<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    version: usize,
+    db: &str,
+) -> Result<(), crate::Error> {
+    let conn_arc = connections.inner().get_conn(db)?;
+    let inputs = lock_mutex(
+        &connections.inner().runtime_migrations.0,
+        "RuntimeMigrations",
+    )?
+    .get(db)
+    .cloned();
+    let (migrations, _) = defined_migrations(&app, db, inputs.as_deref());
+
+    let mut conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    let _ = migrations.to_version(&mut conn, version);
+
+    Ok(())
+}
+
+/// Checks the migrations applied to `db_alias` against the ones defined for
+/// it: those set with `set_migrations` if any, otherwise those added for it
+/// with `Builder::add_migrations`.
+///
+/// Fails with `MigrationInconsistent` if the defined migrations don't apply
+/// cleanly or the database is ahead of them. Pending migrations, e.g. after a
+/// crash mid-migration, are reported and applied with `to_latest` if `repair`
+/// is set. Both run on the pooled connection, so in-memory databases are
+/// checked and repaired too.
+#[command]
+pub(crate) fn migration_repair<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    repair: bool,
+) -> Result<MigrationReport, crate::Error> {
+    let conn_arc = connections.inner().get_conn(db_alias)?;
+    let inputs = lock_mutex(
+        &connections.inner().runtime_migrations.0,
+        "RuntimeMigrations",
+    )?
+    .get(db_alias)
+    .cloned();
+    let (migrations, latest_version) = defined_migrations(&app, db_alias, inputs.as_deref());
+
+    migrations
+        .validate()
+        .map_err(|e| Error::MigrationInconsistent(format!("invalid migrations: {}", e)))?;
+
+    let mut conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    let current = migrations
+        .current_version(&conn)
+        .map_err(|e| Error::MigrationInconsistent(e.to_string()))?;
+    if let SchemaVersion::Outside(version) = current {
+        return Err(Error::MigrationInconsistent(format!(
+            "database is at version {} but only {} migrations are defined",
+            version, latest_version
+        )));
+    }
+
+    let mut current_version = usize::from(current);
+    let repaired = repair && current_version < latest_version;
+    if repaired {
+        migrations
+            .to_latest(&mut conn)
+            .map_err(|e| Error::MigrationInconsistent(e.to_string()))?;
+        current_version = latest_version;
+    }
+
+    Ok(MigrationReport {
+        current_version,
+        latest_version,
+        repaired,
+    })
+}
+
+/// Lists the migrations applied to `db_alias` and those still pending, by
+/// comparing its `user_version` with the migrations registered for it with
+/// `set_migrations`, or else the ones added for it with
+/// `Builder::add_migrations`.
+#[command]
+pub(crate) fn migration_status<R: Runtime>(
+    app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<MigrationStatus, crate::Error> {
+    let runtime = lock_mutex(
+        &connections.inner().runtime_migrations.0,
+        "RuntimeMigrations",
+    )?
+    .get(db_alias)
+    .cloned();
+    let defined: Vec<(i64, String, MigrationKind)> = match runtime {
+        Some(inputs) => inputs
+            .into_iter()
+            .map(|m| (m.version, m.description, MigrationKind::Up))
+            .collect(),
+        None => builder_migrations(&app, db_alias)
+            .unwrap_or_default()
+            .0
+            .iter()
+            .map(|m| (m.version, m.description.to_string(), m.kind))
+            .collect(),
+    };
+
+    let current_version: i64 = with_connection(connections.inner(), db_alias, None, |conn| {
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    })?;
+    let current_version = usize::try_from(current_version).unwrap_or(0);
+
+    let mut status = MigrationStatus {
+        current_version,
+        applied: Vec::new(),
+        pending: Vec::new(),
+    };
+    for (index, (version, description, direction)) in defined.into_iter().enumerate() {
+        if index < current_version {
+            status.applied.push(AppliedMigration {
+                version,
+                description,
+            });
+        } else {
+            status.pending.push(PendingMigration {
+                version,
+                description,
+                direction,
+            });
+        }
+    }
+    Ok(status)
+}
+
+/// Registers `migrations` for `db_alias` at runtime, replacing any registered
+/// before. They are ordered by version, which must run 1, 2, 3, ... without
+/// duplicates or gaps since each is applied as the next schema version.
+/// Nothing is applied until `run_migrations`.
+#[command]
+pub(crate) fn set_migrations<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+    mut migrations: Vec<MigrationInput>,
+) -> Result<(), crate::Error> {
+    migrations.sort_by_key(|m| m.version);
+    if let Some(pair) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+        return Err(Error::InvalidMigration(format!(
+            "duplicate version {}",
+            pair[0].version
+        )));
+    }
+    if let Some((expected, m)) = (1..)
+        .zip(&migrations)
+        .find(|(expected, m)| m.version != *expected)
+    {
+        return Err(Error::InvalidMigration(format!(
+            "expected version {} but got {}",
+            expected, m.version
+        )));
+    }
+
+    lock_mutex(
+        &connections.inner().runtime_migrations.0,
+        "RuntimeMigrations",
+    )?
+    .insert(db_alias.to_string(), migrations);
+    Ok(())
+}
+
+/// Applies the migrations registered for `db_alias` with `set_migrations` on
+/// its pooled connection and returns the resulting schema version (the number
+/// of migrations applied in total).
+///
+/// The pooled connection is used rather than a fresh one so that in-memory
+/// databases, which no other connection can see, are migrated too.
+#[command]
+pub(crate) fn run_migrations<R: Runtime>(
+    _app: AppHandle<R>,
+    connections: State<'_, Rusqlite2Connections<R>>,
+    db_alias: &str,
+) -> Result<usize, crate::Error> {
+    let conn_arc = connections.inner().get_conn(db_alias)?;
+    let inputs = lock_mutex(
+        &connections.inner().runtime_migrations.0,
+        "RuntimeMigrations",
+    )?
+    .get(db_alias)
+    .cloned()
+    .ok_or_else(|| Error::InvalidMigration(format!("no migrations set for {}", db_alias)))?;
+
+    let migrations = runtime_migrations(&inputs);
+    migrations
+        .validate()
+        .map_err(|e| Error::InvalidMigration(e.to_string()))?;
+
+    let mut conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+    migrations
+        .to_latest(&mut conn)
+        .map_err(|e| Error::MigrationInconsistent(e.to_string()))?;
+
+    Ok(inputs.len())
+}
+
+fn runtime_migrations(inputs: &[MigrationInput]) -> RusqliteMigrations<'_> {
+    RusqliteMigrations::new(
+        inputs
+            .iter()
+            .map(|m| match &m.down_sql {
+                Some(down_sql) => M::up(&m.up_sql).down(down_sql),
+                None => M::up(&m.up_sql),
+            })
+            .collect(),
+    )
+}
+
+/// Applies the migrations registered for `db` to `conn` up to the latest one
+/// and returns the version reached: those set with `set_migrations` if any,
+/// otherwise those added with `Builder::add_migrations`. Returns `None` when
+/// neither registered migrations for `db`.
+fn migrate_on_load<R: Runtime>(
+    app: &AppHandle<R>,
+    connections: &Rusqlite2Connections<R>,
+    db: &str,
+    conn: &mut Connection,
+) -> Result<Option<usize>, crate::Error> {
+    let inputs = lock_mutex(&connections.runtime_migrations.0, "RuntimeMigrations")?
+        .get(db)
+        .cloned();
+    let migrations = match &inputs {
+        Some(inputs) => runtime_migrations(inputs),
+        None => match builder_migrations(app, db) {
+            Some(list) => RusqliteMigrations::new(list.resolve()),
+            None => return Ok(None),
+        },
+    };
+    migrations
+        .validate()
+        .map_err(|e| Error::InvalidMigration(e.to_string()))?;
+    migrations
+        .to_latest(conn)
+        .map_err(|e| Error::MigrationInconsistent(e.to_string()))?;
+    let version = migrations
+        .current_version(conn)
+        .map_err(|e| Error::MigrationInconsistent(e.to_string()))?;
+    Ok(Some(usize::from(version)))
+}
+
+/// The migrations defined for `db` and how many there are: `inputs`, the ones
+/// set with `set_migrations`, if given, otherwise those added for it with
+/// `Builder::add_migrations`, which may be none.
+fn defined_migrations<'a, R: Runtime>(
+    app: &AppHandle<R>,
+    db: &str,
+    inputs: Option<&'a [MigrationInput]>,
+) -> (RusqliteMigrations<'a>, usize) {
+    match inputs {
+        Some(inputs) => (runtime_migrations(inputs), inputs.len()),
+        None => {
+            let list = builder_migrations(app, db).unwrap_or_default();
+            let count = list.0.len();
+            (RusqliteMigrations::new(list.resolve()), count)
+        }
+    }
+}
+
+/// The migrations added for connection string `db` with
+/// `Builder::add_migrations`, if any.
+fn builder_migrations<R: Runtime>(app: &AppHandle<R>, db: &str) -> Option<MigrationList> {
+    app.try_state::<crate::BuilderMigrations>()
+        .and_then(|builder| builder.0.get(db).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BackgroundTaskManager, BusyHandlerConfig, ConnectionManager, ConnectionPool,
+        InterruptManager, InvalidUtf8, MultiTransactionManager, PluginConfig,
+        PreparedStatementManager, RuntimeMigrations, Synchronous, TransactionManager,
+    };
+    use serde_json::json;
+    use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+    use tauri::Manager;
+
+    const MEMORY_DB_ALIAS: &str = "sqlite:::memory:";
+
+    fn setup_test_app() -> tauri::App<MockRuntime> {
+        setup_test_app_with_config(PluginConfig::default())
+    }
+
+    fn setup_test_app_with_config(config: PluginConfig) -> tauri::App<MockRuntime> {
+        let assets = noop_assets();
+        let context = mock_context(assets);
+        let app = mock_builder()
+            .build(context)
+            .expect("Failed to build mock app");
+        let handle = app.handle().clone();
+        app.manage(Rusqlite2Connections {
+            app: handle,
+            connections: ConnectionManager::default(),
+            pool: ConnectionPool::default(),
+            transactions: TransactionManager::default(),
+            multi_transactions: MultiTransactionManager::default(),
+            runtime_migrations: RuntimeMigrations::default(),
+            interrupts: InterruptManager::default(),
+            background_tasks: BackgroundTaskManager::default(),
+            prepared: PreparedStatementManager::default(),
+            config,
+        });
+        app
+    }
+
+    fn load_memory_db(app: &tauri::App<MockRuntime>) -> String {
+        load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            MEMORY_DB_ALIAS,
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database")
+    }
+
+    #[test]
+    fn load_and_close_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        {
+            let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+            let map = connections.connections.0.lock().unwrap();
+            assert!(map.contains_key(&db_alias));
+        }
+
+        let closed = close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias.clone()),
+        )
+        .expect("Close should succeed");
+        assert!(closed);
+
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        let map = connections.connections.0.lock().unwrap();
+        assert!(!map.contains_key(&db_alias));
+    }
+
+    #[test]
+    fn load_without_creating_missing_dirs() {
+        let app = setup_test_app_with_config(PluginConfig {
+            create_missing_dirs: false,
+            ..Default::default()
+        });
+
+        let result = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::missing_dir/test.db",
+            Vec::new(),
+            None,
+        );
+        match result {
+            Err(Error::Io(msg)) => assert!(msg.contains("missing_dir"), "{msg}"),
+            other => panic!("Expected Io error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn load_creates_missing_file_without_creating_dirs() {
+        let app = setup_test_app_with_config(PluginConfig {
+            create_missing_dirs: false,
+            ..Default::default()
+        });
+        let data_dir = app.path().app_data_dir().unwrap();
+        std::fs::create_dir_all(&data_dir).expect("Failed to create app data dir");
+        let db_path = format!("no-dirs-{}.db", Uuid::new_v4());
+
+        load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Missing file in an existing directory should be created");
+        assert!(data_dir.join(&db_path).exists());
+        let _ = std::fs::remove_file(data_dir.join(&db_path));
+    }
+
+    #[test]
+    fn persistent_memory_db_is_shared_across_calls() {
+        let app = setup_test_app();
+        let db_alias = load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "scratch",
+        )
+        .expect("Failed to load persistent in-memory database");
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        // A transaction gets its own connection but must see the same database.
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin transaction failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items (name) VALUES (?)",
+            vec![json!("seeded")],
+            Some(tx_id.clone()),
+            None,
+            None,
+        )
+        .expect("Insert within transaction failed");
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Commit failed");
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT name FROM items",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some(&json!("seeded")));
+    }
+
+    #[test]
+    fn memory_aliases_are_shared_and_named_ones_isolated() {
+        let app = setup_test_app();
+        let load_shared = || {
+            load(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "sqlite::memory:",
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to load in-memory database")
+        };
+        let create = |db_alias: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+                "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+        };
+
+        // `sqlite::memory:` is not a file named `memory:`.
+        let shared = load_shared();
+        let path = get_path(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &shared,
+        )
+        .unwrap();
+        assert_eq!(path, ":memory:");
+
+        // Loading it again keeps the table created through the first load.
+        create(&shared).expect("Create table failed");
+        assert_eq!(load_shared(), shared);
+        assert!(create(&shared).is_err());
+
+        let load_named = |alias: &str| {
+            load_in_memory_persistent(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+        };
+        let first = load_named("first").expect("Failed to load named database");
+        let second = load_named("second").expect("Failed to load named database");
+        create(&first).expect("Create table failed");
+        create(&second).expect("Named databases should be isolated");
+        assert!(matches!(
+            load_named("first"),
+            Err(Error::AliasAlreadyLoaded(_))
+        ));
+        create(&first).expect_err("The first database should be kept");
+    }
+
+    #[test]
+    fn execute_non_transactional_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let result = execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        );
+        assert!(result.is_ok(), "Non-TX execute failed: {:?}", result.err());
+    }
+
+    #[test]
+    fn transaction_execute_select_commit_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin transaction should succeed with empty pass");
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL)",
+            Vec::new(),
+            Some(tx_id.clone()),
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        let result = execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO users (name) VALUES (?)",
+            vec![json!("Alice")],
+            Some(tx_id.clone()),
+            None,
+            None,
+        )
+        .expect("Insert failed");
+        assert_eq!(result.rows_affected(), 1);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT id, name FROM users WHERE name = ?",
+            vec![json!("Alice")],
+            Some(tx_id.clone()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some(&json!("Alice")));
+
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Commit should succeed");
+
+        assert!(Uuid::from_str(&tx_id).is_ok(), "Invalid tx id");
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        let tx_map = connections.transactions.0.lock().unwrap();
+        assert!(!tx_map.contains_key(&tx_id));
+    }
+
+    #[test]
+    fn transaction_changes_accumulate_across_statements() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin transaction failed");
+        let run = |query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                Some(tx_id.clone()),
+                None,
+                None,
+            )
+            .expect("Execute failed")
+        };
+        let changes = || {
+            transaction_changes(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &tx_id,
+            )
+            .expect("transaction_changes failed")
+        };
+
+        run("CREATE TABLE items (id INTEGER PRIMARY KEY, qty INTEGER)");
+        assert_eq!(changes(), 0);
+        run("INSERT INTO items (qty) VALUES (1), (2)");
+        let update = run("UPDATE items SET qty = qty + 1");
+        assert_eq!(update.rows_affected(), 2);
+        assert_eq!(changes(), 4);
+
+        rollback_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Rollback failed");
+        assert!(matches!(
+            transaction_changes(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &tx_id,
+            ),
+            Err(Error::TransactionNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn rollback_transaction_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin transaction should succeed");
+
+        rollback_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Rollback should succeed");
+
+        assert!(Uuid::from_str(&tx_id).is_ok(), "Invalid tx id");
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        let tx_map = connections.transactions.0.lock().unwrap();
+        assert!(!tx_map.contains_key(&tx_id));
+    }
+
+    #[test]
+    fn uppercase_and_braced_transaction_ids_are_found() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let begin = || {
+            begin_transaction(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+            )
+            .expect("Begin transaction should succeed")
+        };
+
+        let tx_id = begin();
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 1",
+            Vec::new(),
+            Some(tx_id.to_uppercase()),
+            None,
+            None,
+        )
+        .expect("Execute with uppercase id should succeed");
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id.to_uppercase(),
+        )
+        .expect("Commit with uppercase id should succeed");
+
+        let tx_id = begin();
+        rollback_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("{{{}}}", tx_id),
+        )
+        .expect("Rollback with braced id should succeed");
+
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        assert!(connections.transactions.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rollback_all_transactions_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        for _ in 0..2 {
+            begin_transaction(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+            )
+            .expect("Begin transaction should succeed");
+        }
+
+        let rolled_back = rollback_all_transactions(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Rollback all should succeed");
+        assert_eq!(rolled_back, 2);
+
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        let tx_map = connections.transactions.0.lock().unwrap();
+        assert!(tx_map.is_empty());
+    }
+
+    #[test]
+    fn reset_rolls_back_transactions_and_closes_databases() {
+        let app = setup_test_app();
+        let first = load_memory_db(&app);
+        load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database");
+
+        begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &first,
+        )
+        .expect("Begin transaction should succeed");
+
+        let report = reset(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Reset should succeed");
+        assert_eq!(report.transactions, 1);
+        assert_eq!(report.databases, 2);
+
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        assert!(connections.transactions.0.lock().unwrap().is_empty());
+        assert!(connections.connections.0.lock().unwrap().is_empty());
+        assert!(connections.pool.0.lock().unwrap().is_empty());
+        assert!(connections.interrupts.0.lock().unwrap().is_empty());
+
+        let empty = reset(app.handle().clone(), connections).expect("Reset should succeed");
+        assert_eq!(empty.transactions, 0);
+        assert_eq!(empty.databases, 0);
+    }
+
+    #[test]
+    fn reset_rolls_back_raw_transaction_on_pooled_connection() {
+        let app = setup_test_app_with_config(PluginConfig {
+            open_transaction_on_close: OpenTransactionOnClose::Error,
+            ..Default::default()
+        });
+        let db_path = format!("reset-open-tx-{}.db", Uuid::new_v4());
+        let conn_url = format!("sqlite::{}", db_path);
+        let load_db = || {
+            load(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &conn_url,
+                Vec::new(),
+                None,
+            )
+            .expect("Load failed")
+        };
+        let run = |db_alias: &str, query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed");
+        };
+
+        let db_alias = load_db();
+        run(&db_alias, "CREATE TABLE notes (body TEXT)");
+        run(&db_alias, "BEGIN");
+        run(&db_alias, "INSERT INTO notes VALUES ('unsaved')");
+
+        let report = reset(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Reset should succeed");
+        assert_eq!(report.databases, 1);
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        assert!(connections.connections.0.lock().unwrap().is_empty());
+        assert!(connections.pool.0.lock().unwrap().is_empty());
+
+        let db_alias = load_db();
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT body FROM notes",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert!(rows.is_empty());
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias),
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(&db_path));
+    }
+
+    #[test]
+    fn migrate_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        migrate(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            0,
+            &db_alias,
+        )
+        .expect("Migrate should succeed with empty migration list");
+    }
+
+    #[test]
+    fn migrate_uses_migrations_of_the_alias() {
+        let app = setup_test_app();
+        let migration = |version: i64, sql: &'static str| crate::Migration {
+            version,
+            description: "",
+            sql,
+            down_sql: "",
+            kind: crate::MigrationKind::Up,
+        };
+        app.manage(crate::BuilderMigrations(std::collections::HashMap::from([
+            (
+                MEMORY_DB_ALIAS.to_string(),
+                MigrationList(vec![migration(1, "CREATE TABLE items (id INTEGER)")]),
+            ),
+            (
+                "sqlite::other.db".to_string(),
+                MigrationList(vec![migration(1, "CREATE TABLE other (id INTEGER)")]),
+            ),
+        ])));
+        let db_alias = load_memory_db(&app);
+
+        migrate(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            1,
+            &db_alias,
+        )
+        .expect("Migrate failed");
+        let tables = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(
+            tables,
+            vec![IndexMap::from([("name".to_string(), json!("items"))])]
+        );
+    }
+
+    #[cfg(feature = "hooks")]
+    #[test]
+    fn progress_handler_emits_events() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use tauri::Listener;
+
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let received = Arc::new(AtomicU32::new(0));
+        let counter = received.clone();
+        app.listen_any(PROGRESS_EVENT, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        set_progress_handler(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            true,
+        )
+        .expect("Enabling progress handler failed");
+
+        select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100000) SELECT count(*) AS n FROM c",
+            Vec::new(),
+            None,
+            None,
+            None, None, None,
+        )
+        .expect("Select failed");
+
+        assert!(received.load(Ordering::SeqCst) > 0);
+    }
+
+    #[test]
+    fn json_get_extracts_path() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE docs (id INTEGER PRIMARY KEY, body TEXT)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO docs (body) VALUES (?), (?)",
+            vec![json!("{\"name\": \"a\"}"), json!("{\"name\": \"b\"}")],
+            None,
+            None,
+            None,
+        )
+        .expect("Insert failed");
+
+        let values = json_get(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "docs",
+            "body",
+            "$.name",
+            Some("id > ?".to_string()),
+            vec![json!(1)],
+        )
+        .expect("json_get failed");
+        assert_eq!(values, vec![json!("b")]);
+
+        let invalid = json_get(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "docs; DROP TABLE docs",
+            "body",
+            "$.name",
+            None,
+            Vec::new(),
+        );
+        assert!(matches!(invalid, Err(Error::InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn copy_table_between_aliases() {
+        let app = setup_test_app();
+        let src = load_memory_db(&app);
+        let dest = load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dest",
+        )
+        .expect("Failed to load destination database");
+
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            "INSERT INTO items (name) VALUES ('a'), ('b')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &src,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let missing = copy_table(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &src,
+            &dest,
+            "items",
+            false,
+        );
+        assert!(matches!(missing, Err(Error::TableNotFound(_))));
+
+        let copied = copy_table(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &src,
+            &dest,
+            "items",
+            true,
+        )
+        .expect("copy_table failed");
+        assert_eq!(copied, 2);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &dest,
+            "SELECT name FROM items ORDER BY id",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get("name"), Some(&json!("b")));
+    }
+
+    #[test]
+    fn close_releases_pooled_connection() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let weak = Arc::downgrade(
+            &app.state::<Rusqlite2Connections<MockRuntime>>()
+                .get_conn(&db_alias)
+                .expect("Pooled connection missing"),
+        );
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias.clone()),
+        )
+        .expect("Close failed");
+
+        assert!(
+            weak.upgrade().is_none(),
+            "Pooled connection was not released"
+        );
+        assert!(app
+            .state::<Rusqlite2Connections<MockRuntime>>()
+            .get_conn(&db_alias)
+            .is_err());
+    }
+
+    #[test]
+    fn close_with_open_transaction_errors_or_commits() {
+        for policy in [
+            OpenTransactionOnClose::Error,
+            OpenTransactionOnClose::Commit,
+        ] {
+            let app = setup_test_app_with_config(PluginConfig {
+                open_transaction_on_close: policy,
+                ..Default::default()
+            });
+            let db_path = format!("close-open-tx-{}.db", Uuid::new_v4());
+            let conn_url = format!("sqlite::{}", db_path);
+            let load_db = || {
+                load(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    &conn_url,
+                    Vec::new(),
+                    None,
+                )
+                .expect("Load failed")
+            };
+            let run = |db_alias: &str, query: &str| {
+                execute(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    db_alias,
+                    query,
+                    Vec::new(),
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Execute failed");
+            };
+            let close_db = |db_alias: &str| {
+                close(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    Some(db_alias.to_string()),
+                )
+            };
+
+            let db_alias = load_db();
+            run(&db_alias, "CREATE TABLE notes (body TEXT)");
+            run(&db_alias, "BEGIN");
+            run(&db_alias, "INSERT INTO notes VALUES ('unsaved')");
+
+            let result = close_db(&db_alias);
+            if policy == OpenTransactionOnClose::Error {
+                assert!(
+                    matches!(result, Err(Error::OpenTransaction(ref alias)) if *alias == db_alias)
+                );
+                // Still loaded with the transaction open.
+                run(&db_alias, "COMMIT");
+                close_db(&db_alias).expect("Close failed");
+            } else {
+                result.expect("Close failed");
+            }
+
+            let db_alias = load_db();
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT body FROM notes",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            assert_eq!(rows[0]["body"], json!("unsaved"));
+            close_db(&db_alias).expect("Close failed");
+            let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(&db_path));
+        }
+    }
+
+    #[test]
+    fn alias_config_reports_table_prefix() {
+        let app = setup_test_app();
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            MEMORY_DB_ALIAS,
+            Vec::new(),
+            Some(LoadOptions {
+                table_prefix: Some("tenant_a_".to_string()),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load in-memory database");
+
+        let config = get_alias_config(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("get_alias_config failed");
+        assert_eq!(config.table_prefix.as_deref(), Some("tenant_a_"));
+    }
+
+    #[test]
+    fn busy_commit_keeps_transaction_open() {
+        let app = setup_test_app_with_config(PluginConfig {
+            busy_retries: 1,
+            ..Default::default()
+        });
+        let db_path = format!("busy-{}.db", Uuid::new_v4());
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        // Hold a read lock on the pooled connection so COMMIT cannot escalate.
+        let reader = app
+            .state::<Rusqlite2Connections<MockRuntime>>()
+            .get_conn(&db_alias)
+            .expect("Pooled connection missing");
+        reader
+            .lock()
+            .unwrap()
+            .execute_batch("BEGIN; SELECT count(*) FROM items;")
+            .expect("Read transaction failed");
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        {
+            let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+            let tx_map = connections.transactions.0.lock().unwrap();
+            let conn = tx_map.get(&tx_id).unwrap();
+            conn.lock().unwrap().busy_timeout(Duration::ZERO).unwrap();
+        }
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items DEFAULT VALUES",
+            Vec::new(),
+            Some(tx_id.clone()),
+            None,
+            None,
+        )
+        .expect("Insert within transaction failed");
+
+        let busy = commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        );
+        assert!(matches!(busy, Err(Error::DatabaseLocked(_))), "{busy:?}");
+
+        reader.lock().unwrap().execute_batch("COMMIT").unwrap();
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Retried commit failed");
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(db_path));
+    }
+
+    #[test]
+    fn select_bind_binds_struct_fields_by_name() {
+        #[derive(serde::Serialize)]
+        struct Filter {
+            name: Option<String>,
+            min_id: i64,
+        }
+
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO items (name) VALUES ('a'), (NULL), (NULL)",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let rows = app
+            .state::<Rusqlite2Connections<MockRuntime>>()
+            .select_bind(
+                &db_alias,
+                "SELECT id FROM items WHERE name IS :name AND id >= :min_id",
+                Filter {
+                    name: None,
+                    min_id: 3,
+                },
+            )
+            .expect("select_bind failed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("id"), Some(&json!(3)));
+    }
+
+    #[test]
+    fn schema_version_changes_with_schema() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let version = |tx_id: Option<String>| {
+            schema_version(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                tx_id,
+            )
+            .expect("schema_version failed")
+        };
+
+        let before = version(None);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+        assert!(version(None) > before);
+    }
+
+    #[test]
+    fn sequential_transaction_ids() {
+        let app = setup_test_app_with_config(PluginConfig {
+            transaction_id_style: TransactionIdStyle::Sequential,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+
+        let begin = || {
+            begin_transaction(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+            )
+            .expect("Begin transaction should succeed")
+        };
+        let first = begin();
+        let second = begin();
+        assert_eq!((first.as_str(), second.as_str()), ("1", "2"));
+
+        select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 1",
+            Vec::new(),
+            Some(second.clone()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select within sequential transaction failed");
+
+        // A wrapped-around counter skips ids that are still open.
+        app.state::<Rusqlite2Connections<MockRuntime>>()
+            .transactions
+            .1
+            .store(u64::MAX, Ordering::Relaxed);
+        let wrapped = begin();
+        assert_eq!(begin(), "3");
+        assert_eq!(wrapped, "0");
+
+        let invalid = commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "not-a-number",
+        );
+        assert!(matches!(invalid, Err(Error::InvalidUuid(_))));
+    }
+
+    #[test]
+    fn load_without_creating_missing_file() {
+        let app = setup_test_app();
+        let existing = format!("exists-{}.db", Uuid::new_v4());
+        load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", existing),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+
+        let missing = format!("missing-{}.db", Uuid::new_v4());
+        let result = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", missing),
+            Vec::new(),
+            Some(LoadOptions {
+                create_missing: Some(false),
+                ..Default::default()
+            }),
+        );
+        assert!(matches!(result, Err(Error::CannotOpen(_))), "{result:?}");
+
+        let data_dir = app.path().app_data_dir().unwrap();
+        assert!(!data_dir.join(&missing).exists());
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(data_dir.join(existing));
+    }
+
+    #[test]
+    fn delete_by_ids_deletes_listed_rows() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO items (name) VALUES ('a'), ('b'), ('c')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let delete = |ids: Vec<JsonValue>| {
+            delete_by_ids(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "items",
+                "id",
+                ids,
+                None,
+            )
+        };
+        assert_eq!(delete(Vec::new()).expect("Empty delete failed"), 0);
+        assert_eq!(
+            delete(vec![json!(1), json!(3), json!(9)]).expect("Delete failed"),
+            2
+        );
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT name FROM items",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("name"), Some(&json!("b")));
+    }
+
+    #[test]
+    #[cfg(feature = "limits")]
+    fn delete_by_ids_batches_past_the_variable_limit() {
+        let app = setup_test_app_with_config(PluginConfig {
+            limits: std::collections::HashMap::from([(SqliteLimit::VariableNumber, 10)]),
+            ..PluginConfig::default()
+        });
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 30) INSERT INTO items SELECT i FROM n",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let deleted = delete_by_ids(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "items",
+            "id",
+            (1..=25).map(|id| json!(id)).collect(),
+            None,
+        )
+        .expect("Delete failed");
+        assert_eq!(deleted, 25);
+
+        let missing = delete_by_ids(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "missing",
+            "id",
+            (1..=25).map(|id| json!(id)).collect(),
+            None,
+        );
+        assert!(matches!(
+            missing,
+            Err(Error::QueryFailed {
+                params_count: 10,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn select_json_matches_select() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, \"na\"\"me\" TEXT, price REAL, data BLOB)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setup failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items VALUES (1, 'quote \" and \\', 1.5, x'0102'), (2, NULL, NULL, NULL)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setup failed");
+
+        let query = "SELECT * FROM items WHERE id >= ? ORDER BY id";
+        let json = select_json(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            query,
+            vec![json!(1)],
+            None,
+        )
+        .expect("select_json failed");
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            query,
+            vec![json!(1)],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&json).unwrap(),
+            serde_json::to_value(rows).unwrap()
+        );
+
+        let empty = select_json(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT * FROM items WHERE id > 2",
+            Vec::new(),
+            None,
+        )
+        .expect("select_json failed");
+        assert_eq!(empty, "[]");
+    }
+
+    #[test]
+    fn select_columnar_transposes_rows() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let columnar = |query: &str| {
+            select_columnar(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![json!(3)],
+                None,
+            )
+            .expect("select_columnar failed")
+        };
+
+        let result = columnar(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?) \
+             SELECT i AS day, i * 1.5 AS total, NULLIF(i, 2) AS note FROM n",
+        );
+        assert_eq!(result.columns, ["day", "total", "note"]);
+        assert_eq!(
+            serde_json::to_value(&result.data).unwrap(),
+            json!({
+                "day": [1, 2, 3],
+                "total": [1.5, 3.0, 4.5],
+                "note": [1, null, 3],
+            })
+        );
+
+        let empty = columnar("SELECT 1 AS day, 2 AS total WHERE 0 AND ?");
+        assert_eq!(empty.columns, ["day", "total"]);
+        assert_eq!(
+            serde_json::to_value(&empty.data).unwrap(),
+            json!({ "day": [], "total": [] })
+        );
+    }
+
+    /// Checks that `select_json` is no slower than `select` serialized for IPC
+    /// on 100k rows, comparing the median of several interleaved runs of each
+    /// with some allowance for noise. Prints both timings with
+    /// `cargo test select_json_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn select_json_benchmark() {
+        const RUNS: usize = 7;
+
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items AS WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 100000) SELECT i AS id, 'item ' || i AS name, i * 0.5 AS price FROM n",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setup failed");
+        let query = "SELECT * FROM items";
+        let median = |mut timings: Vec<Duration>| {
+            timings.sort();
+            timings[timings.len() / 2]
+        };
+
+        let mut select_timings = Vec::with_capacity(RUNS);
+        let mut json_timings = Vec::with_capacity(RUNS);
+        for _ in 0..RUNS {
+            let started = Instant::now();
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            let serialized = serde_json::to_string(&rows).unwrap();
+            select_timings.push(started.elapsed());
+            drop(rows);
+
+            let started = Instant::now();
+            let json = select_json(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+            )
+            .expect("select_json failed");
+            json_timings.push(started.elapsed());
+            assert_eq!(json, serialized);
+        }
+
+        let select_elapsed = median(select_timings);
+        let json_elapsed = median(json_timings);
+        println!(
+            "median of {} runs: select + serialize {:?}, select_json {:?}",
+            RUNS, select_elapsed, json_elapsed
+        );
+        assert!(
+            json_elapsed <= select_elapsed * 5 / 4,
+            "select_json took {:?}, select + serialize {:?}",
+            json_elapsed,
+            select_elapsed
+        );
+    }
+
+    #[test]
+    fn insert_builds_statement_from_column_map() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT 'anon', email TEXT UNIQUE)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        let row = |pairs: &[(&str, JsonValue)]| -> IndexMap<String, JsonValue> {
+            pairs
+                .iter()
+                .map(|(column, value)| (column.to_string(), value.clone()))
+                .collect()
+        };
+        let insert_row = |table: &str, values, on_conflict| {
+            insert(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                table,
+                values,
+                on_conflict,
+                None,
+            )
+        };
+
+        // Bound in the map's order, whatever the table's column order.
+        let bob = row(&[("email", json!("bob@example.com")), ("name", json!("Bob"))]);
+        assert_eq!(insert_row("users", bob.clone(), None).unwrap(), Some(1));
+        assert_eq!(insert_row("users", IndexMap::new(), None).unwrap(), Some(2));
+        assert!(insert_row("users", bob.clone(), None).is_err());
+        assert_eq!(
+            insert_row("users", bob, Some(OnConflict::Ignore)).unwrap(),
+            None
+        );
+        let renamed = row(&[
+            ("email", json!("bob@example.com")),
+            ("name", json!("Robert")),
+        ]);
+        assert_eq!(
+            insert_row("users", renamed, Some(OnConflict::Replace)).unwrap(),
+            Some(3)
+        );
+        assert!(matches!(
+            insert_row(
+                "users",
+                row(&[("name\"; DROP TABLE users", json!("x"))]),
+                None
+            ),
+            Err(Error::InvalidIdentifier(_))
+        ));
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT id, name, email FROM users ORDER BY id",
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(
+            json!(rows),
+            json!([
+                { "id": 2, "name": "anon", "email": null },
+                { "id": 3, "name": "Robert", "email": "bob@example.com" },
+            ])
+        );
+    }
+
+    #[test]
+    fn update_builds_statement_and_guards_full_table() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, team TEXT, active INTEGER)",
+            "INSERT INTO users VALUES (1, 'a', 'x', 1), (2, 'b', 'x', 1), (3, 'c', 'y', 1)",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let row = |pairs: &[(&str, JsonValue)]| -> IndexMap<String, JsonValue> {
+            pairs
+                .iter()
+                .map(|(column, value)| (column.to_string(), value.clone()))
+                .collect()
+        };
+        let update_rows = |set, where_eq, allow_full_table| {
+            update(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "users",
+                set,
+                where_eq,
+                allow_full_table,
+                None,
+            )
+        };
+        let names = || {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT name, active FROM users ORDER BY id",
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")
+        };
+
+        let updated = update_rows(
+            row(&[("name", json!("B")), ("active", json!(0))]),
+            row(&[("team", json!("x")), ("id", json!(2))]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(updated, 1);
+
+        let result = update_rows(row(&[("active", json!(0))]), IndexMap::new(), None);
+        assert!(matches!(result, Err(Error::FullTableUpdate(_))));
+        assert_eq!(
+            json!(names()),
+            json!([
+                { "name": "a", "active": 1 },
+                { "name": "B", "active": 0 },
+                { "name": "c", "active": 1 },
+            ])
+        );
+
+        let updated =
+            update_rows(row(&[("active", json!(0))]), IndexMap::new(), Some(true)).unwrap();
+        assert_eq!(updated, 3);
+        assert_eq!(
+            update_rows(IndexMap::new(), row(&[("id", json!(1))]), None).unwrap(),
+            0
+        );
+        assert!(matches!(
+            update_rows(
+                row(&[("name; --", json!("x"))]),
+                row(&[("id", json!(1))]),
+                None
+            ),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn select_one_and_exists_where_stop_at_first_row() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO items (name) VALUES ('a'), ('b'), ('b')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let select_first = |query: &str, values: Vec<JsonValue>| {
+            select_one(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                values,
+                None,
+            )
+            .expect("select_one failed")
+        };
+        let row = select_first(
+            "SELECT id FROM items WHERE name = ? ORDER BY id",
+            vec![json!("b")],
+        );
+        assert_eq!(row.map(|row| row["id"].clone()), Some(json!(2)));
+        assert_eq!(
+            select_first("SELECT id FROM items WHERE name = ?", vec![json!("z")]),
+            None
+        );
+
+        let exists = |table: &str, where_clause: &str, values: Vec<JsonValue>| {
+            exists_where(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                table,
+                where_clause,
+                values,
+                None,
+            )
+        };
+        assert!(exists("items", "name = ?", vec![json!("b")]).unwrap());
+        assert!(!exists("items", "name = ?", vec![json!("z")]).unwrap());
+        assert!(exists("items", "", Vec::new()).unwrap());
+        assert!(matches!(
+            exists("items; DROP TABLE items", "", Vec::new()),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn blob_size_returns_length_without_the_bytes() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE attachments (id INTEGER PRIMARY KEY, data BLOB)",
+            "INSERT INTO attachments (data) VALUES (zeroblob(70000)), (NULL)",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let size = |table: &str, rowid: i64| {
+            blob_size(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                table,
+                "data",
+                rowid,
+                None,
+            )
+        };
+        assert_eq!(size("attachments", 1).unwrap(), Some(70000));
+        assert_eq!(size("attachments", 2).unwrap(), None);
+        assert_eq!(size("attachments", 3).unwrap(), None);
+        assert!(matches!(
+            size("attachments; DROP TABLE attachments", 1),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn select_count_counts_rows_of_query() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO items (name) VALUES ('a'), ('b'), ('b')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let count = |query: &str, values: Vec<JsonValue>| {
+            select_count(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                values,
+                None,
+            )
+        };
+        assert_eq!(count("SELECT * FROM items", Vec::new()).unwrap(), 3);
+        assert_eq!(
+            count(
+                "SELECT name FROM items WHERE name = ? ORDER BY id -- page",
+                vec![json!("b")]
+            )
+            .unwrap(),
+            2
+        );
+        assert_eq!(
+            count("SELECT DISTINCT name FROM items", Vec::new()).unwrap(),
+            2
+        );
+        assert!(matches!(
+            count("SELECT * FROM items LIMIT 1", Vec::new()),
+            Err(Error::LimitAlreadyPresent)
+        ));
+    }
+
+    #[test]
+    fn prepared_statements_run_by_handle_until_finalized() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setup failed");
+
+        let prep = |sql: &str| {
+            prepare(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                sql,
+            )
+        };
+        let exec = |handle: &str, values: Vec<JsonValue>| {
+            run(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                handle,
+                values,
+            )
+        };
+
+        let insert = prep("INSERT INTO items (name) VALUES (?)").expect("prepare failed");
+        for name in ["a", "b"] {
+            let result = exec(&insert, vec![json!(name)]).expect("run failed");
+            assert_eq!(result.rows_affected, 1);
+            assert!(result.rows.is_empty());
+        }
+        let result = exec(&insert, vec![json!("c")]).expect("run failed");
+        assert_eq!(result.last_insert_id, 3);
+
+        let lookup = prep("SELECT id FROM items WHERE name = ?").expect("prepare failed");
+        let result = exec(&lookup, vec![json!("b")]).expect("run failed");
+        assert_eq!(result.rows_affected, 0);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0]["id"], json!(2));
+
+        assert!(matches!(
+            prep("SELECT * FROM missing"),
+            Err(Error::QueryFailed { .. })
+        ));
+
+        let fin = |handle: &str| {
+            finalize(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                handle,
+            )
+            .unwrap()
+        };
+        assert!(fin(&lookup));
+        assert!(!fin(&lookup));
+        assert!(matches!(
+            exec(&lookup, vec![json!("b")]),
+            Err(Error::StatementNotFound(_))
+        ));
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias.clone()),
+        )
+        .expect("close failed");
+        assert!(matches!(
+            exec(&insert, vec![json!("d")]),
+            Err(Error::StatementNotFound(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "limits")]
+    fn set_limit_applies_to_current_and_new_connections() {
+        let app = setup_test_app_with_config(PluginConfig {
+            limits: std::collections::HashMap::from([(SqliteLimit::VariableNumber, 10)]),
+            ..PluginConfig::default()
+        });
+        let db_alias = load_memory_db(&app);
+        let query = "SELECT ?1 + ?11 AS total";
+        let values = || {
+            let mut values = vec![json!(1); 11];
+            values[10] = json!(2);
+            values
+        };
+        let run = |tx_id: Option<String>| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                values(),
+                tx_id,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        assert!(run(None).is_err());
+
+        let limit = set_limit(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            SqliteLimit::VariableNumber,
+            100,
+        )
+        .expect("set_limit failed");
+        assert_eq!(limit, 100);
+        assert_eq!(run(None).expect("Select failed")[0]["total"], json!(3));
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin transaction failed");
+        assert_eq!(
+            run(Some(tx_id)).expect("Select in transaction failed")[0]["total"],
+            json!(3)
+        );
+    }
+
+    #[test]
+    fn incremental_vacuum_frees_pages_gradually() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let vacuum = |pages: Option<u32>| {
+            incremental_vacuum(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                pages,
+            )
+        };
+        assert!(matches!(vacuum(None), Err(Error::AutoVacuumNotIncremental)));
+
+        for query in [
+            "PRAGMA auto_vacuum = INCREMENTAL",
+            "VACUUM",
+            "CREATE TABLE blobs (data BLOB)",
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 20) INSERT INTO blobs SELECT zeroblob(4096) FROM n",
+            "DELETE FROM blobs",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let partial = vacuum(Some(2)).expect("incremental_vacuum failed");
+        assert!(partial.freelist_before > 2);
+        assert_eq!(partial.freelist_after, partial.freelist_before - 2);
+        let rest = vacuum(None).expect("incremental_vacuum failed");
+        assert_eq!(rest.freelist_before, partial.freelist_after);
+        assert_eq!(rest.freelist_after, 0);
+    }
+
+    #[test]
+    fn analyze_fills_query_stats() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "CREATE INDEX idx_items_name ON items (name)",
+            "INSERT INTO items (name) VALUES ('a'), ('b'), ('b')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let stats = || {
+            query_stats(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+            )
+            .expect("query_stats failed")
+        };
+        assert!(stats().stat1.is_empty());
+
+        let run_analyze = |target: Option<&str>| {
+            analyze(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                target.map(str::to_string),
+            )
+        };
+        run_analyze(Some("items")).expect("analyze failed");
+        let stat1 = stats().stat1;
+        assert_eq!(stat1.len(), 1);
+        assert_eq!(stat1[0]["tbl"], json!("items"));
+        assert_eq!(stat1[0]["idx"], json!("idx_items_name"));
+        assert_eq!(stat1[0]["stat"], json!("3 2"));
+
+        run_analyze(None).expect("analyze failed");
+        assert!(matches!(
+            run_analyze(Some("items; DROP TABLE items")),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn failed_query_reports_context() {
+        let app = setup_test_app_with_config(PluginConfig {
+            include_sql_in_errors: true,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+
+        let result = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT * FROM missing WHERE id = ?",
+            vec![json!(1)],
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        match result {
+            Err(Error::QueryFailed {
+                sql, params_count, ..
+            }) => {
+                assert_eq!(sql.as_deref(), Some("SELECT * FROM missing WHERE id = ?"));
+                assert_eq!(params_count, 1);
+            }
+            other => panic!("Expected QueryFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn load_with_unregistered_vfs_fails() {
+        let app = setup_test_app();
+        let result = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            MEMORY_DB_ALIAS,
+            Vec::new(),
+            Some(LoadOptions {
+                vfs: Some("no-such-vfs".to_string()),
+                ..Default::default()
+            }),
+        );
+        assert!(matches!(result, Err(Error::VfsNotFound(_))), "{result:?}");
+    }
+
+    #[test]
+    fn read_transaction_rejects_execute() {
+        let app = setup_test_app();
+        let db_alias = load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "reports",
+        )
+        .expect("Failed to load persistent in-memory database");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        let tx_id = begin_read_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin read transaction failed");
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT count(*) AS n FROM items",
+            Vec::new(),
+            Some(tx_id.clone()),
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select within read transaction failed");
+        assert_eq!(rows[0].get("n"), Some(&json!(0)));
+
+        let result = execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items DEFAULT VALUES",
+            Vec::new(),
+            Some(tx_id.clone()),
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(Error::ReadOnlyTransaction)));
+
+        rollback_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Rollback failed");
+    }
+
+    #[test]
+    fn select_normalizes_column_case() {
+        let app = setup_test_app_with_config(PluginConfig {
+            column_case: ColumnCase::CamelCase,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 1 AS user_id, 2 AS createdAt",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        let keys: Vec<&String> = rows[0].keys().collect();
+        assert_eq!(keys, ["userId", "createdAt"]);
+
+        let collision = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 1 AS userId, 2 AS user_id",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(collision, Err(Error::ColumnNameCollision(_))));
+
+        assert_eq!(
+            convert::normalize_column_names(vec!["userId", "HTTPServer"], ColumnCase::SnakeCase)
+                .unwrap(),
+            ["user_id", "http_server"]
+        );
+    }
+
+    #[test]
+    fn migration_repair_applies_pending_and_detects_inconsistency() {
+        let app = setup_test_app();
+        app.manage(crate::BuilderMigrations(std::collections::HashMap::from([
+            (
+                "sqlite::memory:".to_string(),
+                MigrationList(vec![crate::Migration {
+                    version: 1,
+                    description: "create_items",
+                    sql: "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+                    down_sql: "DROP TABLE items",
+                    kind: crate::MigrationKind::Up,
+                }]),
+            ),
+        ])));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database");
+
+        let repair = |repair: bool| {
+            migration_repair(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                repair,
+            )
+        };
+
+        let report = repair(false).expect("Check failed");
+        assert_eq!((report.current_version, report.latest_version), (0, 1));
+        assert!(!report.repaired);
+
+        let report = repair(true).expect("Repair failed");
+        assert_eq!(report.current_version, 1);
+        assert!(report.repaired);
+        // The loaded database itself was migrated, not a throwaway copy.
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items (id) VALUES (1)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Repaired schema not visible");
+        let report = repair(false).expect("Check failed");
+        assert_eq!((report.current_version, report.latest_version), (1, 1));
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "PRAGMA user_version = 5",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setting user_version failed");
+        assert!(matches!(repair(true), Err(Error::MigrationInconsistent(_))));
+    }
+
+    #[test]
+    fn migration_repair_uses_runtime_migrations() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            vec![MigrationInput {
+                version: 1,
+                description: "create notes".to_string(),
+                up_sql: "CREATE TABLE notes (body TEXT)".to_string(),
+                down_sql: None,
+            }],
+        )
+        .expect("Set migrations failed");
+        run_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Run migrations failed");
+
+        let report = migration_repair(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            false,
+        )
+        .expect("A migrated database should check out");
+        assert_eq!((report.current_version, report.latest_version), (1, 1));
+        assert!(!report.repaired);
+    }
+
+    #[test]
+    fn migration_status_lists_applied_and_pending() {
+        let app = setup_test_app();
+        let migration = |version: i64, description: &'static str| crate::Migration {
+            version,
+            description,
+            sql: "SELECT 1",
+            down_sql: "",
+            kind: crate::MigrationKind::Up,
+        };
+        app.manage(crate::BuilderMigrations(std::collections::HashMap::from([
+            (
+                MEMORY_DB_ALIAS.to_string(),
+                MigrationList(vec![
+                    migration(1, "create_items"),
+                    migration(2, "add_price"),
+                    migration(3, "add_index"),
+                ]),
+            ),
+            (
+                "sqlite::other.db".to_string(),
+                MigrationList(vec![migration(1, "other_database")]),
+            ),
+        ])));
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "PRAGMA user_version = 1",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Setting user_version failed");
+
+        let status = migration_status(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("migration_status failed");
+        assert_eq!(status.current_version, 1);
+        assert_eq!(status.applied.len(), 1);
+        assert_eq!(status.applied[0].description, "create_items");
+        let pending: Vec<i64> = status.pending.iter().map(|m| m.version).collect();
+        assert_eq!(pending, vec![2, 3]);
+        assert_eq!(
+            serde_json::to_value(&status.pending[0]).unwrap(),
+            json!({ "version": 2, "description": "add_price", "direction": "up" })
+        );
+
+        // A database without migrations of its own reports none.
+        load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database");
+        let status = migration_status(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+        )
+        .expect("migration_status failed");
+        assert!(status.applied.is_empty() && status.pending.is_empty());
+    }
+
+    #[test]
+    fn typed_string_params_bind_exact_numbers() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT ? AS big, ? AS real",
+            vec![
+                json!({ "$i64": "9007199254740993" }),
+                json!({ "$f64": "1.5" }),
+            ],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows[0].get("big"), Some(&json!(9007199254740993_i64)));
+        assert_eq!(rows[0].get("real"), Some(&json!(1.5)));
+
+        let invalid = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT ? AS big",
+            vec![json!({ "$i64": "12abc" })],
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        match invalid {
+            Err(Error::ValueConversionError(msg)) => assert!(msg.contains("12abc"), "{msg}"),
+            other => panic!("Expected ValueConversionError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reindex_rebuilds_all_or_target() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE INDEX idx_items_name ON items(name)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+
+        for target in [
+            None,
+            Some("idx_items_name".to_string()),
+            Some("items".to_string()),
+        ] {
+            reindex(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                target,
+            )
+            .expect("Reindex failed");
+        }
+
+        let invalid = reindex(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            Some("items; DROP TABLE items".to_string()),
+        );
+        assert!(matches!(invalid, Err(Error::InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn envelope_params_round_trip_each_storage_class() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let cases = [
+            (json!({ "type": "null" }), json!(null), "null"),
+            (
+                json!({ "type": "integer", "data": "9007199254740993" }),
+                json!(9007199254740993_i64),
+                "integer",
+            ),
+            (json!({ "type": "real", "data": 2.5 }), json!(2.5), "real"),
+            (
+                json!({ "type": "text", "data": "\"quoted\"" }),
+                json!("\"quoted\""),
+                "text",
+            ),
+            (
+                json!({ "type": "blob", "data": "AAEC/w==" }),
+                json!("AAEC/w=="),
+                "blob",
+            ),
+            (
+                json!({ "type": "json", "data": [1, "a", { "b": null }] }),
+                json!(r#"[1,"a",{"b":null}]"#),
+                "text",
+            ),
+            (
+                json!({ "type": "json", "data": { "tags": ["x"] } }),
+                json!(r#"{"tags":["x"]}"#),
+                "text",
+            ),
+        ];
+        for (param, expected, storage_class) in cases {
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT ?1 AS value, typeof(?1) AS kind",
+                vec![param],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            assert_eq!(rows[0].get("value"), Some(&expected));
+            assert_eq!(rows[0].get("kind"), Some(&json!(storage_class)));
+        }
+
+        for param in [
+            json!({ "type": "blob", "data": "not base64!" }),
+            json!({ "type": "text", "data": 1 }),
+            json!({ "type": "date", "data": "2024-01-01" }),
+            json!([1, 2]),
+            json!({ "tags": ["x"] }),
+        ] {
+            let result = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT ? AS value",
+                vec![param],
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            assert!(matches!(result, Err(Error::ValueConversionError(_))));
+        }
+    }
+
+    #[test]
+    fn vacuum_into_writes_compacted_copy_once() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let dest_path = "vacuum_into_test.db";
+        let dest = app.path().app_data_dir().unwrap().join(dest_path);
+        let _ = std::fs::remove_file(&dest);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+
+        let size = vacuum_into(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            dest_path,
+        )
+        .expect("Vacuum into failed");
+        assert_eq!(size, std::fs::metadata(&dest).unwrap().len());
+        assert!(size > 0);
+
+        let copy = Connection::open(&dest).unwrap();
+        let tables: i64 = copy
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'items'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 1);
+        drop(copy);
+
+        let again = vacuum_into(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            dest_path,
+        );
+        assert!(matches!(again, Err(Error::DestinationExists(_))));
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn drop_table_reports_whether_table_existed() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+
+        let drop = |table: &str, if_exists: bool| {
+            drop_table(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                table,
+                if_exists,
+                None,
+            )
+        };
+        assert!(drop("items", true).expect("Drop failed"));
+        assert!(!drop("items", true).expect("Drop if exists failed"));
+        assert!(drop("items", false).is_err());
+        assert!(matches!(
+            drop("items\"; DROP TABLE x; --", true),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn synchronous_level_applies_from_config_and_load_options() {
+        let synchronous = |app: &tauri::App<MockRuntime>, db_alias: &str| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+                "PRAGMA synchronous",
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]
+                .get("synchronous")
+                .cloned()
+        };
+
+        let app = setup_test_app_with_config(PluginConfig {
+            synchronous: Some(Synchronous::Normal),
+            ..PluginConfig::default()
+        });
+        let db_alias = load_memory_db(&app);
+        assert_eq!(synchronous(&app, &db_alias), Some(json!(1)));
+
+        // A loaded in-memory alias is returned as is, so use another one.
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            Some(LoadOptions {
+                synchronous: Some(Synchronous::Off),
+                ..Default::default()
+            }),
+        )
+        .expect("Load failed");
+        assert_eq!(synchronous(&app, &db_alias), Some(json!(0)));
+
+        let invalid = serde_json::from_value::<LoadOptions>(json!({ "synchronous": "fast" }));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn async_queries_run_concurrently_on_separate_aliases() {
+        let app = setup_test_app();
+        for alias in ["async_a", "async_b"] {
+            load_in_memory_persistent(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+            .expect("Load failed");
+        }
+
+        let heavy = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 200000) SELECT count(*) AS total FROM n";
+        let (rows_a, rows_b, inserted) = tauri::async_runtime::block_on(async {
+            let a = tauri::async_runtime::spawn(select_async(
+                app.handle().clone(),
+                "async_a".to_string(),
+                heavy.to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            ));
+            let b = tauri::async_runtime::spawn(select_async(
+                app.handle().clone(),
+                "async_b".to_string(),
+                heavy.to_string(),
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            ));
+            let inserted = execute_async(
+                app.handle().clone(),
+                "async_b".to_string(),
+                "CREATE TABLE t (id INTEGER PRIMARY KEY)".to_string(),
+                vec![],
+                None,
+            )
+            .await;
+            (a.await.unwrap(), b.await.unwrap(), inserted)
+        });
+
+        for rows in [rows_a, rows_b] {
+            assert_eq!(
+                rows.expect("Select failed")[0].get("total"),
+                Some(&json!(200000))
+            );
+        }
+        assert_eq!(inserted.expect("Execute failed"), ExecuteResult::Ddl);
+
+        let missing = tauri::async_runtime::block_on(select_async(
+            app.handle().clone(),
+            "missing".to_string(),
+            "SELECT 1".to_string(),
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+        ));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn clone_database_loads_copy_under_new_alias() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let dest_path = "clone_database_test.db";
+        let dest = app.path().app_data_dir().unwrap().join(dest_path);
+        let _ = std::fs::remove_file(&dest);
+
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            "INSERT INTO items (name) VALUES ('original')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        let cloned = clone_database(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "copy",
+            dest_path,
+        )
+        .expect("Clone failed");
+        assert_eq!(cloned, "copy");
+        assert!(dest.exists());
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO items (name) VALUES ('after clone')",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Source no longer usable");
+        let count = |alias: &str| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+                "SELECT count(*) AS n FROM items",
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]
+                .get("n")
+                .cloned()
+        };
+        assert_eq!(count(&db_alias), Some(json!(2)));
+        assert_eq!(count("copy"), Some(json!(1)));
+
+        let again = clone_database(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "copy",
+            "clone_database_other.db",
+        );
+        assert!(matches!(again, Err(Error::AliasAlreadyLoaded(_))));
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn reads_on_same_alias_see_preceding_writes() {
+        let app = setup_test_app();
+        let db_path = "read_your_writes_test.db";
+        let db_url = format!("sqlite::{}", db_path);
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_url,
+            Vec::new(),
+            None,
+        )
+        .expect("Load failed");
+        for query in [
+            "PRAGMA journal_mode=WAL",
+            "DROP TABLE IF EXISTS counter",
+            "CREATE TABLE counter (n INTEGER)",
+        ] {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+
+        for n in 1..=50 {
+            tauri::async_runtime::block_on(execute_async(
+                app.handle().clone(),
+                db_alias.clone(),
+                "INSERT INTO counter (n) VALUES (?)".to_string(),
+                vec![json!(n)],
+                None,
+            ))
+            .expect("Insert failed");
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT max(n) AS n FROM counter",
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            assert_eq!(rows[0].get("n"), Some(&json!(n)));
+        }
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let data_dir = app.path().app_data_dir().unwrap();
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(data_dir.join(format!("{}{}", db_path, suffix)));
+        }
+    }
+
+    #[test]
+    fn busy_handler_retries_until_writer_releases_lock() {
+        let app = setup_test_app_with_config(PluginConfig {
+            busy_handler: Some(BusyHandlerConfig {
+                max_attempts: 20,
+                base_delay_ms: 5,
+            }),
+            ..PluginConfig::default()
+        });
+        let db_path = "busy_handler_test.db";
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Load failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE IF NOT EXISTS t (id INTEGER PRIMARY KEY)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+
+        // The transaction's dedicated connection holds the write lock for a while.
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        let handle = app.handle().clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(150));
+            commit_transaction(
+                handle.clone(),
+                handle.state::<Rusqlite2Connections<MockRuntime>>(),
+                &tx_id,
+            )
+        });
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO t DEFAULT VALUES",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Contending insert should succeed once the lock is released");
+        writer.join().unwrap().expect("Commit failed");
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(db_path));
+    }
+
+    #[test]
+    fn execute_retry_on_busy_retries_until_writer_releases_lock() {
+        let app = setup_test_app();
+        let db_path = format!("retry-{}.db", Uuid::new_v4());
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            Some(LoadOptions {
+                pragmas: vec![("busy_timeout".to_string(), "0".to_string())],
+                ..Default::default()
+            }),
+        )
+        .expect("Load failed");
+        let try_insert = |retry_on_busy: Option<bool>| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "INSERT INTO t DEFAULT VALUES",
+                vec![],
+                None,
+                retry_on_busy,
+                Some(10),
+            )
+        };
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+
+        // The transaction's dedicated connection holds the write lock.
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        let busy = try_insert(None);
+        assert!(matches!(busy, Err(Error::DatabaseLocked(_))), "{busy:?}");
+
+        let handle = app.handle().clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(150));
+            commit_transaction(
+                handle.clone(),
+                handle.state::<Rusqlite2Connections<MockRuntime>>(),
+                &tx_id,
+            )
+        });
+        let result = try_insert(Some(true)).expect("Retried insert should succeed");
+        assert!(matches!(result, ExecuteResult::Insert { rows: 1, .. }));
+        writer.join().unwrap().expect("Commit failed");
+
+        let conn = app
+            .state::<Rusqlite2Connections<MockRuntime>>()
+            .get_conn(&db_alias)
+            .expect("Pooled connection missing");
+        assert!(conn.lock().unwrap().is_autocommit());
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(db_path));
+    }
+
+    #[test]
+    fn select_skip_nulls_omits_null_columns() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let run = |skip_nulls| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT 1 AS id, NULL AS nickname",
+                vec![],
+                None,
+                skip_nulls,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")
+        };
+
+        let rows = run(None);
+        assert_eq!(rows[0].get("nickname"), Some(&JsonValue::Null));
+        let rows = run(Some(true));
+        assert_eq!(rows[0].get("id"), Some(&json!(1)));
+        assert!(!rows[0].contains_key("nickname"));
+    }
+
+    #[test]
+    fn get_path_returns_resolved_path() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let path = |alias: &str| {
+            get_path(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+        };
+
+        assert_eq!(path(&db_alias).unwrap(), ":memory:");
+
+        load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "scratch",
+        )
+        .expect("Load failed");
+        assert_eq!(path("scratch").unwrap(), ":memory:");
+
+        let db_path = "get_path_test.db";
+        let file_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Load failed");
+        let expected = app.path().app_data_dir().unwrap().join(db_path);
+        assert_eq!(path(&file_alias).unwrap(), expected.to_string_lossy());
+
+        assert!(matches!(path("missing"), Err(Error::DatabaseNotLoaded(_))));
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(expected);
+    }
+
+    #[test]
+    fn multi_transaction_commits_and_rolls_back_all_aliases() {
+        let app = setup_test_app();
+        let aliases = vec!["multi_a".to_string(), "multi_b".to_string()];
+        for alias in &aliases {
+            load_in_memory_persistent(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+            .expect("Load failed");
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+                "CREATE TABLE t (id INTEGER PRIMARY KEY)",
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .expect("Create failed");
+        }
+        let insert_all = |multi: &MultiTransaction| {
+            for (alias, tx_id) in &multi.transactions {
+                execute(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    alias,
+                    "INSERT INTO t DEFAULT VALUES",
+                    vec![],
+                    Some(tx_id.clone()),
+                    None,
+                    None,
+                )
+                .expect("Insert failed");
+            }
+        };
+        let counts = || {
+            aliases
+                .iter()
+                .map(|alias| {
+                    select(
+                        app.handle().clone(),
+                        app.state::<Rusqlite2Connections<MockRuntime>>(),
+                        alias,
+                        "SELECT count(*) AS n FROM t",
+                        vec![],
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                    .expect("Select failed")[0]
+                        .get("n")
+                        .cloned()
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let multi = begin_multi_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            aliases.clone(),
+        )
+        .expect("Begin failed");
+        assert_eq!(multi.transactions.len(), 2);
+        insert_all(&multi);
+        rollback_multi(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &multi.id,
+        )
+        .expect("Rollback failed");
+        assert_eq!(counts(), vec![json!(0), json!(0)]);
+
+        let multi = begin_multi_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            aliases.clone(),
+        )
+        .expect("Begin failed");
+        insert_all(&multi);
+        commit_multi(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &multi.id,
+        )
+        .expect("Commit failed");
+        assert_eq!(counts(), vec![json!(1), json!(1)]);
+
+        let missing = commit_multi(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &multi.id,
+        );
+        assert!(matches!(missing, Err(Error::MultiTransactionNotFound(_))));
+
+        // A failure to begin rolls back the transactions already begun.
+        let failed = begin_multi_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            vec!["multi_a".to_string(), "not_loaded".to_string()],
+        );
+        assert!(matches!(failed, Err(Error::DatabaseNotLoaded(_))));
+        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
+        assert!(connections.transactions.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn export_ndjson_writes_one_object_per_row() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let dest_path = "export_ndjson_test.ndjson";
+
+        let count = export_ndjson(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < ?) SELECT i AS id, 'row ' || i AS name FROM n",
+            vec![json!(3)],
+            dest_path,
+        )
+        .expect("Export failed");
+        assert_eq!(count, 3);
+
+        let dest = app.path().app_data_dir().unwrap().join(dest_path);
+        let contents = std::fs::read_to_string(&dest).unwrap();
+        let lines: Vec<JsonValue> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2], json!({ "id": 3, "name": "row 3" }));
+        let _ = std::fs::remove_file(dest);
+    }
+
+    #[test]
+    fn export_ndjson_keeps_existing_file_on_failure() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let dest_path = format!("export-{}.ndjson", Uuid::new_v4());
+        let dest = app.path().app_data_dir().unwrap().join(&dest_path);
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(&dest, "{\"id\":1}\n").unwrap();
+
+        let failed = export_ndjson(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT * FROM missing_table WHERE id = ?",
+            vec![json!(1)],
+            &dest_path,
+        );
+        assert!(matches!(
+            failed,
+            Err(Error::QueryFailed {
+                params_count: 1,
+                ..
+            })
+        ));
+
+        let unknown = export_ndjson(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite:not-loaded.db",
+            "SELECT 1",
+            Vec::new(),
+            &dest_path,
+        );
+        assert!(unknown.is_err());
+
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), "{\"id\":1}\n");
+        let leftovers = std::fs::read_dir(dest.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(dest_path.as_str())
+            })
+            .count();
+        assert_eq!(leftovers, 1);
+        let _ = std::fs::remove_file(dest);
+    }
+
+    #[cfg(feature = "hooks")]
+    #[test]
+    fn commit_guard_vetoes_transaction_commit() {
+        use std::sync::atomic::AtomicBool;
+
+        let app = setup_test_app();
+        let allow = Arc::new(AtomicBool::new(false));
+        let guard_allow = allow.clone();
+        let mut guards = crate::CommitGuards::default();
+        guards.0.insert(
+            "guarded".to_string(),
+            Arc::new(move || guard_allow.load(Ordering::SeqCst)),
+        );
+        app.manage(guards);
+
+        load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "guarded",
+        )
+        .expect("Load failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "guarded",
+            "CREATE TABLE t (id INTEGER PRIMARY KEY)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Unguarded statement failed");
+
+        let insert_and_commit = || {
+            let tx_id = begin_transaction(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "guarded",
+            )
+            .expect("Begin failed");
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "guarded",
+                "INSERT INTO t DEFAULT VALUES",
+                vec![],
+                Some(tx_id.clone()),
+                None,
+                None,
+            )
+            .expect("Insert failed");
+            commit_transaction(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &tx_id,
+            )
+        };
+        let count = || {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "guarded",
+                "SELECT count(*) AS n FROM t",
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]
+                .get("n")
+                .cloned()
+        };
+
+        assert!(matches!(insert_and_commit(), Err(Error::CommitVetoed(_))));
+        assert_eq!(count(), Some(json!(0)));
+
+        allow.store(true, Ordering::SeqCst);
+        insert_and_commit().expect("Allowed commit failed");
+        assert_eq!(count(), Some(json!(1)));
+    }
+
+    #[cfg(feature = "expand_sql")]
+    #[test]
+    fn expand_sql_inlines_bound_values() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let expanded = expand_sql(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT ? AS id, ? AS name",
+            vec![json!(7), json!("O'Brien")],
+        )
+        .expect("Expand failed");
+        assert_eq!(expanded, "SELECT 7 AS id, 'O''Brien' AS name");
+    }
+
+    #[test]
+    fn runtime_migrations_apply_in_version_order() {
+        let app = setup_test_app();
+        load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dynamic",
+        )
+        .expect("Load failed");
+
+        let migration = |version: i64, up_sql: &str| MigrationInput {
+            version,
+            description: format!("v{}", version),
+            up_sql: up_sql.to_string(),
+            down_sql: None,
+        };
+
+        let duplicate = set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dynamic",
+            vec![migration(1, "SELECT 1"), migration(1, "SELECT 2")],
+        );
+        assert!(matches!(duplicate, Err(Error::InvalidMigration(_))));
+        for versions in [[2, 3], [1, 3]] {
+            let gap = set_migrations(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "dynamic",
+                versions
+                    .iter()
+                    .map(|&version| migration(version, "SELECT 1"))
+                    .collect(),
+            );
+            assert!(matches!(gap, Err(Error::InvalidMigration(_))));
+        }
+
+        set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dynamic",
+            vec![
+                migration(2, "ALTER TABLE users ADD COLUMN email TEXT"),
+                migration(1, "CREATE TABLE users (id INTEGER PRIMARY KEY)"),
+            ],
+        )
+        .expect("Set migrations failed");
+
+        let version = run_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dynamic",
+        )
+        .expect("Run migrations failed");
+        assert_eq!(version, 2);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "dynamic",
+            "INSERT INTO users (email) VALUES ('a@example.com')",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Migrated schema not visible");
+    }
+
+    #[test]
+    fn runtime_migrations_apply_to_memory_db() {
+        let app = setup_test_app();
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database");
+
+        set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            vec![MigrationInput {
+                version: 1,
+                description: "create notes".to_string(),
+                up_sql: "CREATE TABLE notes (body TEXT)".to_string(),
+                down_sql: None,
+            }],
+        )
+        .expect("Set migrations failed");
+        let version = run_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Run migrations failed");
+        assert_eq!(version, 1);
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO notes (body) VALUES ('migrated')",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Migrated schema not visible");
+    }
+
+    #[test]
+    fn migrate_on_load_applies_registered_migrations() {
+        let app = setup_test_app();
+        let migrate = Some(LoadOptions {
+            migrate_on_load: true,
+            ..Default::default()
+        });
+        let migration = |version: i64, up_sql: &str| MigrationInput {
+            version,
+            description: format!("v{}", version),
+            up_sql: up_sql.to_string(),
+            down_sql: None,
+        };
+
+        set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            MEMORY_DB_ALIAS,
+            vec![
+                migration(1, "CREATE TABLE users (id INTEGER PRIMARY KEY)"),
+                migration(2, "ALTER TABLE users ADD COLUMN email TEXT"),
+            ],
+        )
+        .expect("Set migrations failed");
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            MEMORY_DB_ALIAS,
+            Vec::new(),
+            migrate.clone(),
+        )
+        .expect("Load failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO users (email) VALUES ('a@example.com')",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Migrated schema not visible");
+        let config = get_alias_config(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("get_alias_config failed");
+        assert_eq!(config.migration_version, Some(2));
+
+        let db_path = format!("built-{}.db", Uuid::new_v4());
+        let built_alias = format!("sqlite::{}", db_path);
+        app.manage(crate::BuilderMigrations(std::collections::HashMap::from([
+            (
+                built_alias.clone(),
+                MigrationList(vec![crate::Migration {
+                    version: 1,
+                    description: "create_notes",
+                    sql: "CREATE TABLE notes (id INTEGER PRIMARY KEY)",
+                    down_sql: "DROP TABLE notes",
+                    kind: MigrationKind::Up,
+                }]),
+            ),
+        ])));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &built_alias,
+            Vec::new(),
+            migrate.clone(),
+        )
+        .expect("Load failed");
+        let config = get_alias_config(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("get_alias_config failed");
+        assert_eq!(config.migration_version, Some(1));
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias.clone()),
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(&db_path));
+
+        let broken_path = format!("broken-{}.db", Uuid::new_v4());
+        let broken_alias = format!("sqlite::{}", broken_path);
+        set_migrations(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &broken_alias,
+            vec![migration(1, "CREATE TABLE broken (")],
+        )
+        .expect("Set migrations failed");
+        let result = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &broken_alias,
+            Vec::new(),
+            migrate,
+        );
+        assert!(matches!(result, Err(Error::InvalidMigration(_))));
+        let result = get_alias_config(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &broken_alias,
+        );
+        assert!(matches!(result, Err(Error::DatabaseNotLoaded(_))));
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(broken_path));
+    }
+
+    #[test]
+    fn truncate_table_deletes_rows_and_resets_autoincrement() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let run = |query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed")
+        };
+        let truncate = |reset_autoincrement| {
+            truncate_table(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "items",
+                reset_autoincrement,
+            )
+            .expect("Truncate failed")
+        };
+
+        run("CREATE TABLE items (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT)");
+        run("INSERT INTO items (name) VALUES ('a'), ('b')");
+        assert_eq!(truncate(false), 2);
+        let inserted = run("INSERT INTO items (name) VALUES ('c')");
+        assert_eq!(inserted.last_insert_id(), Some(3));
+
+        assert_eq!(truncate(true), 1);
+        let inserted = run("INSERT INTO items (name) VALUES ('d')");
+        assert_eq!(inserted.last_insert_id(), Some(1));
+
+        let invalid = truncate_table(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "items; --",
+            false,
+        );
+        assert!(matches!(invalid, Err(Error::InvalidIdentifier(_))));
+    }
+
+    #[test]
+    fn load_pragmas_apply_in_order_and_reject_unknown_names() {
+        let app = setup_test_app_with_config(PluginConfig {
+            pragmas: vec![("cache_size".to_string(), "-4000".to_string())],
+            ..PluginConfig::default()
+        });
+        let pragma = |db_alias: &str, name: &str| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+                &format!("PRAGMA {}", name),
+                vec![],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]
+                .get(name)
+                .cloned()
+        };
+        let load_with_pragmas = |pragmas: Vec<(&str, &str)>| {
+            load(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                MEMORY_DB_ALIAS,
+                Vec::new(),
+                Some(LoadOptions {
+                    pragmas: pragmas
+                        .into_iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                    ..Default::default()
+                }),
+            )
+        };
+
+        let db_alias = load_with_pragmas(vec![
+            ("journal_mode", "WAL"),
+            ("foreign_keys", "ON"),
+            ("CACHE_SIZE", "-8000"),
+        ])
+        .expect("Load failed");
+        assert_eq!(pragma(&db_alias, "foreign_keys"), Some(json!(1)));
+        // The alias' pragmas run after the configured ones.
+        assert_eq!(pragma(&db_alias, "cache_size"), Some(json!(-8000)));
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "PRAGMA foreign_keys",
+            vec![],
+            Some(tx_id),
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows[0].get("foreign_keys"), Some(&json!(1)));
+
+        let rejected = load_with_pragmas(vec![("key", "secret")]);
+        assert!(matches!(rejected, Err(Error::PragmaNotAllowed(_))));
+        let injected = load_with_pragmas(vec![("foreign_keys = ON; DROP TABLE t; --", "1")]);
+        assert!(matches!(injected, Err(Error::PragmaNotAllowed(_))));
+    }
+
+    #[test]
+    fn transactions_enforce_configured_foreign_keys() {
+        let app = setup_test_app_with_config(PluginConfig {
+            pragmas: vec![("foreign_keys".to_string(), "ON".to_string())],
+            ..PluginConfig::default()
+        });
+        let db_path = format!("tx-foreign-keys-{}.db", Uuid::new_v4());
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Load failed");
+        let run = |query: &str, tx_id: Option<String>| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                tx_id,
+                None,
+                None,
+            )
+        };
+        run("CREATE TABLE authors (id INTEGER PRIMARY KEY)", None).expect("Setup failed");
+        run(
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES authors (id))",
+            None,
+        )
+        .expect("Setup failed");
+
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        let orphan = run("INSERT INTO books VALUES (1, 7)", Some(tx_id.clone()));
+        assert!(
+            matches!(orphan, Err(Error::QueryFailed { .. })),
+            "{orphan:?}"
+        );
+        run("INSERT INTO authors VALUES (7)", Some(tx_id.clone())).expect("Insert failed");
+        run("INSERT INTO books VALUES (1, 7)", Some(tx_id.clone())).expect("Insert failed");
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Commit failed");
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(&db_path));
+    }
+
+    #[test]
+    fn get_config_fills_defaults_and_redacts_keys() {
+        let app = setup_test_app_with_config(PluginConfig {
+            preload: vec![
+                "sqlite:secret:vault.db".to_string(),
+                "sqlite::plain.db".to_string(),
+            ],
+            reader_connections: 2,
+            ..Default::default()
+        });
+        let config = get_config(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("get_config failed");
+        let config = serde_json::to_value(config).unwrap();
+
+        assert_eq!(
+            config["preload"],
+            json!(["sqlite:***:vault.db", "sqlite::plain.db"])
+        );
+        assert_eq!(config["readerConnections"], json!(2));
+        assert_eq!(config["busyRetries"], json!(3));
+        assert_eq!(config["columnCase"], json!("as_is"));
+        assert!(!config.to_string().contains("secret"));
+    }
+
+    #[test]
+    fn diagnostics_reports_databases_and_transactions() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "scratch",
+        )
+        .expect("Load failed");
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+
+        let report = diagnostics(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Diagnostics failed");
+        assert_eq!(report.sqlite_version, rusqlite::version());
+        let aliases: Vec<&str> = report.databases.iter().map(|d| d.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["scratch", db_alias.as_str()]);
+        assert!(report.databases.iter().all(|d| d.path == ":memory:"));
+        assert!(report.databases.iter().all(|d| d.size_bytes.is_none()));
+        assert!(report
+            .databases
+            .iter()
+            .all(|d| d.journal_mode.as_deref() == Some("memory")));
+        assert_eq!(report.transactions.len(), 1);
+        assert_eq!(report.transactions[0].id, tx_id);
+
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Commit failed");
+        let report = diagnostics(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Diagnostics failed");
+        assert!(report.transactions.is_empty());
+    }
+
+    #[test]
+    fn preserved_floats_round_trip_exactly() {
+        let app = setup_test_app_with_config(PluginConfig {
+            preserve_float_precision: true,
+            ..Default::default()
+        });
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::memory:",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load in-memory database");
+
+        let mut samples = vec![
+            f64::MAX,
+            f64::MIN,
+            f64::MIN_POSITIVE,
+            f64::EPSILON,
+            5e-324,
+            0.1,
+            1e300,
+            -1e-300,
+            f64::INFINITY,
+        ];
+        // xorshift64 over raw bit patterns covers every exponent range.
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        while samples.len() < 1000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let f = f64::from_bits(state);
+            if !f.is_nan() {
+                samples.push(f);
+            }
+        }
+
+        for f in samples {
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT ? AS v",
+                vec![serde_json::json!({ "$f64": format!("{:?}", f) })],
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            let text = rows[0]["v"].as_str().expect("REAL should be a string");
+            let parsed: f64 = text.parse().expect("Not a valid f64");
+            assert_eq!(parsed.to_bits(), f.to_bits(), "{f:?} came back as {text}");
+        }
+    }
+
+    #[test]
+    fn load_with_base_dir() {
+        let app = setup_test_app();
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-base-{}", Uuid::new_v4()));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::portable.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some(base_dir.display().to_string()),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load database in base_dir");
+
+        let path = get_path(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("get_path failed");
+        assert_eq!(path, base_dir.join("portable.db").display().to_string());
+        assert!(base_dir.join("portable.db").is_file());
+
+        let result = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::other.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some("relative/dir".to_string()),
+                ..Default::default()
+            }),
+        );
+        assert!(matches!(result, Err(Error::InvalidBaseDir(_))));
+
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
+
+    #[test]
+    fn load_resource_read_only_or_copied() {
+        let app = setup_test_app();
+        let resource = app
+            .path()
+            .resource_dir()
+            .expect("No resource dir")
+            .join("data/ref.db");
+        std::fs::create_dir_all(resource.parent().unwrap()).unwrap();
+        Connection::open(&resource)
+            .and_then(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE codes (code TEXT); INSERT INTO codes VALUES ('a');",
+                )
+            })
+            .expect("Failed to create resource database");
+
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::resource:data/ref.db",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load resource");
+        let run = |alias: &str, query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+        };
+        assert!(run(&db_alias, "INSERT INTO codes VALUES ('b')").is_err());
+
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::resource:data/ref.db",
+            Vec::new(),
+            Some(LoadOptions {
+                copy_to_data: true,
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load copied resource");
+        run(&db_alias, "INSERT INTO codes VALUES ('b')").expect("Copy should be writable");
+        let path = get_path(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("get_path failed");
+        assert_ne!(PathBuf::from(path), resource);
+
+        let original: i64 = Connection::open(&resource)
+            .and_then(|conn| conn.query_row("SELECT count(*) FROM codes", [], |row| row.get(0)))
+            .expect("Failed to read resource");
+        assert_eq!(original, 1);
+    }
+
+    #[test]
+    fn is_writable_probes_without_leaving_residue() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let writable = |alias: &str| {
+            is_writable(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+            .expect("is_writable failed")
+        };
+        assert!(writable(&db_alias));
+        let objects = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT name FROM sqlite_master",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert!(objects.is_empty());
+
+        let resource = app
+            .path()
+            .resource_dir()
+            .expect("No resource dir")
+            .join("data/probe.db");
+        std::fs::create_dir_all(resource.parent().unwrap()).unwrap();
+        Connection::open(&resource)
+            .and_then(|conn| conn.execute_batch("CREATE TABLE t (x)"))
+            .expect("Failed to create resource database");
+        let read_only_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::resource:data/probe.db",
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load resource");
+        assert!(!writable(&read_only_alias));
+    }
+
+    #[test]
+    fn dump_schema_recreates_the_schema() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE)",
+            "CREATE INDEX authors_name ON authors (name)",
+            "CREATE VIEW named AS SELECT name FROM authors",
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES authors (id))",
+            "CREATE TRIGGER no_orphans AFTER DELETE ON authors BEGIN DELETE FROM books WHERE author_id = old.id; END",
+            "INSERT INTO authors (name) VALUES ('a')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+        let dump = |alias: &str| {
+            dump_schema(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+            .expect("dump_schema failed")
+        };
+
+        let script = dump(&db_alias);
+        assert_eq!(
+            script,
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE);\n\
+             CREATE TABLE books (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES authors (id));\n\
+             CREATE VIEW named AS SELECT name FROM authors;\n\
+             CREATE INDEX authors_name ON authors (name);\n\
+             CREATE TRIGGER no_orphans AFTER DELETE ON authors BEGIN DELETE FROM books WHERE author_id = old.id; END;\n"
+        );
+
+        load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "restored",
+        )
+        .expect("Load failed");
+        with_connection(
+            app.state::<Rusqlite2Connections<MockRuntime>>().inner(),
+            "restored",
+            None,
+            |conn| Ok(conn.execute_batch(&script)?),
+        )
+        .expect("Script failed");
+        assert_eq!(dump("restored"), script);
+    }
+
+    #[test]
+    fn select_multi_returns_one_result_set_per_statement() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let result_sets = select_multi(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 'a;b' AS s; -- trailing ; comment\n SELECT ? AS n, ? AS m; SELECT 1 WHERE 0;",
+            vec![Vec::new(), vec![serde_json::json!(1), serde_json::json!(2)]],
+            None,
+        )
+        .expect("select_multi failed");
+
+        assert_eq!(result_sets.len(), 3);
+        assert_eq!(result_sets[0][0]["s"], serde_json::json!("a;b"));
+        assert_eq!(result_sets[1][0]["n"], serde_json::json!(1));
+        assert_eq!(result_sets[1][0]["m"], serde_json::json!(2));
+        assert!(result_sets[2].is_empty());
+    }
+
+    #[test]
+    fn select_multi_rejects_extra_parameter_lists_before_running() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let result = select_multi(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER); INSERT INTO items VALUES (?)",
+            vec![Vec::new(), vec![json!(1)], vec![json!(2)]],
+            None,
+        );
+        match result {
+            Err(Error::ParameterMismatch(msg)) => {
+                assert!(msg.contains("3 parameter lists"), "{msg}")
+            }
+            other => panic!("Expected ParameterMismatch, got {:?}", other),
+        }
+
+        let created = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT name FROM sqlite_master WHERE name = 'items'",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert!(created.is_empty());
+    }
+
+    #[test]
+    fn select_parses_json_columns() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT '{\"a\":[1,2]}' AS doc, 'not json' AS bad, '{}' AS plain",
+            Vec::new(),
+            None,
+            None,
+            Some(vec!["doc".to_string(), "bad".to_string()]),
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows[0]["doc"], json!({ "a": [1, 2] }));
+        assert_eq!(rows[0]["bad"], json!("not json"));
+        assert_eq!(rows[0]["plain"], json!("{}"));
+
+        let strict = setup_test_app_with_config(PluginConfig {
+            strict_json_columns: true,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&strict);
+        let result = select(
+            strict.handle().clone(),
+            strict.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 'not json' AS bad",
+            Vec::new(),
+            None,
+            None,
+            Some(vec!["bad".to_string()]),
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(Error::ValueConversionError(_))));
+    }
+
+    #[test]
+    fn flush_checkpoints_wal() {
+        let app = setup_test_app();
+        let db_path = format!("flush-{}.db", Uuid::new_v4());
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            Some(LoadOptions {
+                pragmas: vec![("journal_mode".to_string(), "WAL".to_string())],
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load file database");
+
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+
+        let flushed = flush(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Flush failed");
+        assert!(flushed);
+
+        let memory = load_memory_db(&app);
+        assert!(flush(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &memory,
+        )
+        .expect("Flush of in-memory database failed"));
+    }
+
+    #[test]
+    fn numbered_params_bind_by_index() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let run = |app: &tauri::App<MockRuntime>, alias: &str, query: &str, values| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+                query,
+                values,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let rows = run(
+            &app,
+            &db_alias,
+            "SELECT ?2 AS b, ?1 AS a, ?2 + ?1 AS sum",
+            vec![json!(1), json!(10)],
+        )
+        .expect("Reused, out-of-order indexes failed");
+        assert_eq!(rows[0]["b"], json!(10));
+        assert_eq!(rows[0]["a"], json!(1));
+        assert_eq!(rows[0]["sum"], json!(11));
+
+        let missing = run(&app, &db_alias, "SELECT ?1, ?3", vec![json!(1)]);
+        match missing {
+            Err(Error::ParameterMismatch(msg)) => assert!(msg.contains("?3"), "{msg}"),
+            other => panic!("Expected ParameterMismatch, got {:?}", other),
+        }
+
+        let gap = run(
+            &app,
+            &db_alias,
+            "SELECT ?1 AS a, ?3 AS c",
+            vec![json!(1), json!(2), json!(3)],
+        );
+        assert!(matches!(gap, Err(Error::ParameterMismatch(_))));
+        let extra = run(&app, &db_alias, "SELECT ? AS a", vec![json!(1), json!(2)]);
+        assert!(matches!(extra, Err(Error::ParameterMismatch(_))));
+
+        let lenient = setup_test_app_with_config(PluginConfig {
+            allow_unused_params: true,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&lenient);
+        let rows = run(
+            &lenient,
+            &db_alias,
+            "SELECT ?1 AS a, ?3 AS c",
+            vec![json!(1), json!(2), json!(3), json!(4)],
+        )
+        .expect("Unused values should be ignored");
+        assert_eq!(rows[0]["a"], json!(1));
+        assert_eq!(rows[0]["c"], json!(3));
+    }
+
+    #[test]
+    fn table_hash_is_order_independent_and_type_aware() {
+        let app = setup_test_app();
+        let hash_of = |inserts: &[&str]| {
+            let db_alias = load_memory_db(&app);
+            for query in ["CREATE TABLE t (id INTEGER, v)"].iter().chain(inserts) {
+                execute(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    &db_alias,
+                    query,
+                    vec![],
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Execute failed");
+            }
+            let hash = table_hash(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "t",
+                None,
+            )
+            .expect("table_hash failed");
+            close(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                Some(db_alias),
+            )
+            .expect("Close failed");
+            hash
+        };
+
+        let a = hash_of(&[
+            "INSERT INTO t VALUES (1, 'x')",
+            "INSERT INTO t VALUES (2, NULL)",
+        ]);
+        let b = hash_of(&[
+            "INSERT INTO t VALUES (2, NULL)",
+            "INSERT INTO t VALUES (1, 'x')",
+        ]);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+
+        let empty_text = hash_of(&[
+            "INSERT INTO t VALUES (1, 'x')",
+            "INSERT INTO t VALUES (2, '')",
+        ]);
+        let empty_blob = hash_of(&[
+            "INSERT INTO t VALUES (1, 'x')",
+            "INSERT INTO t VALUES (2, x'')",
+        ]);
+        assert_ne!(a, empty_text);
+        assert_ne!(a, empty_blob);
+        assert_ne!(empty_text, empty_blob);
+    }
+
+    #[test]
+    fn db_hash_changes_with_file_contents() {
+        let app = setup_test_app();
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::hash-{}.db", Uuid::new_v4()),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+        let hash = || {
+            db_hash(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+            )
+            .expect("db_hash failed")
+        };
+
+        let before = hash();
+        assert_eq!(before, hash());
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE t (id INTEGER)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+        assert_ne!(before, hash());
+
+        let memory = load_memory_db(&app);
+        assert!(db_hash(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &memory,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn assert_schema_compares_normalized_schema_hashes() {
+        let app = setup_test_app();
+        let run = |db_alias: &str, queries: &[&str]| {
+            for query in queries {
+                execute(
+                    app.handle().clone(),
+                    app.state::<Rusqlite2Connections<MockRuntime>>(),
+                    db_alias,
+                    query,
+                    vec![],
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Setup failed");
+            }
+        };
+        let hash = |db_alias: &str| {
+            schema_hash(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+            )
+            .expect("schema_hash failed")
+        };
+        let check = |db_alias: &str, expected_hash: &str| {
+            assert_schema(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                db_alias,
+                expected_hash,
+            )
+        };
+        let load_db = |alias: &str| {
+            load_in_memory_persistent(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+            .expect("Load failed")
+        };
+
+        let first = load_db("schema-first");
+        run(
+            &first,
+            &[
+                "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT)",
+                "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+                "INSERT INTO users (name) VALUES ('a')",
+                "ANALYZE",
+            ],
+        );
+        let second = load_db("schema-second");
+        run(
+            &second,
+            &[
+                "CREATE TABLE notes (id INTEGER PRIMARY KEY,\n    body TEXT)",
+                "CREATE TABLE users (id  INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT)",
+            ],
+        );
+
+        let expected = hash(&first);
+        assert_eq!(expected, hash(&second));
+        check(&second, &expected).expect("Schemas should match");
+        check(&second, &expected.to_uppercase()).expect("Hash case should not matter");
+
+        run(&second, &["ALTER TABLE users ADD COLUMN email TEXT"]);
+        let actual = hash(&second);
+        assert_ne!(expected, actual);
+        match check(&second, &expected) {
+            Err(Error::SchemaHashMismatch {
+                expected: reported_expected,
+                actual: reported_actual,
+            }) => {
+                assert_eq!(reported_expected, expected);
+                assert_eq!(reported_actual, actual);
+            }
+            other => panic!("expected SchemaHashMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn idle_connections_are_recycled() {
+        let app = setup_test_app_with_config(PluginConfig {
+            max_idle_time_ms: Some(1),
+            ..Default::default()
+        });
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::recycle-{}.db", Uuid::new_v4()),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE t (id INTEGER)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO t VALUES (1)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Insert failed");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT id FROM t",
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select after recycle failed");
+        assert_eq!(rows.len(), 1);
+
+        let report = diagnostics(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Diagnostics failed");
+        assert!(report.connections_recycled >= 1);
+        assert!(report.connections_created > report.connections_recycled);
+    }
+
+    #[cfg(feature = "preupdate_hook")]
+    #[test]
+    fn watched_changes_survive_reopened_connections() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use tauri::Listener;
+
+        let app = setup_test_app_with_config(PluginConfig {
+            max_idle_time_ms: Some(1),
+            ..Default::default()
+        });
+        let db_path = format!("watch-reopen-{}.db", Uuid::new_v4());
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::{}", db_path),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+        let insert = |query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed");
+        };
+        insert("CREATE TABLE t (id INTEGER)");
+
+        let received = Arc::new(AtomicU32::new(0));
+        let counter = received.clone();
+        app.listen_any(CHANGE_EVENT, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        watch_changes(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Watch failed");
+
+        // Idle past `max_idle_time_ms`, so the next call recycles the connection.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        insert("INSERT INTO t VALUES (1)");
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+        let report = diagnostics(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+        )
+        .expect("Diagnostics failed");
+        assert!(report.connections_recycled >= 1);
+
+        let released = release_connection(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Release failed");
+        assert!(released);
+        insert("INSERT INTO t VALUES (2)");
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+
+        let _ = std::fs::remove_file(app.path().app_data_dir().unwrap().join(&db_path));
+    }
+
+    #[test]
+    fn decimal_columns_round_trip_exactly() {
+        let app = setup_test_app_with_config(PluginConfig {
+            decimal_columns: vec!["amount".to_string()],
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE ledger (amount TEXT, price DECIMAL(10, 2), qty INTEGER)",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("Create failed");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO ledger VALUES (?, ?, ?)",
+            vec![
+                serde_json::json!({ "$decimal": "10.10" }),
+                serde_json::json!({ "$decimal": "10.10" }),
+                serde_json::json!(3),
+            ],
+            None,
+            None,
+            None,
+        )
+        .expect("Insert failed");
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT amount, price, qty FROM ledger",
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows[0]["amount"], serde_json::json!("10.10"));
+        assert_eq!(rows[0]["qty"], serde_json::json!(3));
+        // NUMERIC affinity stores the value as REAL, so the trailing zero is
+        // lost, but it is still never returned as a JSON number.
+        #[cfg(feature = "column_decltype")]
+        assert_eq!(rows[0]["price"], serde_json::json!("10.1"));
+
+        let result = execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "INSERT INTO ledger (amount) VALUES (?)",
+            vec![serde_json::json!({ "$decimal": "1e" })],
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(Error::ValueConversionError(_))));
+    }
+
+    #[cfg(feature = "column_decltype")]
+    #[test]
+    fn coerce_to_affinity_converts_values_to_the_declared_type() {
+        let app = setup_test_app_with_config(PluginConfig {
+            coerce_to_affinity: true,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+        // SQLite applies affinity on insert, so simulate a database written
+        // with other declared types by rewriting the schema afterwards.
+        with_connection(
+            app.state::<Rusqlite2Connections<MockRuntime>>().inner(),
+            &db_alias,
+            None,
+            |conn| {
+                conn.execute_batch(
+                    "CREATE TABLE loose (i, r, t, b, n);
+                     INSERT INTO loose VALUES (' 42 ', '2.5', 7, x'00', '1e3');
+                     INSERT INTO loose VALUES ('abc', 3, 1.5, '12', '1.25');
+                     PRAGMA writable_schema = ON;
+                     UPDATE sqlite_master
+                     SET sql = 'CREATE TABLE loose (i INTEGER, r REAL, t TEXT, b BLOB, n BOOLEAN)'
+                     WHERE name = 'loose';
+                     PRAGMA writable_schema = OFF;",
+                )?;
+                let version: i64 =
+                    conn.pragma_query_value(None, "schema_version", |row| row.get(0))?;
+                conn.pragma_update(None, "schema_version", version + 1)?;
+                Ok(())
+            },
+        )
+        .expect("Setup failed");
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT i, r, t, b, n, i || '' AS expr FROM loose",
+            vec![],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(
+            JsonValue::from(rows[0].values().cloned().collect::<Vec<_>>()),
+            json!([42, 2.5, "7", "AA==", 1000, " 42 "])
+        );
+        assert_eq!(
+            JsonValue::from(rows[1].values().cloned().collect::<Vec<_>>()),
+            json!(["abc", 3.0, "1.5", "12", 1.25, "abc"])
+        );
+    }
+
+    #[test]
+    fn unix_time_columns_read_as_iso_and_whole_floats_bind_as_integers() {
+        let app = setup_test_app_with_config(PluginConfig {
+            unix_time_columns: vec!["created_at".to_string()],
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT ?1 AS created_at, typeof(?1) AS kind, ?2 AS raw",
+            vec![json!(1_706_702_400.0), json!(1_706_702_400)],
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(rows[0]["created_at"], json!("2024-01-31T12:00:00.000Z"));
+        assert_eq!(rows[0]["kind"], json!("integer"));
+        assert_eq!(rows[0]["raw"], json!(1_706_702_400));
+
+        let app = setup_test_app_with_config(PluginConfig {
+            unix_time_columns: vec!["at".to_string()],
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+        let rows = select(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT value AS at FROM (SELECT -1.5 AS value UNION ALL SELECT 951782400 \
+             UNION ALL SELECT 'soon' UNION ALL SELECT 1e300)",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        let values: Vec<_> = rows.iter().map(|row| row["at"].clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                json!("1969-12-31T23:59:58.500Z"),
+                json!("2000-02-29T00:00:00.000Z"),
+                json!("soon"),
+                json!(1e300),
+            ]
+        );
+
+        let now = now_iso(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("now_iso failed");
+        assert_eq!(now.len(), "2024-01-31T12:00:00.000Z".len());
+        assert!(now.ends_with('Z'));
+    }
+
+    #[test]
+    fn execute_returning_collects_rows_of_updates_and_deletes() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let run = |query: &str, values: Vec<JsonValue>| {
+            execute_returning(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                values,
+                None,
+            )
+            .expect("execute_returning failed")
+        };
+        let ids = |rows: Vec<IndexMap<String, JsonValue>>| {
+            rows.into_iter()
+                .map(|row| row["id"].clone())
+                .collect::<Vec<_>>()
+        };
+
+        run(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT, qty INTEGER)",
+            Vec::new(),
+        );
+        let inserted = run(
+            "INSERT INTO items (name, qty) VALUES ('a', 1), ('b', 1), ('c', 5) RETURNING id",
+            Vec::new(),
+        );
+        assert_eq!(ids(inserted), vec![json!(1), json!(2), json!(3)]);
+
+        let updated = run(
+            "UPDATE items SET qty = qty + 1 WHERE qty < ? RETURNING id, qty",
+            vec![json!(5)],
+        );
+        assert_eq!(updated.len(), 2);
+        assert!(updated.iter().all(|row| row["qty"] == json!(2)));
+        assert_eq!(ids(updated), vec![json!(1), json!(2)]);
+
+        let deleted = run(
+            "DELETE FROM items WHERE id <> ? RETURNING id",
+            vec![json!(2)],
+        );
+        assert_eq!(ids(deleted), vec![json!(1), json!(3)]);
+        let left = run("DELETE FROM items RETURNING *", Vec::new());
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0]["name"], json!("b"));
+        assert!(run("DELETE FROM items RETURNING id", Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn with_foreign_keys_off_defers_checks_and_rolls_back_violations() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "PRAGMA foreign_keys = ON",
+            "CREATE TABLE authors (id INTEGER PRIMARY KEY)",
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, author_id INTEGER REFERENCES authors (id))",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+        let op = |sql: &str, values: Vec<JsonValue>| Operation {
+            sql: sql.to_string(),
+            values,
+        };
+        let bulk = |ops: Vec<Operation>| {
+            with_foreign_keys_off(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                ops,
+            )
+        };
+        let count = |table: &str| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                &format!("SELECT count(*) AS n FROM {table}"),
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]["n"]
+                .clone()
+        };
+
+        // Children before their parent are fine once the batch is complete.
+        let violations = bulk(vec![
+            op("INSERT INTO books VALUES (1, ?)", vec![json!(7)]),
+            op("INSERT INTO authors VALUES (?)", vec![json!(7)]),
+        ])
+        .expect("Bulk load failed");
+        assert!(violations.is_empty());
+        assert_eq!(count("books"), json!(1));
+
+        let violations = bulk(vec![
+            op("INSERT INTO books VALUES (2, 8)", Vec::new()),
+            op("INSERT INTO books VALUES (3, 7)", Vec::new()),
+        ])
+        .expect("Bulk load failed");
+        assert_eq!(
+            violations,
+            vec![ForeignKeyViolation {
+                table: "books".to_string(),
+                rowid: Some(2),
+                parent: "authors".to_string(),
+                fkid: 0,
+            }]
+        );
+        assert_eq!(count("books"), json!(1));
+
+        let failed = bulk(vec![
+            op("INSERT INTO authors VALUES (9)", Vec::new()),
+            op("INSERT INTO missing VALUES (1)", Vec::new()),
+        ]);
+        assert!(matches!(failed, Err(Error::QueryFailed { .. })));
+        assert_eq!(count("authors"), json!(1));
+    }
+
+    #[test]
+    fn import_sql_file_runs_a_dump_statement_by_statement() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let data_dir = app.path().app_data_dir().unwrap();
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let import = |name: &str, dump: &str| {
+            std::fs::write(data_dir.join(name), dump).unwrap();
+            let result = import_sql_file(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                name,
+                None,
+            );
+            let _ = std::fs::remove_file(data_dir.join(name));
+            result
+        };
+        let notes = || {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT body FROM notes ORDER BY id",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")
+        };
+
+        let dump = "-- dump; not a statement\n\
+            BEGIN TRANSACTION;\n\
+            CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);\n\
+            CREATE TABLE audit (n INTEGER);\n\
+            CREATE TRIGGER log AFTER INSERT ON notes BEGIN\n\
+              INSERT INTO audit VALUES (new.id);\n\
+            END;\n\
+            /* a block comment;\n spanning lines */\n\
+            INSERT INTO notes (body) VALUES ('first;\nsecond'); INSERT INTO notes (body)\n\
+            VALUES ('it''s');\n\
+            COMMIT;\n\
+            INSERT INTO notes (body) VALUES ('no trailing semicolon')";
+        assert_eq!(import("import_test.sql", dump).expect("Import failed"), 6);
+        assert_eq!(
+            notes(),
+            vec![
+                IndexMap::from([("body".to_string(), json!("first;\nsecond"))]),
+                IndexMap::from([("body".to_string(), json!("it's"))]),
+                IndexMap::from([("body".to_string(), json!("no trailing semicolon"))]),
+            ]
+        );
+
+        // A failing statement undoes the whole import.
+        let failed = import(
+            "import_failing.sql",
+            "INSERT INTO notes (body) VALUES ('kept?');\nINSERT INTO missing VALUES (1);\n",
+        );
+        assert!(matches!(failed, Err(Error::QueryFailed { .. })));
+        assert_eq!(notes().len(), 3);
+
+        let missing = import_sql_file(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "missing_dump.sql",
+            None,
+        );
+        assert!(matches!(missing, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn execute_accepts_pragmas_returning_rows() {
+        let app = setup_test_app();
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &format!("sqlite::pragma-{}.db", Uuid::new_v4()),
+            Vec::new(),
+            None,
+        )
+        .expect("Failed to load file database");
+
+        let result = execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "pragma journal_mode = WAL",
+            vec![],
+            None,
+            None,
+            None,
+        )
+        .expect("PRAGMA through execute failed");
+        assert_eq!(result, ExecuteResult::Other { rows: 0 });
+
+        let rows = pragma(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "journal_mode",
+            None,
+        )
+        .expect("pragma failed");
+        assert_eq!(rows[0]["journal_mode"], json!("wal"));
+    }
+
+    #[test]
+    fn application_id_is_set_on_new_databases() {
+        let app = setup_test_app();
+        app.manage(crate::ApplicationId(0x5253_3251));
+        let db_alias = load_memory_db(&app);
+
+        let read = |set| {
+            application_id(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                set,
+            )
+            .expect("application_id failed")
+        };
+        assert_eq!(read(None), 0x5253_3251);
+        assert_eq!(read(Some(42)), 42);
+        assert_eq!(read(None), 42);
+    }
+
+    #[test]
+    fn poll_watch_reports_row_changes() {
+        use tauri::Listener;
+
+        let app = setup_test_app();
+        let db_alias = load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "polled",
+        )
+        .expect("Failed to load persistent in-memory database");
+        let run = |query: &str, tx_id: Option<String>| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                tx_id,
+                None,
+                None,
+            )
+            .expect("Execute failed");
+        };
+        run(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)",
+            None,
+        );
+        run("INSERT INTO items VALUES (1, 'a'), (2, 'b')", None);
+
+        let (sender, events) = std::sync::mpsc::channel::<JsonValue>();
+        let sender = Mutex::new(sender);
+        app.listen_any(POLL_CHANGE_EVENT, move |event| {
+            let payload = serde_json::from_str(event.payload()).unwrap();
+            sender.lock().unwrap().send(payload).ok();
+        });
+
+        let watch_id = poll_watch(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT id, name FROM items ORDER BY id",
+            vec![],
+            10,
+            Some("id".to_string()),
+        )
+        .expect("poll_watch failed");
+
+        // Committed at once, so a single poll sees every change.
+        let tx_id = begin_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        run("INSERT INTO items VALUES (3, 'c')", Some(tx_id.clone()));
+        run(
+            "UPDATE items SET name = 'B' WHERE id = 2",
+            Some(tx_id.clone()),
+        );
+        run("DELETE FROM items WHERE id = 1", Some(tx_id.clone()));
+        commit_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Commit failed");
+
+        let change = events
+            .recv_timeout(Duration::from_secs(5))
+            .expect("No change event");
+        assert_eq!(change["watchId"], json!(watch_id));
+        assert_eq!(change["added"], json!([{ "id": 3, "name": "c" }]));
+        assert_eq!(change["changed"], json!([{ "id": 2, "name": "B" }]));
+        assert_eq!(change["removed"], json!([{ "id": 1, "name": "a" }]));
+
+        let stopped = poll_unwatch(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &watch_id,
+        )
+        .expect("poll_unwatch failed");
+        assert!(stopped);
+    }
+
+    #[test]
+    fn background_tasks_are_listed_and_cancelled() {
+        let app = setup_test_app();
+        let db_alias = load_in_memory_persistent(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "tasks",
+        )
+        .expect("Failed to load persistent in-memory database");
+        let list = || {
+            list_background_tasks(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+            )
+            .expect("list_background_tasks failed")
+        };
+        let cancel = |task_id: &str| {
+            cancel_background_task(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                task_id,
+            )
+            .expect("cancel_background_task failed")
+        };
+        assert!(list().is_empty());
+
+        let watch_id = poll_watch(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "SELECT 1",
+            vec![],
+            10,
+            None,
+        )
+        .expect("poll_watch failed");
+        let tasks = list();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, watch_id);
+        assert_eq!(tasks[0].kind, "poll_watch");
+        assert_eq!(tasks[0].db_alias, db_alias);
+
+        assert!(cancel(&watch_id));
+        assert!(!cancel(&watch_id));
+        assert!(list().is_empty());
+    }
+
+    #[test]
+    fn execute_result_is_classified_by_statement() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        let run = |query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                vec![],
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed")
+        };
+
+        assert_eq!(
+            run("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)"),
+            ExecuteResult::Ddl
+        );
+        assert_eq!(
+            run("insert into items (name) VALUES ('a'), ('b')"),
+            ExecuteResult::Insert {
+                rows: 2,
+                last_id: 2
+            }
+        );
+        assert_eq!(
+            run("UPDATE items SET name = 'c'"),
+            ExecuteResult::Update { rows: 2 }
+        );
+        assert_eq!(
+            run("  DELETE FROM items WHERE id = 1"),
+            ExecuteResult::Delete { rows: 1 }
+        );
+        assert_eq!(
+            run("ALTER TABLE items ADD COLUMN extra TEXT"),
+            ExecuteResult::Ddl
+        );
 
-    fn setup_test_app() -> tauri::App<MockRuntime> {
-        let assets = noop_assets();
-        let context = mock_context(assets);
-        let app = mock_builder()
-            .build(context)
-            .expect("Failed to build mock app");
-        let handle = app.handle().clone();
-        app.manage(Mutex::new(MigrationList::default()));
-        app.manage(Rusqlite2Connections {
-            app: handle,
-            connections: ConnectionManager::default(),
-            pool: ConnectionPool::default(),
-            transactions: TransactionManager::default(),
-        });
-        app
+        let serialized = serde_json::to_value(ExecuteResult::Insert {
+            rows: 1,
+            last_id: 7,
+        })
+        .unwrap();
+        assert_eq!(
+            serialized,
+            json!({ "kind": "insert", "rows": 1, "lastId": 7 })
+        );
     }
 
-    fn load_memory_db(app: &tauri::App<MockRuntime>) -> String {
-        load(
+    #[test]
+    fn execute_many_is_atomic_unless_committing_per_row() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        execute(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
-            MEMORY_DB_ALIAS,
+            &db_alias,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
             Vec::new(),
+            None,
+            None,
+            None,
         )
-        .expect("Failed to load in-memory database")
+        .expect("Create table failed");
+        let insert = |per_row_commit: bool| {
+            execute_many(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "INSERT INTO items (name) VALUES (?)",
+                vec![
+                    vec![json!("a")],
+                    vec![json!("b")],
+                    vec![JsonValue::Null],
+                    vec![json!("c")],
+                ],
+                None,
+                Some(per_row_commit),
+            )
+        };
+        let count = || {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT count(*) AS n FROM items",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")[0]["n"]
+                .clone()
+        };
+
+        assert!(matches!(
+            insert(false),
+            Err(Error::QueryFailed {
+                params_count: 1,
+                ..
+            })
+        ));
+        assert_eq!(count(), json!(0));
+
+        let result = insert(true).expect("execute_many failed");
+        assert_eq!(result.rows, 2);
+        assert_eq!(result.failed_row, Some(2));
+        assert!(result.error.is_some());
+        assert_eq!(count(), json!(2));
     }
 
     #[test]
-    fn load_and_close_memory_db() {
+    fn select_pages_with_bound_limit_and_offset() {
         let app = setup_test_app();
         let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
+            "INSERT INTO items (id) VALUES (1), (2), (3), (4), (5)",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+        let page = |query: &str, values: Vec<JsonValue>, limit, offset| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                values,
+                None,
+                None,
+                None,
+                limit,
+                offset,
+            )
+            .map(|rows| rows.iter().map(|row| row["id"].clone()).collect::<Vec<_>>())
+        };
 
-        {
-            let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
-            let map = connections.connections.0.lock().unwrap();
-            assert!(map.contains_key(&db_alias));
+        assert_eq!(
+            page(
+                "SELECT id FROM items ORDER BY id;",
+                vec![],
+                Some(2),
+                Some(1)
+            )
+            .unwrap(),
+            vec![json!(2), json!(3)]
+        );
+        assert_eq!(
+            page(
+                "SELECT id FROM items WHERE id > ? ORDER BY id",
+                vec![json!(1)],
+                None,
+                Some(3)
+            )
+            .unwrap(),
+            vec![json!(5)]
+        );
+        assert_eq!(
+            page(
+                "SELECT id FROM items WHERE id >= ?1 AND id != ?1 ORDER BY id",
+                vec![json!(2)],
+                Some(1),
+                None
+            )
+            .unwrap(),
+            vec![json!(3)]
+        );
+        assert_eq!(
+            page(
+                "SELECT id FROM items WHERE id IN (SELECT id FROM items LIMIT 2)",
+                vec![],
+                Some(1),
+                None
+            )
+            .unwrap(),
+            vec![json!(1)]
+        );
+        assert!(matches!(
+            page("SELECT id FROM items LIMIT 3", vec![], Some(1), None),
+            Err(Error::LimitAlreadyPresent)
+        ));
+    }
+
+    #[test]
+    fn limit_clause_is_detected_outside_literals_and_subqueries() {
+        assert!(has_limit_clause("SELECT * FROM t limit 5"));
+        assert!(has_limit_clause(
+            "SELECT * FROM t ORDER BY a\nLIMIT 5 OFFSET 1"
+        ));
+        assert!(!has_limit_clause(
+            "SELECT 'LIMIT' AS \"limit\", [limit] FROM t"
+        ));
+        assert!(!has_limit_clause("SELECT * FROM t -- LIMIT 5\n/* LIMIT */"));
+        assert!(!has_limit_clause("SELECT * FROM (SELECT * FROM t LIMIT 5)"));
+        assert!(!has_limit_clause("SELECT rate_limit FROM t"));
+    }
+
+    #[test]
+    fn select_with_origin_keeps_same_named_columns() {
+        let app = setup_test_app();
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE users (id INTEGER PRIMARY KEY)",
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER)",
+            "INSERT INTO users (id) VALUES (1)",
+            "INSERT INTO orders (id, user_id) VALUES (7, 1)",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
         }
 
-        let closed = close(
+        let result = select_with_origin(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
-            Some(db_alias.clone()),
+            &db_alias,
+            "SELECT u.id, o.id AS order_id, 1 + 1 FROM users u JOIN orders o ON o.user_id = u.id",
+            Vec::new(),
+            None,
         )
-        .expect("Close should succeed");
-        assert!(closed);
+        .expect("select_with_origin failed");
+        let names: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["id", "order_id", "1 + 1"]);
+        assert_eq!(result.rows, vec![vec![json!(1), json!(7), json!(2)]]);
 
-        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
-        let map = connections.connections.0.lock().unwrap();
-        assert!(!map.contains_key(&db_alias));
+        #[cfg(feature = "column_metadata")]
+        {
+            assert_eq!(result.columns[0].table_name.as_deref(), Some("users"));
+            assert_eq!(result.columns[1].table_name.as_deref(), Some("orders"));
+            assert_eq!(result.columns[1].origin_name.as_deref(), Some("id"));
+            assert_eq!(result.columns[1].database_name.as_deref(), Some("main"));
+            assert_eq!(result.columns[2].table_name, None);
+        }
+        #[cfg(not(feature = "column_metadata"))]
+        assert!(result.columns.iter().all(|c| c.table_name.is_none()));
     }
 
     #[test]
-    fn execute_non_transactional_memory_db() {
+    fn ensure_table_creates_once_and_detects_mismatches() {
         let app = setup_test_app();
         let db_alias = load_memory_db(&app);
+        let ensure = |create_sql: &str| {
+            ensure_table(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "notes",
+                create_sql,
+            )
+        };
 
-        let result = execute(
+        assert!(
+            ensure("CREATE TABLE IF NOT EXISTS notes (id INTEGER PRIMARY KEY, body TEXT);")
+                .unwrap()
+        );
+        assert!(!ensure("create table notes(id INTEGER PRIMARY KEY,\n    body TEXT)").unwrap());
+        assert!(matches!(
+            ensure("CREATE TABLE notes (id INTEGER PRIMARY KEY, body BLOB)"),
+            Err(Error::SchemaMismatch { .. })
+        ));
+        assert!(matches!(
+            ensure("CREATE TABLE other (id INTEGER)"),
+            Err(Error::TableNotFound(_))
+        ));
+        let other_exists = select(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &db_alias,
-            "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+            "SELECT count(*) AS n FROM sqlite_master WHERE name = 'other'",
             Vec::new(),
             None,
-        );
-        assert!(result.is_ok(), "Non-TX execute failed: {:?}", result.err());
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("Select failed");
+        assert_eq!(other_exists[0]["n"], json!(0));
     }
 
     #[test]
-    fn transaction_execute_select_commit_memory_db() {
+    fn indexes_lists_columns_in_key_order() {
         let app = setup_test_app();
         let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, sku TEXT UNIQUE, name TEXT, qty INTEGER)",
+            "CREATE INDEX items_name_qty ON items (name, qty) WHERE qty > 0",
+            "CREATE INDEX items_lower_name ON items (lower(name))",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+        let list = |table: &str| {
+            indexes(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                table,
+                None,
+            )
+        };
 
-        let tx_id = begin_transaction(
+        let mut found = list("items").expect("indexes failed");
+        found.sort_by_key(|index| index["name"].as_str().unwrap_or_default().to_string());
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0]["name"], json!("items_lower_name"));
+        assert_eq!(found[0]["columns"], json!([null]));
+        assert_eq!(found[1]["name"], json!("items_name_qty"));
+        assert_eq!(found[1]["columns"], json!(["name", "qty"]));
+        assert_eq!(found[1]["unique"], json!(false));
+        assert_eq!(found[1]["partial"], json!(true));
+        assert_eq!(found[2]["columns"], json!(["sku"]));
+        assert_eq!(found[2]["unique"], json!(true));
+        assert_eq!(found[2]["origin"], json!("u"));
+
+        assert!(matches!(list("missing"), Err(Error::TableNotFound(_))));
+        assert!(matches!(
+            list("items; DROP TABLE items"),
+            Err(Error::InvalidIdentifier(_))
+        ));
+    }
+
+    #[test]
+    fn load_emits_database_created_only_for_new_files() {
+        use std::sync::Mutex;
+        use tauri::Listener;
+
+        let app = setup_test_app();
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-created-{}", Uuid::new_v4()));
+        let created = Arc::new(Mutex::new(Vec::new()));
+        let received = created.clone();
+        app.listen_any(DATABASE_CREATED_EVENT, move |event| {
+            let payload: JsonValue = serde_json::from_str(event.payload()).unwrap();
+            received.lock().unwrap().push(payload["dbAlias"].clone());
+        });
+        let load_file = || {
+            load(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                "sqlite::created.db",
+                Vec::new(),
+                Some(LoadOptions {
+                    base_dir: Some(base_dir.display().to_string()),
+                    ..Default::default()
+                }),
+            )
+            .expect("Failed to load database")
+        };
+
+        let db_alias = load_file();
+        load_file();
+        load_memory_db(&app);
+        assert_eq!(*created.lock().unwrap(), vec![json!(db_alias)]);
+
+        close(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
-            &db_alias,
+            None,
         )
-        .expect("Begin transaction should succeed with empty pass");
+        .expect("Close failed");
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
 
+    #[test]
+    #[cfg(any(
+        feature = "sqlcipher",
+        feature = "bundled-sqlcipher",
+        feature = "bundled-sqlcipher-vendored-openssl"
+    ))]
+    fn rekey_verified_switches_to_new_key() {
+        let app = setup_test_app();
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-rekey-{}", Uuid::new_v4()));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite:old-key:secret.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some(base_dir.display().to_string()),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load database");
         execute(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &db_alias,
-            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL)",
+            "CREATE TABLE items (id INTEGER PRIMARY KEY)",
             Vec::new(),
-            Some(tx_id.clone()),
+            None,
+            None,
+            None,
         )
         .expect("Create table failed");
 
-        let (changes, _) = execute(
+        let rekey = |new_key: &str| {
+            rekey_verified(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                new_key,
+            )
+        };
+        assert!(matches!(rekey(""), Err(Error::EncryptionFailed(_))));
+        rekey("new-key").expect("rekey failed");
+
+        let path = base_dir.join("secret.db");
+        let read_with = |key: &str| {
+            let conn = Connection::open(&path).unwrap();
+            conn.pragma_update(None, "key", key).unwrap();
+            conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        };
+        assert_eq!(read_with("new-key").unwrap(), 1);
+        assert!(read_with("old-key").is_err());
+
+        // Transactions open their own connection from the stored key.
+        let tx_id = begin_transaction(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &db_alias,
-            "INSERT INTO users (name) VALUES (?)",
-            vec![json!("Alice")],
-            Some(tx_id.clone()),
         )
-        .expect("Insert failed");
-        assert_eq!(changes, 1);
+        .expect("Begin transaction failed");
+        rollback_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &tx_id,
+        )
+        .expect("Rollback failed");
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
+
+    #[test]
+    fn release_connection_reopens_and_sees_external_changes() {
+        let app = setup_test_app();
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-release-{}", Uuid::new_v4()));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::released.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some(base_dir.display().to_string()),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load database");
+        execute(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+            "CREATE TABLE items (name TEXT)",
+            Vec::new(),
+            None,
+            None,
+            None,
+        )
+        .expect("Create table failed");
+        let release = |alias: &str| {
+            release_connection(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                alias,
+            )
+        };
+
+        assert!(release(&db_alias).expect("release_connection failed"));
+        assert!(!release(&db_alias).expect("release_connection failed"));
+        Connection::open(base_dir.join("released.db"))
+            .and_then(|conn| conn.execute("INSERT INTO items VALUES ('external')", []))
+            .expect("External write failed");
 
         let rows = select(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &db_alias,
-            "SELECT id, name FROM users WHERE name = ?",
-            vec![json!("Alice")],
-            Some(tx_id.clone()),
+            "SELECT name FROM items",
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
         )
-        .expect("Select failed");
+        .expect("Select after release failed");
         assert_eq!(rows.len(), 1);
-        assert_eq!(rows[0].get("name"), Some(&json!("Alice")));
+        assert_eq!(rows[0]["name"], json!("external"));
 
-        commit_transaction(
+        let memory_alias = load_memory_db(&app);
+        assert!(!release(&memory_alias).expect("release_connection failed"));
+        assert!(matches!(
+            release("sqlite::not_loaded.db"),
+            Err(Error::DatabaseNotLoaded(_))
+        ));
+
+        close(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
-            &tx_id,
+            Some(db_alias),
         )
-        .expect("Commit should succeed");
+        .expect("Close failed");
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
 
-        let uuid = Uuid::from_str(&tx_id).expect("Invalid tx id");
-        let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
-        let tx_map = connections.transactions.0.lock().unwrap();
-        assert!(!tx_map.contains_key(&uuid));
+    #[test]
+    fn invalid_utf8_text_is_decoded_by_config() {
+        let read = |invalid_utf8: InvalidUtf8| {
+            let app = setup_test_app_with_config(PluginConfig {
+                invalid_utf8,
+                ..Default::default()
+            });
+            let db_alias = load_memory_db(&app);
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT CAST(x'ff61' AS TEXT) AS raw, 'ok' AS valid",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let lossy = read(InvalidUtf8::Lossy).expect("Lossy select failed");
+        assert_eq!(lossy[0]["raw"], json!("\u{fffd}a"));
+        let base64 = read(InvalidUtf8::Base64).expect("Base64 select failed");
+        assert_eq!(base64[0]["raw"], json!("/2E="));
+        assert_eq!(base64[0]["valid"], json!("ok"));
+        match read(InvalidUtf8::Error) {
+            Err(Error::ValueConversionError(message)) => assert!(message.contains("\"raw\"")),
+            other => panic!("Expected a conversion error, got {:?}", other),
+        }
     }
 
     #[test]
-    fn rollback_transaction_memory_db() {
+    fn session_pragmas_persist_across_calls_and_reopened_connections() {
         let app = setup_test_app();
-        let db_alias = load_memory_db(&app);
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-like-{}", Uuid::new_v4()));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::like.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some(base_dir.display().to_string()),
+                pragmas: vec![
+                    ("case_sensitive_like".to_string(), "ON".to_string()),
+                    ("recursive_triggers".to_string(), "ON".to_string()),
+                ],
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load database");
+        for query in [
+            "CREATE TABLE fruits (name TEXT)",
+            "INSERT INTO fruits VALUES ('Apple')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Setup failed");
+        }
+        let run = |query: &str, tx_id: Option<String>| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                tx_id,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")
+        };
+        let like_matches = |tx_id: Option<String>| {
+            run("SELECT name FROM fruits WHERE name LIKE 'apple'", tx_id).len()
+        };
 
-        let tx_id = begin_transaction(
+        assert_eq!(like_matches(None), 0);
+        assert_eq!(like_matches(None), 0);
+
+        release_connection(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &db_alias,
         )
-        .expect("Begin transaction should succeed");
+        .expect("release_connection failed");
+        assert_eq!(like_matches(None), 0);
+        assert_eq!(
+            run("PRAGMA recursive_triggers", None)[0]["recursive_triggers"],
+            json!(1)
+        );
 
+        let tx_id = begin_read_transaction(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Begin failed");
+        assert_eq!(like_matches(Some(tx_id.clone())), 0);
         rollback_transaction(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
             &tx_id,
         )
-        .expect("Rollback should succeed");
+        .expect("Rollback failed");
+
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            Some(db_alias),
+        )
+        .expect("Close failed");
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
+
+    #[test]
+    fn reader_connections_serve_read_only_selects() {
+        let app = setup_test_app_with_config(PluginConfig {
+            reader_connections: 2,
+            ..Default::default()
+        });
+        let base_dir = std::env::temp_dir().join(format!("rusqlite2-readers-{}", Uuid::new_v4()));
+        let db_alias = load(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            "sqlite::readers.db",
+            Vec::new(),
+            Some(LoadOptions {
+                base_dir: Some(base_dir.display().to_string()),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to load database");
+        let run = |query: &str| {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed");
+        };
+        let query = |query: &str| {
+            select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed")
+        };
+        let readers = || {
+            app.state::<Rusqlite2Connections<MockRuntime>>()
+                .pool
+                .0
+                .lock()
+                .unwrap()
+                .get(&db_alias)
+                .map_or(0, |pooled| pooled.readers.len())
+        };
+        run("PRAGMA journal_mode = WAL");
+        run("CREATE TABLE items (name TEXT)");
+        run("INSERT INTO items VALUES ('a')");
+
+        // A reader sees the write made just before.
+        assert_eq!(query("SELECT name FROM items").len(), 1);
+        assert_eq!(readers(), 1);
+
+        // Writes and the writer's temp tables stay on the writer.
+        let inserted = query("INSERT INTO items VALUES ('b') RETURNING name");
+        assert_eq!(inserted[0]["name"], json!("b"));
+        run("CREATE TEMP TABLE scratch (n INTEGER)");
+        assert!(query("SELECT n FROM scratch").is_empty());
+        assert_eq!(query("SELECT name FROM items").len(), 2);
+        assert_eq!(readers(), 1);
+
+        interrupt(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            &db_alias,
+        )
+        .expect("Interrupt failed");
+        close(
+            app.handle().clone(),
+            app.state::<Rusqlite2Connections<MockRuntime>>(),
+            None,
+        )
+        .expect("Close failed");
+        std::fs::remove_dir_all(&base_dir).ok();
+    }
+
+    #[test]
+    fn memory_databases_keep_one_connection_with_readers_configured() {
+        let app = setup_test_app_with_config(PluginConfig {
+            reader_connections: 2,
+            ..Default::default()
+        });
+        let db_alias = load_memory_db(&app);
+        for query in [
+            "CREATE TABLE items (name TEXT)",
+            "INSERT INTO items VALUES ('a')",
+        ] {
+            execute(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                query,
+                Vec::new(),
+                None,
+                None,
+                None,
+            )
+            .expect("Execute failed");
+        }
 
-        let uuid = Uuid::from_str(&tx_id).expect("Invalid tx id");
+        // A second connection to `:memory:` would be a fresh, empty database.
+        for _ in 0..3 {
+            let rows = select(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                "SELECT name FROM items",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Select failed");
+            assert_eq!(rows.len(), 1);
+        }
         let connections = app.state::<Rusqlite2Connections<MockRuntime>>();
-        let tx_map = connections.transactions.0.lock().unwrap();
-        assert!(!tx_map.contains_key(&uuid));
+        let readers = connections
+            .pool
+            .0
+            .lock()
+            .unwrap()
+            .get(&db_alias)
+            .map(|pooled| pooled.readers.len());
+        assert_eq!(readers, Some(0));
+        assert_eq!(connections.pool.1.created.load(Ordering::Relaxed), 1);
     }
 
     #[test]
-    fn migrate_memory_db() {
-        let app = setup_test_app();
+    fn sql_limits_reject_oversized_input() {
+        let app = setup_test_app_with_config(PluginConfig {
+            max_sql_length: Some(60),
+            max_statements_per_batch: Some(2),
+            ..Default::default()
+        });
         let db_alias = load_memory_db(&app);
 
-        migrate(
+        let long_query = format!("SELECT '{}' AS v", "x".repeat(60));
+        let result = select(
             app.handle().clone(),
             app.state::<Rusqlite2Connections<MockRuntime>>(),
-            0,
             &db_alias,
-        )
-        .expect("Migrate should succeed with empty migration list");
+            &long_query,
+            Vec::new(),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(Error::SqlTooLarge {
+                length: 74,
+                max: 60
+            })
+        ));
+
+        let script = |sql: &str| {
+            select_multi(
+                app.handle().clone(),
+                app.state::<Rusqlite2Connections<MockRuntime>>(),
+                &db_alias,
+                sql,
+                Vec::new(),
+                None,
+            )
+        };
+        assert_eq!(script("SELECT 1; SELECT ';';").unwrap().len(), 2);
+        assert!(matches!(
+            script("CREATE TABLE t (a); SELECT 1; SELECT 2"),
+            Err(Error::TooManyStatements { count: 3, max: 2 })
+        ));
+        // Nothing ran.
+        assert!(script("SELECT * FROM t").is_err());
+
+        assert_eq!(statement_count(" ; -- only a comment;\n"), 0);
+        assert_eq!(
+            statement_count(
+                "CREATE TEMP TRIGGER log AFTER INSERT ON t BEGIN \
+                 INSERT INTO audit VALUES (1); UPDATE audit SET n = 2; END; SELECT 1"
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn errors_serialize_with_the_configured_serializer() {
+        fn to_json(error: &Error) -> JsonValue {
+            json!({ "code": "locked", "message": error.to_string() })
+        }
+
+        let error = Error::DatabaseLocked("main".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!("database is locked or in use by another connection: main")
+        );
+
+        crate::error::set_error_serializer(to_json);
+        let serialized = serde_json::to_value(&error);
+        crate::error::set_error_serializer(crate::default_error_serializer);
+        assert_eq!(
+            serialized.unwrap(),
+            json!({
+                "code": "locked",
+                "message": "database is locked or in use by another connection: main"
+            })
+        );
     }
 }
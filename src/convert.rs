@@ -1,18 +1,20 @@
 #![allow(clippy::useless_conversion)] // Needed for rusqlite::ToSql trait
-use crate::Error;
+use crate::{ColumnCase, Error, InvalidUtf8};
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
 use rusqlite::types::{Null, ValueRef};
 use rusqlite::ToSql;
 use serde_json::Value as JsonValue;
 
 /// Converts a JSON value into a `rusqlite::ToSql` compatible type.
-/// Note: Does not support JSON Arrays or Objects as parameters.
+/// Note: Does not support plain JSON Arrays as parameters. Objects are only
+/// accepted in the typed forms handled by `envelope_param` and `typed_param`;
+/// the `json` envelope binds an array or object as JSON text.
 pub(crate) fn json_to_rusqlite_param(value: JsonValue) -> Result<Box<dyn ToSql>, Error> {
     Ok(match value {
         JsonValue::Null => Box::new(Null),
         JsonValue::Bool(b) => Box::new(b),
         JsonValue::Number(n) => {
-            if let Some(i) = n.as_i64() {
+            if let Some(i) = n.as_i64().or_else(|| n.as_f64().and_then(exact_integer)) {
                 Box::new(i)
             } else if let Some(f) = n.as_f64() {
                 Box::new(f)
@@ -39,13 +41,101 @@ pub(crate) fn json_to_rusqlite_param(value: JsonValue) -> Result<Box<dyn ToSql>,
         }
         JsonValue::Array(_) => {
             return Err(Error::ValueConversionError(
-                "JSON arrays are not supported as parameters".to_string(),
+                "JSON arrays are not supported as parameters; bind them as text with the `json` envelope".to_string(),
             ))
         }
-        JsonValue::Object(_) => {
-            return Err(Error::ValueConversionError(
-                "JSON objects are not supported as parameters".to_string(),
-            ))
+        JsonValue::Object(map) if map.contains_key("type") => return envelope_param(map),
+        JsonValue::Object(map) => match typed_param(&map) {
+            Some(param) => return param,
+            None => {
+                return Err(Error::ValueConversionError(
+                    "JSON objects are not supported as parameters; bind them as text with the `json` envelope".to_string(),
+                ))
+            }
+        },
+    })
+}
+
+/// The integer `f` holds exactly, if any. Whole numbers can arrive as floats
+/// (e.g. `1.7e12` from `Date.now()` serialized as a float, or an `f64` from
+/// Rust); they are bound as INTEGER so that timestamps and ids compare and
+/// index like the integers they are. A REAL is bound with `{"$f64": ...}` or
+/// the `real` envelope instead.
+fn exact_integer(f: f64) -> Option<i64> {
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+    (f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER).then_some(f as i64)
+}
+
+/// Handles the `{"$i64": "..."}` and `{"$f64": "..."}` forms, which carry numbers
+/// as strings so that large integers survive JavaScript's number precision, and
+/// `{"$decimal": "..."}`, which binds a validated decimal literal as TEXT.
+fn typed_param(map: &serde_json::Map<String, JsonValue>) -> Option<Result<Box<dyn ToSql>, Error>> {
+    if map.len() != 1 {
+        return None;
+    }
+    let (kind, value) = map.iter().next()?;
+    let raw = match value {
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let parsed: Option<Box<dyn ToSql>> = match kind.as_str() {
+        "$i64" => raw.trim().parse::<i64>().ok().map(|i| Box::new(i) as _),
+        "$f64" => raw.trim().parse::<f64>().ok().map(|f| Box::new(f) as _),
+        "$decimal" => Some(raw.trim())
+            .filter(|s| is_decimal_literal(s))
+            .map(|s| Box::new(s.to_string()) as _),
+        _ => return None,
+    };
+
+    Some(
+        parsed.ok_or_else(|| {
+            Error::ValueConversionError(format!("Invalid {} value: {}", kind, value))
+        }),
+    )
+}
+
+/// Handles the explicit `{"type": ..., "data": ...}` envelope, which names the
+/// SQLite storage class to bind: `null`, `integer`, `real`, `text` (bound as
+/// is, without the double-serialization unwrapping) or `blob` (base64 `data`).
+/// `json` binds any `data`, including arrays and objects, as TEXT serialized
+/// with `serde_json`, for columns read with the JSON1 functions.
+fn envelope_param(mut map: serde_json::Map<String, JsonValue>) -> Result<Box<dyn ToSql>, Error> {
+    let kind = match map.remove("type") {
+        Some(JsonValue::String(kind)) => kind,
+        other => {
+            return Err(Error::ValueConversionError(format!(
+                "Parameter type must be a string, got {}",
+                other.unwrap_or(JsonValue::Null)
+            )))
+        }
+    };
+    let data = map.remove("data").unwrap_or(JsonValue::Null);
+    let invalid = |data: &JsonValue| {
+        Error::ValueConversionError(format!("Invalid {} parameter data: {}", kind, data))
+    };
+
+    Ok(match (kind.as_str(), &data) {
+        ("null", _) => Box::new(Null),
+        ("integer", JsonValue::Number(n)) => Box::new(n.as_i64().ok_or_else(|| invalid(&data))?),
+        ("integer", JsonValue::String(s)) => {
+            Box::new(s.trim().parse::<i64>().map_err(|_| invalid(&data))?)
+        }
+        ("real", JsonValue::Number(n)) => Box::new(n.as_f64().ok_or_else(|| invalid(&data))?),
+        ("real", JsonValue::String(s)) => {
+            Box::new(s.trim().parse::<f64>().map_err(|_| invalid(&data))?)
+        }
+        ("text", JsonValue::String(s)) => Box::new(s.clone()),
+        ("blob", JsonValue::String(s)) => {
+            Box::new(BASE64_STANDARD.decode(s).map_err(|_| invalid(&data))?)
+        }
+        ("json", _) => Box::new(data.to_string()),
+        ("integer" | "real" | "text" | "blob", _) => return Err(invalid(&data)),
+        _ => {
+            return Err(Error::ValueConversionError(format!(
+                "Unknown parameter type: {}",
+                kind
+            )))
         }
     })
 }
@@ -57,18 +147,342 @@ pub(crate) fn json_to_rusqlite_params(
     params.into_iter().map(json_to_rusqlite_param).collect()
 }
 
+/// Parameters bound by name, e.g. `(":id", value)`.
+pub(crate) type NamedParams = Vec<(String, Box<dyn ToSql>)>;
+
+/// Converts a JSON object into named parameters. Keys without a `:`, `@` or `$`
+/// prefix are bound as `:key`; `null` values bind NULL.
+pub(crate) fn json_to_named_params(value: JsonValue) -> Result<NamedParams, Error> {
+    let map = match value {
+        JsonValue::Object(map) => map,
+        JsonValue::Null => return Ok(Vec::new()),
+        other => {
+            return Err(Error::ValueConversionError(format!(
+                "Named parameters must be an object, got {}",
+                other
+            )))
+        }
+    };
+
+    map.into_iter()
+        .map(|(key, value)| {
+            let name = if key.starts_with([':', '@', '$']) {
+                key
+            } else {
+                format!(":{}", key)
+            };
+            Ok((name, json_to_rusqlite_param(value)?))
+        })
+        .collect()
+}
+
+/// How values read from SQLite are converted to JSON, from the plugin config.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ValueFormat {
+    pub(crate) preserve_float_precision: bool,
+    pub(crate) invalid_utf8: InvalidUtf8,
+}
+
 /// Converts a `rusqlite::types::ValueRef` into a `serde_json::Value`.
-/// Blobs are encoded as base64 strings.
-pub(crate) fn rusqlite_value_to_json(value_ref: ValueRef<'_>) -> Result<JsonValue, Error> {
+/// Blobs are encoded as base64 strings. With `preserve_float_precision`, reals
+/// are encoded as strings in their shortest round-trip form (e.g. `"1e300"`),
+/// which parse back to the exact same `f64`. Text that is not valid UTF-8 is
+/// handled according to `invalid_utf8`.
+pub(crate) fn rusqlite_value_to_json(
+    value_ref: ValueRef<'_>,
+    format: ValueFormat,
+) -> Result<JsonValue, Error> {
     Ok(match value_ref {
         ValueRef::Null => JsonValue::Null,
         ValueRef::Integer(i) => JsonValue::Number(i.into()),
+        ValueRef::Real(f) if format.preserve_float_precision => {
+            JsonValue::String(format!("{:?}", f))
+        }
         ValueRef::Real(f) => {
             JsonValue::Number(serde_json::Number::from_f64(f).ok_or_else(|| {
                 Error::ValueConversionError(format!("Cannot convert f64 '{}' to JSON Number", f))
             })?)
         }
-        ValueRef::Text(t) => JsonValue::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Text(t) => match (std::str::from_utf8(t), format.invalid_utf8) {
+            (Ok(text), _) => JsonValue::String(text.to_string()),
+            (Err(_), InvalidUtf8::Lossy) => {
+                JsonValue::String(String::from_utf8_lossy(t).into_owned())
+            }
+            (Err(_), InvalidUtf8::Base64) => JsonValue::String(BASE64_STANDARD.encode(t)),
+            (Err(e), InvalidUtf8::Error) => {
+                return Err(Error::ValueConversionError(format!(
+                    "TEXT value is not valid UTF-8: {}",
+                    e
+                )))
+            }
+        },
         ValueRef::Blob(b) => JsonValue::String(BASE64_STANDARD.encode(b)),
     })
 }
+
+/// Converts a value of a decimal column into a JSON string so it never goes
+/// through a JavaScript number: integers and text as stored, reals in their
+/// shortest round-trip form.
+pub(crate) fn decimal_value_to_json(
+    value_ref: ValueRef<'_>,
+    format: ValueFormat,
+) -> Result<JsonValue, Error> {
+    Ok(match value_ref {
+        ValueRef::Integer(i) => JsonValue::String(i.to_string()),
+        ValueRef::Real(f) => JsonValue::String(format!("{:?}", f)),
+        other => rusqlite_value_to_json(other, format)?,
+    })
+}
+
+/// How the values of a result column are converted, on top of `ValueFormat`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnFormat {
+    #[default]
+    Plain,
+    /// Read with `decimal_value_to_json`.
+    Decimal,
+    /// Read with `unix_time_value_to_json`.
+    UnixTime,
+    /// Read with `affinity_value_to_json`.
+    #[cfg_attr(not(feature = "column_decltype"), allow(dead_code))]
+    Affinity(Affinity),
+}
+
+/// Column affinity, determined from a declared column type like SQLite does
+/// (https://www.sqlite.org/datatype3.html#determination_of_column_affinity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+impl Affinity {
+    /// The first rule that matches wins: a type containing `INT` has INTEGER
+    /// affinity; `CHAR`, `CLOB` or `TEXT`, TEXT; `BLOB` or no type at all,
+    /// BLOB; `REAL`, `FLOA` or `DOUB`, REAL; anything else, NUMERIC.
+    #[cfg_attr(not(feature = "column_decltype"), allow(dead_code))]
+    pub(crate) fn from_decl_type(decl_type: Option<&str>) -> Self {
+        let decl = decl_type.unwrap_or_default().to_ascii_uppercase();
+        let has = |names: &[&str]| names.iter().any(|name| decl.contains(name));
+        if has(&["INT"]) {
+            Affinity::Integer
+        } else if has(&["CHAR", "CLOB", "TEXT"]) {
+            Affinity::Text
+        } else if decl.is_empty() || has(&["BLOB"]) {
+            Affinity::Blob
+        } else if has(&["REAL", "FLOA", "DOUB"]) {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+}
+
+/// Converts a value as if it were coerced to the affinity of its column,
+/// whatever its storage class:
+///
+/// - INTEGER and NUMERIC: text holding an integer that fits in 64 bits
+///   becomes an integer; text holding another finite number becomes a real,
+///   or an integer when it has no fractional part and fits in 64 bits. Reals
+///   without a fractional part that fit in 64 bits become integers.
+/// - REAL: integers, and text holding a finite number, become reals.
+/// - TEXT: integers and reals become text, reals in their shortest
+///   round-trip form (e.g. `"1.5"`, `"1e300"`).
+/// - BLOB: values are kept as stored.
+///
+/// Numbers in text may have surrounding whitespace, a sign, a fraction and an
+/// exponent; hex literals, `inf` and `nan` are not numbers. NULL, blobs and
+/// text that is not a number are never coerced. The result is then converted
+/// as usual, so `preserve_float_precision` still applies.
+pub(crate) fn affinity_value_to_json(
+    value_ref: ValueRef<'_>,
+    affinity: Affinity,
+    format: ValueFormat,
+) -> Result<JsonValue, Error> {
+    let coerced = match (affinity, value_ref) {
+        (Affinity::Integer | Affinity::Numeric, ValueRef::Text(t)) => {
+            numeric_text(t).map(integral_real).unwrap_or(value_ref)
+        }
+        (Affinity::Integer | Affinity::Numeric, ValueRef::Real(_)) => integral_real(value_ref),
+        (Affinity::Real, ValueRef::Integer(i)) => ValueRef::Real(i as f64),
+        (Affinity::Real, ValueRef::Text(t)) => match numeric_text(t) {
+            Some(ValueRef::Integer(i)) => ValueRef::Real(i as f64),
+            Some(real) => real,
+            None => value_ref,
+        },
+        (Affinity::Text, ValueRef::Integer(i)) => return Ok(JsonValue::String(i.to_string())),
+        (Affinity::Text, ValueRef::Real(f)) => return Ok(JsonValue::String(format!("{:?}", f))),
+        _ => value_ref,
+    };
+    rusqlite_value_to_json(coerced, format)
+}
+
+/// Parses text holding a decimal number into an integer, or a real if it is
+/// not an integer that fits in 64 bits. Returns `None` for anything else.
+fn numeric_text(text: &[u8]) -> Option<ValueRef<'static>> {
+    let text = std::str::from_utf8(text).ok()?.trim();
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        || !digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+    if let Ok(i) = text.parse::<i64>() {
+        return Some(ValueRef::Integer(i));
+    }
+    text.parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(ValueRef::Real)
+}
+
+/// Turns a real without a fractional part that fits in 64 bits into an
+/// integer; other values are returned as is.
+fn integral_real(value_ref: ValueRef<'_>) -> ValueRef<'_> {
+    match value_ref {
+        // `i64::MAX as f64` is 2^63, which does not fit.
+        ValueRef::Real(f) if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 => {
+            ValueRef::Integer(f as i64)
+        }
+        other => other,
+    }
+}
+
+/// Converts a value of a unix time column, in seconds since the epoch, into
+/// an ISO 8601 UTC string with milliseconds, e.g. `"2024-01-31T12:00:00.000Z"`,
+/// the format returned by `now_iso`. Values that are not numbers, or fall
+/// outside of years 0000-9999, are converted as usual.
+pub(crate) fn unix_time_value_to_json(
+    value_ref: ValueRef<'_>,
+    format: ValueFormat,
+) -> Result<JsonValue, Error> {
+    let millis = match value_ref {
+        ValueRef::Integer(i) => i.checked_mul(1000),
+        ValueRef::Real(f) if f.is_finite() => Some((f * 1000.0).round() as i64),
+        _ => None,
+    };
+    match millis.and_then(iso_from_unix_millis) {
+        Some(iso) => Ok(JsonValue::String(iso)),
+        None => rusqlite_value_to_json(value_ref, format),
+    }
+}
+
+/// Formats milliseconds since the unix epoch as an ISO 8601 UTC timestamp,
+/// using the proleptic Gregorian calendar like SQLite's date functions.
+fn iso_from_unix_millis(millis: i64) -> Option<String> {
+    const MILLIS_PER_DAY: i64 = 86_400_000;
+    let days = millis.div_euclid(MILLIS_PER_DAY);
+    let time = millis.rem_euclid(MILLIS_PER_DAY);
+
+    // Civil date from days since 1970-01-01, counting in 400-year eras that
+    // start on March 1st so the leap day ends each year.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3_600_000,
+        time / 60_000 % 60,
+        time / 1000 % 60,
+        time % 1000
+    ))
+}
+
+/// Whether `s` is a plain decimal literal such as `-10.10` or `1.5e3`.
+fn is_decimal_literal(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (s, None),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let exponent_ok = exponent.is_none_or(|e| {
+        let e = e.strip_prefix(['-', '+']).unwrap_or(e);
+        !e.is_empty() && digits(e)
+    });
+    !(int.is_empty() && frac.is_empty()) && digits(int) && digits(frac) && exponent_ok
+}
+
+/// Applies `case` to result column names. Fails if two distinct columns end up
+/// with the same name, e.g. `userId` and `user_id` in camel case.
+pub(crate) fn normalize_column_names(
+    names: Vec<&str>,
+    case: ColumnCase,
+) -> Result<Vec<String>, Error> {
+    let convert = match case {
+        ColumnCase::AsIs => return Ok(names.into_iter().map(String::from).collect()),
+        ColumnCase::CamelCase => to_camel_case,
+        ColumnCase::SnakeCase => to_snake_case,
+    };
+
+    let mut normalized: Vec<String> = Vec::with_capacity(names.len());
+    for (i, name) in names.iter().enumerate() {
+        let converted = convert(name);
+        if let Some(j) = normalized.iter().position(|n| *n == converted) {
+            if names[j] != *name {
+                return Err(Error::ColumnNameCollision(format!(
+                    "\"{}\" and \"{}\" both map to \"{}\"",
+                    names[j], names[i], converted
+                )));
+            }
+        }
+        normalized.push(converted);
+    }
+    Ok(normalized)
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper_next = false;
+    for c in name.chars() {
+        if c == '_' && !out.is_empty() {
+            upper_next = true;
+        } else if upper_next {
+            out.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
@@ -2,12 +2,49 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use rusqlite::ErrorCode;
 use serde::{Serialize, Serializer};
+use std::sync::{PoisonError, RwLock};
+
+/// Converts errors returned by commands into the value the frontend receives,
+/// set with `Builder::error_serializer`.
+pub type ErrorSerializer = fn(&Error) -> serde_json::Value;
+
+/// The `ErrorSerializer` in use. Tauri serializes command errors without
+/// access to app state, so it is shared by the whole process.
+static ERROR_SERIALIZER: RwLock<ErrorSerializer> = RwLock::new(default_error_serializer);
+
+/// The default `ErrorSerializer`: the error message as a JSON string.
+pub fn default_error_serializer(error: &Error) -> serde_json::Value {
+    serde_json::Value::String(error.to_string())
+}
+
+pub(crate) fn set_error_serializer(serializer: ErrorSerializer) {
+    *ERROR_SERIALIZER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = serializer;
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
-    Rusqlite(#[from] rusqlite::Error),
+    Rusqlite(rusqlite::Error),
+    #[error(
+        "query failed{}: {source} (extended code {}, {params_count} bound parameters)",
+        .sql.as_ref().map(|sql| format!(" `{}`", sql)).unwrap_or_default(),
+        .extended_code.map(|c| c.to_string()).unwrap_or_else(|| "n/a".to_string())
+    )]
+    QueryFailed {
+        /// The failing SQL, only set when `includeSqlInErrors` is enabled.
+        sql: Option<String>,
+        params_count: usize,
+        extended_code: Option<i32>,
+        source: rusqlite::Error,
+    },
+    #[error("database is locked or in use by another connection: {0}")]
+    DatabaseLocked(String),
+    #[error("unable to open database file: {0}")]
+    CannotOpen(String),
     #[error("invalid database url format: {0}")]
     InvalidDatabaseUrl(String),
     #[error("database alias \"{0}\" not loaded. Make sure you have called `load` for this alias.")]
@@ -36,6 +73,131 @@ pub enum Error {
 
     #[error("Mutex lock poisoned: {0}")]
     LockPoisoned(String),
+
+    #[error("the \"{0}\" feature is not enabled. Rebuild the plugin with this Cargo feature to use this command.")]
+    FeatureNotEnabled(String),
+
+    #[error("pragma \"{0}\" is not in the list of pragmas that can be set on load")]
+    PragmaNotAllowed(String),
+
+    #[error("invalid identifier \"{0}\". Only letters, digits and underscores are allowed.")]
+    InvalidIdentifier(String),
+
+    #[error("invalid migration: {0}")]
+    InvalidMigration(String),
+
+    #[error("migrations are inconsistent with the database: {0}")]
+    MigrationInconsistent(String),
+
+    #[error("column name collision: {0}")]
+    ColumnNameCollision(String),
+
+    #[error("the transaction is read-only and can only be used with `select`")]
+    ReadOnlyTransaction,
+
+    #[error("VFS \"{0}\" is not registered")]
+    VfsNotFound(String),
+
+    #[error("commit of transaction \"{0}\" was vetoed by a commit guard and rolled back")]
+    CommitVetoed(String),
+
+    #[error("multi-transaction with id \"{0}\" not found")]
+    MultiTransactionNotFound(String),
+
+    #[error("commit of \"{alias}\" failed after {committed:?} were committed; the remaining transactions were rolled back: {source}")]
+    PartialCommit {
+        alias: String,
+        committed: Vec<String>,
+        source: Box<Error>,
+    },
+
+    #[error("database alias \"{0}\" is already loaded")]
+    AliasAlreadyLoaded(String),
+
+    #[error("invalid base directory: {0}")]
+    InvalidBaseDir(String),
+
+    #[error("destination file already exists: {0}")]
+    DestinationExists(String),
+
+    #[error("table \"{0}\" not found")]
+    TableNotFound(String),
+
+    #[error("parameter mismatch: {0}")]
+    ParameterMismatch(String),
+
+    #[error("column mismatch: {0}")]
+    ColumnMismatch(String),
+
+    #[error(
+        "table \"{table}\" exists with a different schema: expected `{expected}`, found `{actual}`"
+    )]
+    SchemaMismatch {
+        table: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("schema hash is {actual}, expected {expected}; the database schema differs from the expected one")]
+    SchemaHashMismatch { expected: String, actual: String },
+
+    #[error("refusing to update every row of \"{0}\" without a condition; set `allowFullTable` to do so")]
+    FullTableUpdate(String),
+
+    #[error("the JSON1 extension is not available in this SQLite build")]
+    JsonUnsupported,
+
+    #[error("the query already has a LIMIT clause; remove it to page with `limit` and `offset`")]
+    LimitAlreadyPresent,
+
+    #[error("SQL is {length} bytes long, more than the {max} allowed by `maxSqlLength`")]
+    SqlTooLarge { length: usize, max: usize },
+
+    #[error(
+        "script has {count} statements, more than the {max} allowed by `maxStatementsPerBatch`"
+    )]
+    TooManyStatements { count: usize, max: usize },
+
+    #[error(
+        "prepared statement \"{0}\" not found. It may have been finalized or its database closed."
+    )]
+    StatementNotFound(String),
+
+    #[error("incremental vacuum requires `PRAGMA auto_vacuum = INCREMENTAL`, set before the first table is created or followed by a VACUUM")]
+    AutoVacuumNotIncremental,
+
+    #[error("database \"{0}\" has an open transaction, e.g. from a raw `BEGIN`; commit or roll it back before closing")]
+    OpenTransaction(String),
+}
+
+/// Classifies SQLite errors the frontend should be able to tell apart,
+/// e.g. a database that is in use versus one that cannot be opened.
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+                Error::DatabaseLocked(e.to_string())
+            }
+            Some(ErrorCode::CannotOpen) => Error::CannotOpen(e.to_string()),
+            _ => Error::Rusqlite(e),
+        }
+    }
+}
+
+impl Error {
+    /// Attaches the failing query to a generic SQLite error. Errors that
+    /// already carry a specific meaning (e.g. `DatabaseLocked`) are kept as is.
+    pub(crate) fn with_query(self, sql: &str, params_count: usize, include_sql: bool) -> Self {
+        match self {
+            Error::Rusqlite(source) => Error::QueryFailed {
+                sql: include_sql.then(|| sql.to_string()),
+                params_count,
+                extended_code: source.sqlite_error().map(|e| e.extended_code),
+                source,
+            },
+            other => other,
+        }
+    }
 }
 
 impl Serialize for Error {
@@ -43,6 +205,9 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        let to_json = *ERROR_SERIALIZER
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        to_json(self).serialize(serializer)
     }
 }
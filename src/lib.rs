@@ -15,17 +15,18 @@ mod error;
 mod utils;
 
 use indexmap::IndexMap;
-use rusqlite::Connection;
+use rusqlite::{Connection, InterruptHandle};
 use rusqlite_migration::{Migrations as Rusqlite2Migrations, M};
 use serde_json::Value as JsonValue;
 use tauri::AppHandle;
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use uuid::Uuid;
+use std::time::{Duration, Instant};
 
-pub use error::Error;
+pub use error::{default_error_serializer, Error, ErrorSerializer};
 
 use serde::{Deserialize, Serialize}; // Adjusted imports
 
@@ -36,10 +37,381 @@ use tauri::{
 
 use crate::utils::lock_mutex;
 
-#[derive(Serialize)]
-#[serde(untagged)]
-pub enum LastInsertId {
-    Sqlite(i64),
+/// Result of `execute`, tagged with the kind of statement that ran, so the
+/// row count and last insert id are only reported where they mean something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum ExecuteResult {
+    /// `CREATE`, `DROP` or `ALTER`.
+    Ddl,
+    /// `INSERT` or `REPLACE`, with the rowid of the last inserted row.
+    Insert {
+        rows: u64,
+        last_id: i64,
+    },
+    Update {
+        rows: u64,
+    },
+    Delete {
+        rows: u64,
+    },
+    /// Any other statement, e.g. `PRAGMA`, `BEGIN` or one starting with `WITH`.
+    Other {
+        rows: u64,
+    },
+}
+
+impl ExecuteResult {
+    /// Number of rows changed, `0` for DDL.
+    pub fn rows_affected(&self) -> u64 {
+        match *self {
+            Self::Ddl => 0,
+            Self::Insert { rows, .. }
+            | Self::Update { rows }
+            | Self::Delete { rows }
+            | Self::Other { rows } => rows,
+        }
+    }
+
+    /// Rowid of the last inserted row, for inserts only.
+    pub fn last_insert_id(&self) -> Option<i64> {
+        match *self {
+            Self::Insert { last_id, .. } => Some(last_id),
+            _ => None,
+        }
+    }
+}
+
+/// Result of `execute_many`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteManyResult {
+    /// Number of rows changed by the rows that ran successfully.
+    pub rows: u64,
+    /// With `per_row_commit`, index of the row that failed; rows after it
+    /// were not run.
+    pub failed_row: Option<usize>,
+    /// Error of the failed row.
+    pub error: Option<String>,
+}
+
+/// Result of `run`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunResult {
+    /// Rows returned by the statement; empty for statements without results.
+    pub rows: Vec<IndexMap<String, JsonValue>>,
+    /// Rows inserted, updated or deleted; `0` for read-only statements.
+    pub rows_affected: u64,
+    /// Rowid of the last row inserted on the connection.
+    pub last_insert_id: i64,
+}
+
+/// Result of `incremental_vacuum`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalVacuumReport {
+    /// `PRAGMA freelist_count` before the vacuum.
+    pub freelist_before: u64,
+    /// `PRAGMA freelist_count` after the vacuum.
+    pub freelist_after: u64,
+}
+
+/// Result of `select_columnar`: the values of each result column, in row
+/// order.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnarResult {
+    /// Result column names, in query order.
+    pub columns: Vec<String>,
+    /// One entry per column, in the order of `columns`.
+    pub data: IndexMap<String, Vec<JsonValue>>,
+}
+
+/// Result of `query_stats`: the rows of the statistics tables `ANALYZE`
+/// writes for the query planner.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStats {
+    /// Rows of `sqlite_stat1`: `tbl`, `idx` and `stat`, the row count
+    /// followed by the average rows per distinct value of each index prefix.
+    pub stat1: Vec<IndexMap<String, JsonValue>>,
+    /// Rows of `sqlite_stat4`, the sampled index keys. Only present when
+    /// SQLite is compiled with `SQLITE_ENABLE_STAT4`.
+    pub stat4: Vec<IndexMap<String, JsonValue>>,
+}
+
+/// Result of `migration_repair`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    /// Schema version of the database after the check (and repair, if any).
+    pub current_version: usize,
+    /// Number of defined migrations.
+    pub latest_version: usize,
+    /// Whether pending migrations were applied.
+    pub repaired: bool,
+}
+
+/// Result of `migration_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    /// Number of migrations applied to the database (its `user_version`).
+    pub current_version: usize,
+    /// Applied migrations, oldest first.
+    pub applied: Vec<AppliedMigration>,
+    /// Defined migrations not applied yet, in the order they would run.
+    pub pending: Vec<PendingMigration>,
+}
+
+/// A migration listed as applied by `migration_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+/// A migration listed as pending by `migration_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+    pub direction: MigrationKind,
+}
+
+/// Result of `select_with_origin`: rows as arrays of values in column order,
+/// so columns with the same name (e.g. `id` of two joined tables) are kept.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginResult {
+    pub columns: Vec<ColumnOrigin>,
+    pub rows: Vec<Vec<JsonValue>>,
+}
+
+/// A result column of `select_with_origin` and where its values come from.
+/// The origin is only known with the `column_metadata` feature, and only for
+/// columns read directly from a table; it is `None` for expressions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnOrigin {
+    /// Name of the result column, after `column_case` is applied.
+    pub name: String,
+    /// Database the table belongs to, e.g. `main`.
+    pub database_name: Option<String>,
+    pub table_name: Option<String>,
+    /// Name of the column in the table, which differs from `name` when the
+    /// column is aliased with `AS`.
+    pub origin_name: Option<String>,
+}
+
+/// Result of `reset`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetReport {
+    /// Open transactions that were rolled back.
+    pub transactions: usize,
+    /// Databases that were closed and unregistered.
+    pub databases: usize,
+}
+
+/// Result of `begin_multi_transaction`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiTransaction {
+    /// Coordinating id passed to `commit_multi`/`rollback_multi`.
+    pub id: String,
+    /// Transaction id of each alias, usable with `execute` and `select`.
+    pub transactions: IndexMap<String, String>,
+}
+
+/// Result of `diagnostics`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+    pub sqlite_version: String,
+    pub databases: Vec<DatabaseDiagnostics>,
+    pub transactions: Vec<TransactionDiagnostics>,
+    /// Pooled connections opened since startup, including reopened ones.
+    pub connections_created: u64,
+    /// Pooled connections reopened by the recycle policy.
+    pub connections_recycled: u64,
+}
+
+/// A loaded alias, as reported by `diagnostics`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseDiagnostics {
+    pub alias: String,
+    /// Resolved path, or `":memory:"`.
+    pub path: String,
+    /// Size of the database file, without its WAL; `None` for in-memory databases.
+    pub size_bytes: Option<u64>,
+    /// `PRAGMA journal_mode`, e.g. `"wal"`; `None` if it could not be read,
+    /// e.g. because another connection held a lock past the busy timeout.
+    pub journal_mode: Option<String>,
+}
+
+/// An open transaction, as reported by `diagnostics`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionDiagnostics {
+    pub id: String,
+    /// Milliseconds since the transaction began.
+    pub age_ms: u64,
+}
+
+/// One statement of a `with_foreign_keys_off` batch.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Operation {
+    pub sql: String,
+    #[serde(default)]
+    pub values: Vec<JsonValue>,
+}
+
+/// A row of `PRAGMA foreign_key_check`: a row of `table` whose foreign key
+/// `fkid` references a missing row of `parent`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    /// `None` for `WITHOUT ROWID` tables.
+    pub rowid: Option<i64>,
+    pub parent: String,
+    pub fkid: i64,
+}
+
+/// Per-alias options accepted by `load`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoadOptions {
+    /// Prefix applied to the tables of this alias, e.g. a tenant id.
+    /// Only stored and reported back through `get_alias_config`.
+    pub table_prefix: Option<String>,
+    /// Create the database file if it does not exist, along with its parent
+    /// directories unless `createMissingDirs` is off. Defaults to the
+    /// `createMissingFiles` plugin config. When disabled,
+    /// a missing file fails with `CannotOpen` instead of silently
+    /// creating an empty database, e.g. because of a typo in the path.
+    pub create_missing: Option<bool>,
+    /// Name of a registered SQLite VFS used for every connection of this alias,
+    /// e.g. for encrypted or sandboxed platform storage.
+    pub vfs: Option<String>,
+    /// `PRAGMA synchronous` level for every connection of this alias.
+    /// Defaults to the `synchronous` plugin config.
+    pub synchronous: Option<Synchronous>,
+    /// `PRAGMA name = value` statements run in order on every connection of
+    /// this alias, after the `pragmas` plugin config.
+    pub pragmas: Vec<(String, String)>,
+    /// Absolute directory the database path is resolved against instead of
+    /// `app_data_dir`, e.g. next to the executable for a portable app.
+    pub base_dir: Option<String>,
+    /// For a `resource:` database, copy the bundled file to `app_data_dir`
+    /// on first load and open the writable copy instead of the read-only
+    /// original.
+    pub copy_to_data: bool,
+    /// Apply the migrations registered for this connection string right
+    /// after opening it: those set with `set_migrations`, otherwise those
+    /// added with `Builder::add_migrations`. A failing migration fails the
+    /// load. The version reached is reported by `get_alias_config`.
+    pub migrate_on_load: bool,
+}
+
+/// Options of `Rusqlite2Connections::select_with_options`, matching the
+/// optional arguments of the `select` command.
+#[derive(Debug, Default, Clone)]
+pub struct SelectOptions {
+    /// Omit NULL columns from each row, so rows may lack keys.
+    pub skip_nulls: Option<bool>,
+    /// Columns whose text values are parsed and embedded as JSON.
+    pub json_columns: Option<Vec<String>>,
+    /// Return at most this many rows, bound as a parameter.
+    pub limit: Option<u32>,
+    /// Skip this many rows first, bound as a parameter.
+    pub offset: Option<u32>,
+}
+
+/// Options an alias was loaded with, as returned by `get_alias_config`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasConfig {
+    pub table_prefix: Option<String>,
+    /// Migration version the database was brought to by `migrate_on_load`.
+    pub migration_version: Option<usize>,
+}
+
+/// Payload of the `sql://progress` event emitted for databases with progress
+/// reporting enabled via `set_progress_handler`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub db_alias: String,
+    /// Approximate number of VM instructions executed by the connection so far.
+    pub ops: u64,
+}
+
+/// Payload of the `sql://change` event emitted for databases watched with
+/// `watch_changes`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowChange {
+    pub db_alias: String,
+    /// `insert`, `update` or `delete`.
+    pub action: String,
+    pub table: String,
+    pub rowid: i64,
+    pub old_values: Option<IndexMap<String, JsonValue>>,
+    pub new_values: Option<IndexMap<String, JsonValue>>,
+    /// Columns whose value differs between the old and new row.
+    /// For inserts and deletes every column is reported.
+    pub changed_columns: Vec<String>,
+}
+
+/// Payload of the `sql://database-created` event emitted by `load` when the
+/// database file did not exist before, e.g. to seed it on first run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseCreated {
+    pub db_alias: String,
+}
+
+/// Payload of the `sql://import-progress` event emitted by `import_sql_file`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgress {
+    pub db_alias: String,
+    /// Statements executed so far.
+    pub statements: u64,
+}
+
+/// Payload of the `sql://changed` event emitted by `poll_watch` when the
+/// result of the watched query changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollChange {
+    pub watch_id: String,
+    pub db_alias: String,
+    pub added: Vec<IndexMap<String, JsonValue>>,
+    pub removed: Vec<IndexMap<String, JsonValue>>,
+    /// Rows whose key column matched a previous row but whose other values
+    /// differ, as they are now. Always empty without a key column.
+    pub changed: Vec<IndexMap<String, JsonValue>>,
+}
+
+/// A background task, as listed by `list_background_tasks`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTaskInfo {
+    pub id: String,
+    /// What the task is, e.g. `poll_watch`.
+    pub kind: String,
+    pub db_alias: String,
 }
 
 #[macro_export]
@@ -55,13 +427,346 @@ macro_rules! params {
 
 // struct Migrations(Mutex<HashMap<String, MigrationList>>);
 
-#[derive(Default, Clone, Deserialize)]
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PluginConfig {
-    #[serde(default)]
+    /// Connection strings loaded on startup. Serialized without their
+    /// encryption keys.
+    #[serde(default, serialize_with = "serialize_redacted_urls")]
     preload: Vec<String>,
+    /// Create missing parent directories for database files on `load`.
+    /// When disabled, loading a database whose parent directory does not exist fails.
+    #[serde(default = "default_true")]
+    create_missing_dirs: bool,
+    /// Create a database file that does not exist on `load`, unless the load
+    /// sets `create_missing`. When disabled, loading a missing file fails.
+    #[serde(default = "default_true")]
+    create_missing_files: bool,
+    /// Run `PRAGMA optimize` on a database's pooled connection before `close` closes it.
+    #[serde(default = "default_true")]
+    optimize_on_close: bool,
+    /// What `close` does with a transaction left open on a database's pooled
+    /// connection, e.g. by a `BEGIN` sent with `execute`.
+    #[serde(default)]
+    open_transaction_on_close: OpenTransactionOnClose,
+    /// Times `COMMIT`/`ROLLBACK` of a transaction is retried while SQLite reports `SQLITE_BUSY`.
+    #[serde(default = "default_busy_retries")]
+    busy_retries: u32,
+    /// Include the SQL text in `QueryFailed` errors returned by `execute` and `select`.
+    /// Off by default so queries containing secrets don't end up in logs.
+    #[serde(default)]
+    include_sql_in_errors: bool,
+    /// Case applied to the column names of `select` results.
+    #[serde(default)]
+    column_case: ColumnCase,
+    /// Return REAL values as strings holding their shortest round-trip decimal
+    /// form instead of JSON numbers, which JavaScript may not parse back exactly.
+    #[serde(default)]
+    preserve_float_precision: bool,
+    /// Fail a `select` when a value of one of its `json_columns` is not valid
+    /// JSON, instead of returning the value as a string.
+    #[serde(default)]
+    strict_json_columns: bool,
+    /// How TEXT values that are not valid UTF-8 are returned.
+    #[serde(default)]
+    invalid_utf8: InvalidUtf8,
+    /// Ignore positional values that no placeholder references instead of
+    /// failing the statement.
+    #[serde(default)]
+    allow_unused_params: bool,
+    /// Result columns always read as strings holding their exact decimal
+    /// value, in addition to columns declared `DECIMAL`/`NUMERIC` (detected
+    /// with the `column_decltype` feature).
+    #[serde(default)]
+    decimal_columns: Vec<String>,
+    /// Result columns holding unix times in seconds, read as ISO 8601 UTC
+    /// strings like those returned by `now_iso`.
+    #[serde(default)]
+    unix_time_columns: Vec<String>,
+    /// Convert `select` values as if coerced to the affinity of their column's
+    /// declared type (see `convert::affinity_value_to_json`), e.g. numeric
+    /// text in an `INTEGER` column is returned as a number. Columns that are
+    /// not table columns, and those read as decimals or unix times, are not
+    /// coerced. Requires the `column_decltype` feature.
+    #[serde(default)]
+    coerce_to_affinity: bool,
+    /// Longest SQL text, in bytes, accepted by `execute`, `select` and the
+    /// like. Unlimited when unset.
+    #[serde(default)]
+    max_sql_length: Option<usize>,
+    /// Most statements accepted in one `select_multi` script. Unlimited when
+    /// unset.
+    #[serde(default)]
+    max_statements_per_batch: Option<usize>,
+    /// Read-only connections per database that run `select`s alongside the
+    /// single connection used for writes. `0` runs everything on that one
+    /// connection.
+    #[serde(default)]
+    reader_connections: usize,
+    /// Reopen a database's pooled connection once it has been open this long,
+    /// releasing memory it accumulated (statement cache, WAL mapping).
+    #[serde(default)]
+    max_connection_lifetime_ms: Option<u64>,
+    /// Reopen a database's pooled connection when it is used again after
+    /// being idle this long.
+    #[serde(default)]
+    max_idle_time_ms: Option<u64>,
+    /// Format of the ids returned by `begin_transaction`.
+    #[serde(default)]
+    transaction_id_style: TransactionIdStyle,
+    /// `PRAGMA synchronous` level applied to every connection, unless a `load`
+    /// overrides it. SQLite's default (`FULL`) is kept when unset.
+    #[serde(default)]
+    synchronous: Option<Synchronous>,
+    /// `PRAGMA name = value` statements run in order on every connection, before
+    /// the ones given to `load`. Names must be in the allowlist.
+    #[serde(default)]
+    pragmas: Vec<(String, String)>,
+    /// SQLite limits set on every connection right after it is opened, e.g.
+    /// `{ "variable_number": 32766 }`. Requires the `limits` feature.
+    #[serde(default)]
+    limits: HashMap<SqliteLimit, i32>,
+    /// Retry busy databases with exponential backoff and jitter instead of
+    /// the fixed 5 second `busy_timeout`.
+    #[serde(default)]
+    busy_handler: Option<BusyHandlerConfig>,
+    /// Number of SQLite VM instructions between `sql://progress` events for
+    /// databases with progress reporting enabled.
+    #[serde(default = "default_progress_interval")]
+    #[cfg_attr(not(feature = "hooks"), allow(dead_code))]
+    progress_interval: i32,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            preload: Vec::new(),
+            create_missing_dirs: true,
+            create_missing_files: true,
+            optimize_on_close: true,
+            open_transaction_on_close: OpenTransactionOnClose::default(),
+            busy_retries: default_busy_retries(),
+            include_sql_in_errors: false,
+            column_case: ColumnCase::default(),
+            preserve_float_precision: false,
+            strict_json_columns: false,
+            invalid_utf8: InvalidUtf8::default(),
+            allow_unused_params: false,
+            decimal_columns: Vec::new(),
+            unix_time_columns: Vec::new(),
+            coerce_to_affinity: false,
+            max_sql_length: None,
+            max_statements_per_batch: None,
+            reader_connections: 0,
+            max_connection_lifetime_ms: None,
+            max_idle_time_ms: None,
+            transaction_id_style: TransactionIdStyle::default(),
+            synchronous: None,
+            busy_handler: None,
+            pragmas: Vec::new(),
+            limits: HashMap::new(),
+            progress_interval: default_progress_interval(),
+        }
+    }
+}
+
+/// Serializes `sqlite:key:path` connection strings as `sqlite:***:path`, so
+/// `get_config` never returns an encryption key.
+fn serialize_redacted_urls<S: serde::Serializer>(
+    urls: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(urls.iter().map(|url| {
+        match url.splitn(3, ':').collect::<Vec<_>>().as_slice() {
+            [kind, pass, path] if !pass.is_empty() => format!("{}:***:{}", kind, path),
+            _ => url.clone(),
+        }
+    }))
+}
+
+impl PluginConfig {
+    pub(crate) fn value_format(&self) -> convert::ValueFormat {
+        convert::ValueFormat {
+            preserve_float_precision: self.preserve_float_precision,
+            invalid_utf8: self.invalid_utf8,
+        }
+    }
+}
+
+/// Handling of TEXT values that are not valid UTF-8, e.g. arbitrary bytes
+/// stored in a TEXT column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidUtf8 {
+    /// Replace invalid bytes with U+FFFD. The original bytes are lost.
+    #[default]
+    Lossy,
+    /// Fail the query with `ValueConversionError`, naming the column.
+    Error,
+    /// Return the raw bytes base64-encoded, like a blob. Valid text is
+    /// returned as is, so the two can't be told apart by the value alone.
+    Base64,
+}
+
+/// Case applied to the column names of `select` results.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnCase {
+    /// Column names as returned by SQLite.
+    #[default]
+    AsIs,
+    /// `user_id` becomes `userId`.
+    CamelCase,
+    /// `userId` becomes `user_id`.
+    SnakeCase,
+}
+
+/// Handling of a transaction still open on a pooled connection when its
+/// database is closed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenTransactionOnClose {
+    /// Fail with `Error::OpenTransaction` and keep the database loaded.
+    #[default]
+    Error,
+    /// Commit the transaction, then close.
+    Commit,
+}
+
+/// Format of transaction ids.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionIdStyle {
+    /// Random v4 UUIDs.
+    #[default]
+    Uuid,
+    /// Short increasing numbers, unique for the lifetime of the app.
+    Sequential,
+}
+
+/// Conflict resolution of an `insert`, as in `INSERT OR IGNORE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Skip a row that would violate a constraint.
+    Ignore,
+    /// Delete the rows a new row conflicts with, then insert it.
+    Replace,
+}
+
+/// Busy handler retrying with exponential backoff and jitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusyHandlerConfig {
+    /// Retries before giving up with `DatabaseLocked`.
+    #[serde(default = "default_busy_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled on every attempt up to one second,
+    /// with random jitter of up to half the delay.
+    #[serde(default = "default_busy_base_delay_ms")]
+    pub base_delay_ms: u64,
+}
+
+/// A run-time limit of SQLite, set with `set_limit` or the `limits` plugin
+/// config. Each maps to the `SQLITE_LIMIT_*` constant of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SqliteLimit {
+    /// Maximum size of a string or blob, in bytes.
+    Length,
+    /// Maximum length of an SQL statement, in bytes.
+    SqlLength,
+    /// Maximum number of columns of a table, index, view or result.
+    Column,
+    /// Maximum depth of an expression tree.
+    ExprDepth,
+    /// Maximum number of terms in a compound `SELECT`.
+    CompoundSelect,
+    /// Maximum number of VM instructions of one statement.
+    VdbeOp,
+    /// Maximum number of arguments of a function.
+    FunctionArg,
+    /// Maximum number of attached databases.
+    Attached,
+    /// Maximum length of a `LIKE` or `GLOB` pattern.
+    LikePatternLength,
+    /// Maximum index of a `?NNN` parameter, i.e. of bound values per statement.
+    VariableNumber,
+    /// Maximum depth of recursive trigger calls.
+    TriggerDepth,
+    /// Maximum number of auxiliary worker threads of one statement.
+    WorkerThreads,
+}
+
+#[cfg(feature = "limits")]
+impl From<SqliteLimit> for rusqlite::limits::Limit {
+    fn from(limit: SqliteLimit) -> Self {
+        use rusqlite::limits::Limit;
+        match limit {
+            SqliteLimit::Length => Limit::SQLITE_LIMIT_LENGTH,
+            SqliteLimit::SqlLength => Limit::SQLITE_LIMIT_SQL_LENGTH,
+            SqliteLimit::Column => Limit::SQLITE_LIMIT_COLUMN,
+            SqliteLimit::ExprDepth => Limit::SQLITE_LIMIT_EXPR_DEPTH,
+            SqliteLimit::CompoundSelect => Limit::SQLITE_LIMIT_COMPOUND_SELECT,
+            SqliteLimit::VdbeOp => Limit::SQLITE_LIMIT_VDBE_OP,
+            SqliteLimit::FunctionArg => Limit::SQLITE_LIMIT_FUNCTION_ARG,
+            SqliteLimit::Attached => Limit::SQLITE_LIMIT_ATTACHED,
+            SqliteLimit::LikePatternLength => Limit::SQLITE_LIMIT_LIKE_PATTERN_LENGTH,
+            SqliteLimit::VariableNumber => Limit::SQLITE_LIMIT_VARIABLE_NUMBER,
+            SqliteLimit::TriggerDepth => Limit::SQLITE_LIMIT_TRIGGER_DEPTH,
+            SqliteLimit::WorkerThreads => Limit::SQLITE_LIMIT_WORKER_THREADS,
+        }
+    }
+}
+
+/// `PRAGMA synchronous` level, trading durability for write speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    /// No syncing. Fastest, but a power loss or OS crash can corrupt the database.
+    Off,
+    /// Syncs at critical moments only. Safe from corruption in WAL mode, but a
+    /// power loss may roll back the last committed transactions.
+    Normal,
+    /// Syncs on every commit. SQLite's default.
+    Full,
+    /// Like `Full`, also syncing the directory after a rollback-journal commit.
+    Extra,
+}
+
+impl Synchronous {
+    pub(crate) fn as_pragma(self) -> &'static str {
+        match self {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+            Synchronous::Extra => "EXTRA",
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_busy_retries() -> u32 {
+    3
+}
+
+fn default_busy_max_attempts() -> u32 {
+    10
+}
+
+fn default_busy_base_delay_ms() -> u64 {
+    10
+}
+
+fn default_progress_interval() -> i32 {
+    1000
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MigrationKind {
     Up,
     Down,
@@ -78,9 +783,32 @@ pub struct Migration {
     pub kind: MigrationKind,
 }
 
+/// An owned migration definition, registered at runtime with `set_migrations`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationInput {
+    pub version: i64,
+    #[serde(default)]
+    pub description: String,
+    pub up_sql: String,
+    #[serde(default)]
+    pub down_sql: Option<String>,
+}
+
+/// Migrations registered at runtime with `set_migrations`, keyed by alias and
+/// sorted by version.
+#[derive(Default, Clone)]
+pub struct RuntimeMigrations(pub Arc<Mutex<HashMap<String, Vec<MigrationInput>>>>);
+
 #[derive(Debug, Default, Clone)]
 struct MigrationList(Vec<Migration>);
 
+/// Every migration list added with `Builder::add_migrations`, keyed by
+/// connection string. The migration commands look up the list of an alias
+/// here.
+#[derive(Default)]
+struct BuilderMigrations(HashMap<String, MigrationList>);
+
 impl MigrationList {
     pub fn resolve(self) -> Vec<M<'static>> {
         let mut migrations = Vec::new();
@@ -88,130 +816,1485 @@ impl MigrationList {
             migrations.push(M::up(migration.sql).down(migration.down_sql));
         }
 
-        migrations
+        migrations
+    }
+}
+
+// --- New State Definitions ---
+
+// Reintroduce DbInfo
+#[derive(Clone, Debug)] // Removed Send + Sync from derive
+pub struct DbInfo {
+    path: PathBuf,
+    extensions: Vec<String>,
+    pass: String,
+    /// Install a progress handler on every connection opened for this alias.
+    #[cfg_attr(not(feature = "hooks"), allow(dead_code))]
+    report_progress: bool,
+    /// Install the `watch_changes` preupdate hook on every writable connection
+    /// opened for this alias.
+    #[cfg_attr(not(feature = "preupdate_hook"), allow(dead_code))]
+    watch_changes: bool,
+    table_prefix: Option<String>,
+    /// Open with `SQLITE_OPEN_CREATE`, creating the file if it is missing.
+    create_missing: bool,
+    vfs: Option<String>,
+    /// Open with `SQLITE_OPEN_READ_ONLY`, used by read transactions.
+    read_only: bool,
+    synchronous: Option<Synchronous>,
+    busy_handler: Option<BusyHandlerConfig>,
+    /// Validated `(name, value)` pragmas applied after opening.
+    pragmas: Vec<(String, String)>,
+    /// SQLite limits applied after opening, from the plugin config and
+    /// `set_limit`.
+    limits: HashMap<SqliteLimit, i32>,
+    /// Version reached by `migrate_on_load`, if it ran.
+    migration_version: Option<usize>,
+}
+
+#[derive(Default, Clone)]
+// Revert ConnectionManager to hold DbInfo
+pub struct ConnectionManager(pub Arc<Mutex<HashMap<String, DbInfo>>>);
+
+/// One persistent connection per alias. Every non-transactional `execute` and
+/// `select` for an alias goes through this single connection, so a read always
+/// sees the writes made before it without waiting for a WAL checkpoint.
+///
+/// With `reader_connections` set, read-only `select`s run on up to that many
+/// read-only connections per alias instead, opened as needed, while the
+/// pooled connection stays the single writer.
+#[derive(Default, Clone)]
+pub struct ConnectionPool(
+    pub Arc<Mutex<HashMap<String, PooledConnection>>>,
+    pub Arc<PoolStats>,
+);
+
+/// The pooled connection of an alias, with the times used to recycle it.
+pub struct PooledConnection {
+    pub conn: Arc<Mutex<Connection>>,
+    pub opened_at: Instant,
+    pub last_used: Instant,
+    /// Read-only connections of the alias with their interrupt handles. They
+    /// are closed with the pooled connection once no query holds them.
+    pub readers: Vec<(Arc<Mutex<Connection>>, Arc<InterruptHandle>)>,
+}
+
+/// Connection counters reported by `diagnostics`.
+#[derive(Default)]
+pub struct PoolStats {
+    /// Pooled connections opened, including reopened ones.
+    pub created: AtomicU64,
+    /// Pooled connections closed and reopened by the recycle policy.
+    pub recycled: AtomicU64,
+    /// Round-robin counter handing out busy readers once all are open.
+    pub reader_turns: AtomicU64,
+}
+
+impl ConnectionPool {
+    /// Makes `conn` the pooled connection of `alias`. A previous connection is
+    /// closed once no other thread holds it.
+    pub(crate) fn insert(&self, alias: &str, conn: Connection) -> Result<(), crate::Error> {
+        let now = Instant::now();
+        lock_mutex(&self.0, "ConnectionManager")?.insert(
+            alias.to_string(),
+            PooledConnection {
+                conn: Arc::new(Mutex::new(conn)),
+                opened_at: now,
+                last_used: now,
+                readers: Vec::new(),
+            },
+        );
+        self.1.created.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// A task the plugin runs in the background, e.g. a `poll_watch` thread.
+pub struct BackgroundTask {
+    /// What the task is, e.g. `poll_watch`.
+    pub kind: &'static str,
+    pub db_alias: String,
+    /// Dropping the sender stops the task.
+    pub stop: std::sync::mpsc::Sender<()>,
+}
+
+/// The running background tasks, keyed by task id. Removing a task stops it.
+#[derive(Default, Clone)]
+pub struct BackgroundTaskManager(pub Arc<Mutex<HashMap<String, BackgroundTask>>>);
+
+/// Interrupt handles of the pooled connections, usable while a query holds the connection lock.
+#[derive(Default, Clone)]
+pub struct InterruptManager(pub Arc<Mutex<HashMap<String, Arc<InterruptHandle>>>>);
+
+/// Statements compiled by `prepare`, keyed by handle id.
+#[derive(Default, Clone)]
+pub struct PreparedStatementManager(pub Arc<Mutex<HashMap<String, PreparedStatement>>>);
+
+/// A `prepare`d statement: its compiled form lives in the statement cache of
+/// the database's pooled connection, so the handle only names it.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    pub db_alias: String,
+    pub sql: String,
+}
+
+/// Open transactions keyed by transaction id, the counter used for
+/// sequential ids, and when each open transaction began.
+#[derive(Default, Clone)]
+pub struct TransactionManager(
+    pub Arc<Mutex<HashMap<String, Arc<Mutex<rusqlite::Connection>>>>>,
+    pub Arc<AtomicU64>,
+    pub Arc<Mutex<HashMap<String, Instant>>>,
+);
+/// Transactions begun together by `begin_multi_transaction`, keyed by the
+/// coordinating id. Each maps alias to the id of its own transaction.
+#[derive(Default, Clone)]
+pub struct MultiTransactionManager(pub Arc<Mutex<HashMap<String, IndexMap<String, String>>>>);
+
+pub struct Rusqlite2Connections<R: Runtime> {
+    pub app: AppHandle<R>,
+    pub connections: ConnectionManager,
+    pub pool: ConnectionPool,
+    pub transactions: TransactionManager,
+    pub multi_transactions: MultiTransactionManager,
+    pub runtime_migrations: RuntimeMigrations,
+    pub interrupts: InterruptManager,
+    pub background_tasks: BackgroundTaskManager,
+    pub prepared: PreparedStatementManager,
+    pub config: PluginConfig,
+}
+
+// Not derived: `#[derive(Clone)]` would require `R: Clone`.
+impl<R: Runtime> Clone for Rusqlite2Connections<R> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            connections: self.connections.clone(),
+            pool: self.pool.clone(),
+            transactions: self.transactions.clone(),
+            multi_transactions: self.multi_transactions.clone(),
+            runtime_migrations: self.runtime_migrations.clone(),
+            interrupts: self.interrupts.clone(),
+            background_tasks: self.background_tasks.clone(),
+            prepared: self.prepared.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<R: Runtime> Rusqlite2Connections<R> {
+    ///Get a raw connection to run queries
+    pub fn get_conn(&self, db_alias: &str) -> Result<Arc<Mutex<Connection>>, crate::Error> {
+        if self.config.max_connection_lifetime_ms.is_some()
+            || self.config.max_idle_time_ms.is_some()
+        {
+            self.recycle_if_expired(db_alias)?;
+        }
+        {
+            let mut pool = lock_mutex(&self.pool.0, "ConnectionManager")?;
+            if let Some(pooled) = pool.get_mut(db_alias) {
+                pooled.last_used = Instant::now();
+                return Ok(pooled.conn.clone());
+            }
+        }
+        self.reopen(db_alias)
+    }
+
+    /// Returns a read-only connection of `db_alias`, preferring one that is
+    /// not busy and opening a new one while there are fewer than
+    /// `reader_connections`. Returns `None` when readers are disabled, and for
+    /// in-memory databases, which other connections can't see.
+    pub(crate) fn get_reader(
+        &self,
+        db_alias: &str,
+    ) -> Result<Option<Arc<Mutex<Connection>>>, crate::Error> {
+        let size = self.config.reader_connections;
+        if size == 0 {
+            return Ok(None);
+        }
+        // Loads the writer first, so readers follow its reopening and recycling.
+        self.get_conn(db_alias)?;
+        {
+            let pool = lock_mutex(&self.pool.0, "ConnectionManager")?;
+            let Some(pooled) = pool.get(db_alias) else {
+                return Ok(None);
+            };
+            let idle = pooled
+                .readers
+                .iter()
+                .find(|(reader, _)| reader.try_lock().is_ok());
+            if let Some((reader, _)) = idle {
+                return Ok(Some(reader.clone()));
+            }
+            if pooled.readers.len() >= size {
+                let next = self.pool.1.reader_turns.fetch_add(1, Ordering::Relaxed);
+                let (reader, _) = &pooled.readers[next as usize % pooled.readers.len()];
+                return Ok(Some(reader.clone()));
+            }
+        }
+
+        let Some(db_info) = lock_mutex(&self.connections.0, "ConnectionManager")?
+            .get(db_alias)
+            .cloned()
+        else {
+            return Err(Error::DatabaseNotLoaded(db_alias.to_string()));
+        };
+        if commands::display_path(&db_info) == ":memory:" {
+            return Ok(None);
+        }
+        let db_info = DbInfo {
+            read_only: true,
+            ..db_info
+        };
+        let conn = commands::open_alias_conn(self, db_alias, &db_info)?;
+        let interrupt = Arc::new(conn.get_interrupt_handle());
+        let reader = Arc::new(Mutex::new(conn));
+        if let Some(pooled) = lock_mutex(&self.pool.0, "ConnectionManager")?.get_mut(db_alias) {
+            // Another thread may have filled the pool meanwhile; the extra
+            // reader then serves this query only.
+            if pooled.readers.len() < size {
+                pooled.readers.push((reader.clone(), interrupt));
+            }
+        }
+        Ok(Some(reader))
+    }
+
+    /// Opens a new pooled connection for a loaded alias that has none, e.g.
+    /// after `release_connection`, from the `DbInfo` kept for the alias.
+    fn reopen(&self, db_alias: &str) -> Result<Arc<Mutex<Connection>>, crate::Error> {
+        let Some(db_info) = lock_mutex(&self.connections.0, "ConnectionManager")?
+            .get(db_alias)
+            .cloned()
+        else {
+            return Err(Error::DatabaseNotLoaded(db_alias.to_string()));
+        };
+
+        let conn = commands::open_alias_conn(self, db_alias, &db_info)?;
+        lock_mutex(&self.interrupts.0, "InterruptManager")?
+            .insert(db_alias.to_string(), Arc::new(conn.get_interrupt_handle()));
+        self.pool.insert(db_alias, conn)?;
+        lock_mutex(&self.pool.0, "ConnectionManager")?
+            .get(db_alias)
+            .map(|pooled| pooled.conn.clone())
+            .ok_or_else(|| Error::DatabaseNotLoaded(db_alias.to_string()))
+    }
+
+    /// Reopens the pooled connection of `db_alias` if it is older than
+    /// `max_connection_lifetime_ms` or was idle longer than `max_idle_time_ms`.
+    /// In-memory databases, which would lose their contents, and connections
+    /// that are in use or inside a transaction are left alone.
+    fn recycle_if_expired(&self, db_alias: &str) -> Result<(), crate::Error> {
+        let expired = |limit: Option<u64>, since: Instant| {
+            limit.is_some_and(|ms| since.elapsed() > Duration::from_millis(ms))
+        };
+        let old_conn = {
+            let pool = lock_mutex(&self.pool.0, "ConnectionManager")?;
+            match pool.get(db_alias) {
+                Some(pooled)
+                    if expired(self.config.max_connection_lifetime_ms, pooled.opened_at)
+                        || expired(self.config.max_idle_time_ms, pooled.last_used) =>
+                {
+                    pooled.conn.clone()
+                }
+                _ => return Ok(()),
+            }
+        };
+        let idle = old_conn
+            .try_lock()
+            .map(|conn| conn.is_autocommit())
+            .unwrap_or(false);
+        if !idle {
+            return Ok(());
+        }
+
+        let Some(db_info) = lock_mutex(&self.connections.0, "ConnectionManager")?
+            .get(db_alias)
+            .cloned()
+        else {
+            return Ok(());
+        };
+        if commands::display_path(&db_info) == ":memory:" {
+            return Ok(());
+        }
+
+        let conn = commands::open_alias_conn(self, db_alias, &db_info)?;
+        lock_mutex(&self.interrupts.0, "InterruptManager")?
+            .insert(db_alias.to_string(), Arc::new(conn.get_interrupt_handle()));
+        self.pool.insert(db_alias, conn)?;
+        self.pool.1.recycled.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    ///
+    ///
+    /// A static initializer which connects to the underlying database and
+    /// returns the db string instance once a connection to the database is established.
+    ///
+    /// # Sqlite
+    ///
+    /// The path is relative to `tauri::path::BaseDirectory::App` and must start with `sqlite:`.
+    ///
+    /// @example
+    /// ```ignore
+    /// let app:tauri::AppHandle;
+    ///
+    /// let db:String = app.rusqlite2_connection()
+    ///      .load("sqlite:test.db".to_string(), vec!["path/to/ext_1", "path/to/ext_2"])
+    ///      .unwrap();
+    ///
+    /// ```
+    ///
+    pub fn load(&self, db: &str, extensions: Vec<String>) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::load(self.app.clone(), connections, db, extensions, None)
+    }
+
+    ///
+    /// Runs a SELECT on the pooled connection of `db`, binding the fields of
+    /// `params` to named parameters (`:field`). `None` fields bind NULL and
+    /// fields the query does not reference are ignored.
+    ///
+    /// ```ignore
+    /// #[derive(serde::Serialize)]
+    /// struct Filter { name: Option<String> }
+    ///
+    /// let rows = app.rusqlite2_connection()
+    ///     .select_bind(db, "SELECT * FROM users WHERE name IS :name", Filter { name: None })
+    ///     .unwrap();
+    /// ```
+    pub fn select_bind<P: Serialize>(
+        &self,
+        db: &str,
+        query: &str,
+        params: P,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        let value =
+            serde_json::to_value(params).map_err(|e| Error::ValueConversionError(e.to_string()))?;
+        let named = convert::json_to_named_params(value)?;
+
+        let conn_arc = self.get_conn(db)?;
+        let conn = lock_mutex(&conn_arc, "ConnectionManager")?;
+        commands::query_rows_named(&conn, query, named, self.config.column_case, &self.config)
+    }
+    ///
+    /// Deletes the rows of `table` whose `id_column` is one of `ids`.
+    /// Optionally runs within the transaction identified by `tx_id`.
+    ///
+    /// * `returns` - The number of rows deleted.
+    ///
+    /// ```ignore
+    /// let deleted = app.rusqlite2_connection()
+    ///     .delete_by_ids(db, "users", "id", params![1, 2, 3], None)
+    ///     .unwrap();
+    /// ```
+    pub fn delete_by_ids(
+        &self,
+        db: &str,
+        table: &str,
+        id_column: &str,
+        ids: Vec<JsonValue>,
+        tx_id: Option<String>,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::delete_by_ids(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            id_column,
+            ids,
+            tx_id,
+        )
+    }
+    ///
+    /// Checks the applied migrations of `db` against the defined ones and,
+    /// if `repair` is set, applies any pending migrations.
+    ///
+    /// ```ignore
+    /// let report = app.rusqlite2_connection().migration_repair(db, true).unwrap();
+    /// ```
+    pub fn migration_repair(
+        &self,
+        db: &str,
+        repair: bool,
+    ) -> Result<MigrationReport, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::migration_repair(self.app.clone(), connections, db, repair)
+    }
+    ///
+    /// Lists the applied and pending migrations of `db`.
+    ///
+    /// ```ignore
+    /// let status = app.rusqlite2_connection().migration_status(db).unwrap();
+    /// ```
+    pub fn migration_status(&self, db: &str) -> Result<MigrationStatus, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::migration_status(self.app.clone(), connections, db)
+    }
+    ///
+    /// Returns the number of rows changed in transaction `tx_id` so far.
+    ///
+    /// ```ignore
+    /// let changed = app.rusqlite2_connection().transaction_changes(&tx_id).unwrap();
+    /// ```
+    pub fn transaction_changes(&self, tx_id: &str) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::transaction_changes(self.app.clone(), connections, tx_id)
+    }
+    ///
+    /// Closes the live connection of `db` but keeps it loaded with its
+    /// configuration; the next call reopens it. Returns whether a connection
+    /// was closed; `false` if there was none, or if another call still holds
+    /// it, in which case it is closed once that call finishes.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().release_connection(&db).unwrap();
+    /// ```
+    pub fn release_connection(&self, db: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::release_connection(self.app.clone(), connections, db)
+    }
+    ///
+    /// Creates `table` with `create_sql` unless it exists, and returns whether
+    /// it was created. Fails with `Error::SchemaMismatch` if the table exists
+    /// with a different definition.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection()
+    ///     .ensure_table(&db, "notes", "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")
+    ///     .unwrap();
+    /// ```
+    pub fn ensure_table(
+        &self,
+        db: &str,
+        table: &str,
+        create_sql: &str,
+    ) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::ensure_table(self.app.clone(), connections, db, table, create_sql)
+    }
+    ///
+    /// Lists the indexes of `table` with their uniqueness and columns.
+    /// Optionally reads them within the transaction identified by `tx_id`.
+    ///
+    /// ```ignore
+    /// let indexes = app.rusqlite2_connection().indexes(&db, "items", None).unwrap();
+    /// ```
+    pub fn indexes(
+        &self,
+        db: &str,
+        table: &str,
+        tx_id: Option<String>,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::indexes(self.app.clone(), connections, db, table, tx_id)
+    }
+    ///
+    /// Returns the schema cookie of `db`, which changes whenever its schema is modified.
+    /// Optionally reads it within the transaction identified by `tx_id`.
+    ///
+    /// ```ignore
+    /// let version = app.rusqlite2_connection().schema_version(db, None).unwrap();
+    /// ```
+    pub fn schema_version(&self, db: &str, tx_id: Option<String>) -> Result<i64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::schema_version(self.app.clone(), connections, db, tx_id)
+    }
+    ///
+    /// Returns the `CREATE` statements of `db`'s tables, views, indexes and
+    /// triggers as one SQL script.
+    ///
+    /// ```ignore
+    /// let ddl = app.rusqlite2_connection().dump_schema(db).unwrap();
+    /// ```
+    pub fn dump_schema(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::dump_schema(self.app.clone(), connections, db)
+    }
+    ///
+    /// Writes a compacted copy of `db` to `dest_path` (relative to
+    /// `app_data_dir`) and returns its size in bytes.
+    ///
+    /// ```ignore
+    /// let size = app.rusqlite2_connection().vacuum_into(db, "backups/compact.db").unwrap();
+    /// ```
+    pub fn vacuum_into(&self, db: &str, dest_path: &str) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::vacuum_into(self.app.clone(), connections, db, dest_path)
+    }
+    ///
+    /// Like `select`, but runs the query on the blocking thread pool of the
+    /// async runtime instead of the calling thread.
+    ///
+    /// ```ignore
+    /// let rows = app.rusqlite2_connection()
+    ///     .select_async(db, "SELECT * FROM users", vec![], None)
+    ///     .await
+    ///     .unwrap();
+    /// ```
+    pub async fn select_async(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        tx_id: Option<String>,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        crate::commands::select_async(
+            self.app.clone(),
+            db.to_string(),
+            query.to_string(),
+            values,
+            tx_id,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+    }
+    ///
+    /// Copies `db` to `dest_path` (relative to `app_data_dir`) and loads the
+    /// copy as `new_alias`. The source stays loaded.
+    ///
+    /// ```ignore
+    /// let copy = app.rusqlite2_connection()
+    ///     .clone_database(db, "sqlite::copy.db", "copy.db")
+    ///     .unwrap();
+    /// ```
+    pub fn clone_database(
+        &self,
+        db: &str,
+        new_alias: &str,
+        dest_path: &str,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::clone_database(self.app.clone(), connections, db, new_alias, dest_path)
+    }
+    ///
+    /// Begins a transaction on each of `aliases`, grouped under one id for
+    /// `commit_multi`/`rollback_multi`. Not atomic across databases.
+    ///
+    /// ```ignore
+    /// let multi = app.rusqlite2_connection()
+    ///     .begin_multi_transaction(vec![db_a.clone(), db_b.clone()])
+    ///     .unwrap();
+    /// let tx_a = multi.transactions[&db_a].clone();
+    /// ```
+    pub fn begin_multi_transaction(
+        &self,
+        aliases: Vec<String>,
+    ) -> Result<MultiTransaction, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::begin_multi_transaction(self.app.clone(), connections, aliases)
+    }
+    ///
+    /// Commits every transaction of multi-transaction `id`, rolling back the
+    /// rest if one fails.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().commit_multi(&multi.id).unwrap();
+    /// ```
+    pub fn commit_multi(&self, id: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::commit_multi(self.app.clone(), connections, id)
+    }
+    ///
+    /// Rolls back every transaction of multi-transaction `id`.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().rollback_multi(&multi.id).unwrap();
+    /// ```
+    pub fn rollback_multi(&self, id: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::rollback_multi(self.app.clone(), connections, id)
+    }
+    ///
+    /// Streams the rows of `query` to `dest_path` (relative to `app_data_dir`)
+    /// as newline-delimited JSON and returns the number of rows written.
+    ///
+    /// ```ignore
+    /// let count = app.rusqlite2_connection()
+    ///     .export_ndjson(db, "SELECT * FROM events", vec![], "exports/events.ndjson")
+    ///     .unwrap();
+    /// ```
+    pub fn export_ndjson(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        dest_path: &str,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::export_ndjson(self.app.clone(), connections, db, query, values, dest_path)
+    }
+    ///
+    /// Returns `query` with `values` inlined, for debugging only. The result
+    /// contains the bound values verbatim. Requires the `expand_sql` feature.
+    ///
+    /// ```ignore
+    /// let sql = app.rusqlite2_connection()
+    ///     .expand_sql(db, "SELECT * FROM users WHERE id = ?", params![1])
+    ///     .unwrap();
+    /// ```
+    pub fn expand_sql(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::expand_sql(self.app.clone(), connections, db, query, values)
+    }
+    ///
+    /// Registers migrations for `db` at runtime, replacing any registered
+    /// before. Apply them with `run_migrations`.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().set_migrations(db, vec![MigrationInput {
+    ///     version: 1,
+    ///     description: "create users".to_string(),
+    ///     up_sql: "CREATE TABLE users (id INTEGER PRIMARY KEY)".to_string(),
+    ///     down_sql: Some("DROP TABLE users".to_string()),
+    /// }]).unwrap();
+    /// ```
+    pub fn set_migrations(
+        &self,
+        db: &str,
+        migrations: Vec<MigrationInput>,
+    ) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::set_migrations(self.app.clone(), connections, db, migrations)
+    }
+    ///
+    /// Applies the migrations registered with `set_migrations` to `db` and
+    /// returns the resulting schema version.
+    ///
+    /// ```ignore
+    /// let version = app.rusqlite2_connection().run_migrations(db).unwrap();
+    /// ```
+    pub fn run_migrations(&self, db: &str) -> Result<usize, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::run_migrations(self.app.clone(), connections, db)
+    }
+    ///
+    /// Deletes every row of `table` and returns the number deleted, optionally
+    /// resetting its `AUTOINCREMENT` counter.
+    ///
+    /// ```ignore
+    /// let deleted = app.rusqlite2_connection().truncate_table(db, "logs", true).unwrap();
+    /// ```
+    pub fn truncate_table(
+        &self,
+        db: &str,
+        table: &str,
+        reset_autoincrement: bool,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::truncate_table(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            reset_autoincrement,
+        )
+    }
+    ///
+    /// Summarizes the loaded databases, open transactions and SQLite version.
+    ///
+    /// ```ignore
+    /// let report = app.rusqlite2_connection().diagnostics().unwrap();
+    /// ```
+    pub fn diagnostics(&self) -> Result<Diagnostics, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::diagnostics(self.app.clone(), connections)
+    }
+    ///
+    /// Runs a script of several statements and returns one result set per
+    /// statement. `values_per_statement[i]` is bound to the i-th statement.
+    ///
+    /// ```ignore
+    /// let result_sets = app.rusqlite2_connection().select_multi(
+    ///     db,
+    ///     "SELECT * FROM users WHERE id = ?; SELECT count(*) AS n FROM orders;",
+    ///     vec![vec![json!(1)], vec![]],
+    ///     None,
+    /// )?;
+    /// ```
+    pub fn select_multi(
+        &self,
+        db: &str,
+        sql: &str,
+        values_per_statement: Vec<Vec<JsonValue>>,
+        tx_id: Option<String>,
+    ) -> Result<Vec<Vec<IndexMap<String, JsonValue>>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select_multi(
+            self.app.clone(),
+            connections,
+            db,
+            sql,
+            values_per_statement,
+            tx_id,
+        )
+    }
+    ///
+    /// Flushes `db` to disk and checkpoints its WAL. Returns `false` if the
+    /// checkpoint was blocked by another connection.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().flush(db).unwrap();
+    /// ```
+    pub fn flush(&self, db: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::flush(self.app.clone(), connections, db)
+    }
+    ///
+    /// Hex SHA-256 digest of the contents of `table`, with rows ordered by
+    /// `order_by` (every column by default).
+    ///
+    /// ```ignore
+    /// let digest = app.rusqlite2_connection().table_hash(db, "users", None).unwrap();
+    /// ```
+    pub fn table_hash(
+        &self,
+        db: &str,
+        table: &str,
+        order_by: Option<Vec<String>>,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::table_hash(self.app.clone(), connections, db, table, order_by)
+    }
+    ///
+    /// Hex SHA-256 digest of the database file of `db`.
+    ///
+    /// ```ignore
+    /// let digest = app.rusqlite2_connection().db_hash(db).unwrap();
+    /// ```
+    pub fn db_hash(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::db_hash(self.app.clone(), connections, db)
+    }
+    ///
+    /// Hex SHA-256 digest of the schema of `db`, for `assert_schema`.
+    ///
+    /// ```ignore
+    /// let expected = app.rusqlite2_connection().schema_hash(db).unwrap();
+    /// ```
+    pub fn schema_hash(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::schema_hash(self.app.clone(), connections, db)
+    }
+    ///
+    /// Fails with `Error::SchemaHashMismatch` unless the schema of `db`
+    /// hashes to `expected_hash`.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection()
+    ///     .assert_schema(db, EXPECTED_SCHEMA_HASH)
+    ///     .expect("unexpected database schema");
+    /// ```
+    pub fn assert_schema(&self, db: &str, expected_hash: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::assert_schema(self.app.clone(), connections, db, expected_hash)
+    }
+    ///
+    /// Reads pragma `name` of `db` and returns its rows.
+    ///
+    /// ```ignore
+    /// let rows = app.rusqlite2_connection().pragma(db, "journal_mode", None).unwrap();
+    /// ```
+    pub fn pragma(
+        &self,
+        db: &str,
+        name: &str,
+        tx_id: Option<String>,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::pragma(self.app.clone(), connections, db, name, tx_id)
+    }
+    ///
+    /// Reads `PRAGMA application_id` of `db`, setting it to `set` first if given.
+    ///
+    /// ```ignore
+    /// let is_ours = app.rusqlite2_connection().application_id(db, None)? == MY_APP_ID;
+    /// ```
+    pub fn application_id(&self, db: &str, set: Option<i32>) -> Result<i32, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::application_id(self.app.clone(), connections, db, set)
+    }
+    ///
+    /// Re-runs `query` every `interval_ms` and emits `sql://changed` events when
+    /// its result changes. Returns the watch id to pass to `poll_unwatch`.
+    ///
+    /// ```ignore
+    /// let watch_id = app.rusqlite2_connection()
+    ///     .poll_watch(db, "SELECT id, title FROM todos", vec![], 500, Some("id".to_string()))
+    ///     .unwrap();
+    /// ```
+    pub fn poll_watch(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        interval_ms: u64,
+        key_column: Option<String>,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::poll_watch(
+            self.app.clone(),
+            connections,
+            db,
+            query,
+            values,
+            interval_ms,
+            key_column,
+        )
+    }
+    ///
+    /// Stops the watch started by `poll_watch`.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().poll_unwatch(&watch_id).unwrap();
+    /// ```
+    pub fn poll_unwatch(&self, watch_id: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::poll_unwatch(self.app.clone(), connections, watch_id)
+    }
+    ///
+    /// The plugin configuration in effect, with defaults filled in and the
+    /// encryption keys of `preload` connection strings redacted.
+    ///
+    /// ```ignore
+    /// let config = app.rusqlite2_connection().get_config().unwrap();
+    /// ```
+    pub fn get_config(&self) -> Result<PluginConfig, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::get_config(self.app.clone(), connections)
+    }
+    ///
+    /// Lists the tasks the plugin runs in the background, e.g. poll watches.
+    ///
+    /// ```ignore
+    /// let tasks = app.rusqlite2_connection().list_background_tasks().unwrap();
+    /// ```
+    pub fn list_background_tasks(&self) -> Result<Vec<BackgroundTaskInfo>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::list_background_tasks(self.app.clone(), connections)
+    }
+    ///
+    /// Stops the background task `task_id`.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().cancel_background_task(&task_id).unwrap();
+    /// ```
+    pub fn cancel_background_task(&self, task_id: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::cancel_background_task(self.app.clone(), connections, task_id)
+    }
+    ///
+    /// Drops `table` from `db` and returns whether it existed.
+    ///
+    /// ```ignore
+    /// let existed = app.rusqlite2_connection().drop_table(db, "sessions", true, None).unwrap();
+    /// ```
+    pub fn drop_table(
+        &self,
+        db: &str,
+        table: &str,
+        if_exists: bool,
+        tx_id: Option<String>,
+    ) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::drop_table(self.app.clone(), connections, db, table, if_exists, tx_id)
+    }
+    ///
+    /// Rebuilds all indexes of `db`, or only those covered by `target`
+    /// (an index, table or collation name).
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().reindex(db, Some("idx_users_email".to_string())).unwrap();
+    /// ```
+    pub fn reindex(&self, db: &str, target: Option<String>) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::reindex(self.app.clone(), connections, db, target)
+    }
+    ///
+    /// Same as `load`, with per-alias options such as a table prefix.
+    ///
+    /// ```ignore
+    /// let db = app.rusqlite2_connection().load_with_options(
+    ///     "sqlite::tenants.db",
+    ///     vec![],
+    ///     LoadOptions { table_prefix: Some("tenant_a_".to_string()), ..Default::default() },
+    /// ).unwrap();
+    /// ```
+    pub fn load_with_options(
+        &self,
+        db: &str,
+        extensions: Vec<String>,
+        options: LoadOptions,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::load(self.app.clone(), connections, db, extensions, Some(options))
+    }
+
+    ///
+    /// Returns the resolved filesystem path of `db`, or `":memory:"` for
+    /// in-memory databases.
+    ///
+    /// ```ignore
+    /// let path = app.rusqlite2_connection().get_path(db).unwrap();
+    /// ```
+    pub fn get_path(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::get_path(self.app.clone(), connections, db)
+    }
+
+    ///
+    /// Returns whether writes to `db` succeed, probing with a write that is
+    /// always rolled back.
+    ///
+    /// ```ignore
+    /// if !app.rusqlite2_connection().is_writable(db).unwrap() {
+    ///     // read-only media or missing permissions
+    /// }
+    /// ```
+    pub fn is_writable(&self, db: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::is_writable(self.app.clone(), connections, db)
+    }
+    ///
+    /// Returns the options `db` was loaded with.
+    ///
+    /// ```ignore
+    /// let prefix = app.rusqlite2_connection().get_alias_config(db).unwrap().table_prefix;
+    /// ```
+    pub fn get_alias_config(&self, db: &str) -> Result<AliasConfig, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::get_alias_config(self.app.clone(), connections, db)
+    }
+
+    ///
+    /// Changes the SQLCipher key of `db` to `new_key` and verifies it by
+    /// opening the database with the new key, restoring the old key if that
+    /// fails. Requires one of the SQLCipher features.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().rekey_verified(&db, "new-key").unwrap();
+    /// ```
+    pub fn rekey_verified(&self, db: &str, new_key: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::rekey_verified(self.app.clone(), connections, db, new_key)
+    }
+
+    ///
+    /// Loads an in-memory database under `alias` that is shared by every
+    /// connection for that alias, including transactions, until it is closed.
+    /// Fails if `alias` is already loaded.
+    ///
+    /// ```ignore
+    /// let db = app.rusqlite2_connection().load_in_memory_persistent("scratch").unwrap();
+    /// ```
+    pub fn load_in_memory_persistent(&self, alias: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::load_in_memory_persistent(self.app.clone(), connections, alias)
+    }
+
+    ///
+    /// Removes the database alias association. This prevents new operations
+    /// from being started with this alias until `load` is called again.
+    /// Does not affect currently active transactions, which will continue until
+    /// committed or rolled back.
+    ///
+    /// ```ignore
+    /// const success:bool = app.rusqlite2_connection.close().unwrap();
+    /// ```
+    /// * `dbPath` - The specific database path/alias to close. If omitted, attempts to close the alias associated with this `Database` instance.
+    ///
+    pub fn close(&self, db: Option<String>) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::close(self.app.clone(), connections, db)
+    }
+
+    ///
+    ///
+    /// Starts a new transaction and returns a unique transaction identifier.
+    /// All subsequent `execute` or `select` calls using this identifier will run
+    /// within the same transaction context.
+    ///
+    /// * `returns` -  The transaction identifier string.
+    ///
+    /// ```ignore
+    /// let txId:String = app.rusqlite2_connection.begin_transaction().unwrap;
+    /// ```
+    pub fn begin_transaction(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::begin_transaction(self.app.clone(), connections, db)
+    }
+
+    ///
+    /// Starts a read-only snapshot transaction that never blocks writers.
+    /// The returned id can be used with `select`; `execute` is rejected.
+    ///
+    /// ```ignore
+    /// let tx_id = app.rusqlite2_connection().begin_read_transaction(db).unwrap();
+    /// ```
+    pub fn begin_read_transaction(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::begin_read_transaction(self.app.clone(), connections, db)
+    }
+    ///
+    /// Commits the transaction identified by `txId`.
+    ///  * `txId` - The transaction identifier returned by `beginTransaction`.
+    ///
+    ///```ignore
+    /// let res = app.rusqlite2_connection.commit_transaction(txId);
+    ///```
+    ///
+    pub fn commit_transaction(&self, tx_id: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::commit_transaction(self.app.clone(), connections, tx_id)
+    }
+
+    ///
+    /// Rolls back the transaction identified by `txId`.
+    ///
+    /// * `txId`` - The transaction identifier returned by `begin_transaction`.
+    ///
+    /// ```ignore
+    /// let res = app.rusqlite2_connection.rollback_transaction(txId);
+    /// ```
+    pub fn rollback_transaction(&self, tx_id: &str) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::rollback_transaction(self.app.clone(), connections, tx_id)
+    }
+
+    ///
+    /// Rolls back every open transaction and clears the transaction manager.
+    /// Useful for recovering from leaked transactions during development.
+    ///
+    /// * `returns` - The number of transactions rolled back.
+    ///
+    /// ```ignore
+    /// let count = app.rusqlite2_connection().rollback_all_transactions().unwrap();
+    /// ```
+    pub fn rollback_all_transactions(&self) -> Result<usize, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::rollback_all_transactions(self.app.clone(), connections)
+    }
+
+    ///
+    /// Rolls back every open transaction, stops every poll watch and closes
+    /// every database, leaving the plugin as if nothing had been loaded.
+    ///
+    /// * `returns` - How many transactions and databases were cleared.
+    ///
+    /// ```ignore
+    /// let report = app.rusqlite2_connection().reset().unwrap();
+    /// ```
+    pub fn reset(&self) -> Result<ResetReport, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::reset(self.app.clone(), connections)
+    }
+
+    ///
+    /// Returns the database's current time as an ISO 8601 UTC string, e.g.
+    /// `"2024-01-31T12:00:00.000Z"`.
+    ///
+    /// ```ignore
+    /// let now = app.rusqlite2_connection().now_iso(&db).unwrap();
+    /// ```
+    pub fn now_iso(&self, db: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::now_iso(self.app.clone(), connections, db)
+    }
+
+    ///
+    /// Runs `query` and returns its rows as one JSON array string.
+    ///
+    /// ```ignore
+    /// let json = app.rusqlite2_connection()
+    ///     .select_json(&db, "SELECT * FROM users", vec![])
+    ///     .unwrap();
+    /// ```
+    pub fn select_json(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select_json(self.app.clone(), connections, db, query, values, None)
+    }
+
+    ///
+    /// Runs `query` and returns its values grouped by column, e.g. to feed the
+    /// series of a chart.
+    ///
+    /// ```ignore
+    /// let series = app.rusqlite2_connection()
+    ///     .select_columnar(&db, "SELECT day, total FROM sales", vec![])
+    ///     .unwrap();
+    /// ```
+    pub fn select_columnar(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<ColumnarResult, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select_columnar(self.app.clone(), connections, db, query, values, None)
+    }
+
+    ///
+    /// Returns the first row of `query`, or `None`, without reading further rows.
+    ///
+    /// ```ignore
+    /// let user = app.rusqlite2_connection()
+    ///     .select_one(&db, "SELECT * FROM users WHERE email = ?", params![email])
+    ///     .unwrap();
+    /// ```
+    pub fn select_one(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<Option<IndexMap<String, JsonValue>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select_one(self.app.clone(), connections, db, query, values, None)
+    }
+
+    ///
+    /// Inserts a row into `table` with the column-to-value map `values` and
+    /// returns its rowid, or `None` if `on_conflict` skipped it.
+    ///
+    /// ```ignore
+    /// let mut values = IndexMap::new();
+    /// values.insert("name".to_string(), json!("Bob"));
+    /// let rowid = app.rusqlite2_connection()
+    ///     .insert(&db, "users", values, None, None)
+    ///     .unwrap();
+    /// ```
+    pub fn insert(
+        &self,
+        db: &str,
+        table: &str,
+        values: IndexMap<String, JsonValue>,
+        on_conflict: Option<OnConflict>,
+        tx_id: Option<String>,
+    ) -> Result<Option<i64>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::insert(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            values,
+            on_conflict,
+            tx_id,
+        )
     }
-}
 
-// --- New State Definitions ---
+    ///
+    /// Updates the rows of `table` whose columns equal `where_eq` with `set`
+    /// and returns the number of rows updated. An empty `where_eq` fails with
+    /// `Error::FullTableUpdate` unless `allow_full_table` is set.
+    ///
+    /// ```ignore
+    /// let mut set = IndexMap::new();
+    /// set.insert("name".to_string(), json!("Robert"));
+    /// let mut where_eq = IndexMap::new();
+    /// where_eq.insert("id".to_string(), json!(1));
+    /// let updated = app.rusqlite2_connection()
+    ///     .update(&db, "users", set, where_eq, None, None)
+    ///     .unwrap();
+    /// ```
+    pub fn update(
+        &self,
+        db: &str,
+        table: &str,
+        set: IndexMap<String, JsonValue>,
+        where_eq: IndexMap<String, JsonValue>,
+        allow_full_table: Option<bool>,
+        tx_id: Option<String>,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::update(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            set,
+            where_eq,
+            allow_full_table,
+            tx_id,
+        )
+    }
+    ///
+    /// Returns whether `table` has a row matching `where_clause`, which is raw
+    /// SQL bound with `values`.
+    ///
+    /// ```ignore
+    /// let taken = app.rusqlite2_connection()
+    ///     .exists_where(&db, "users", "email = ?", params![email])
+    ///     .unwrap();
+    /// ```
+    pub fn exists_where(
+        &self,
+        db: &str,
+        table: &str,
+        where_clause: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::exists_where(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            where_clause,
+            values,
+            None,
+        )
+    }
 
-// Reintroduce DbInfo
-#[derive(Clone, Debug)] // Removed Send + Sync from derive
-pub struct DbInfo {
-    path: PathBuf,
-    extensions: Vec<String>,
-    pass: String,
-}
+    ///
+    /// Returns the length of `column` in the row of `table` with `rowid`, in
+    /// bytes for a BLOB, without fetching the value.
+    ///
+    /// ```ignore
+    /// let size = app.rusqlite2_connection()
+    ///     .blob_size(&db, "attachments", "data", rowid)
+    ///     .unwrap();
+    /// ```
+    pub fn blob_size(
+        &self,
+        db: &str,
+        table: &str,
+        column: &str,
+        rowid: i64,
+    ) -> Result<Option<i64>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::blob_size(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            column,
+            rowid,
+            None,
+        )
+    }
 
-#[derive(Default, Clone)]
-// Revert ConnectionManager to hold DbInfo
-pub struct ConnectionManager(pub Arc<Mutex<HashMap<String, DbInfo>>>);
+    ///
+    /// Returns how many rows `query` returns without fetching them. `query`
+    /// must not have a `LIMIT` of its own.
+    ///
+    /// ```ignore
+    /// let total = app.rusqlite2_connection()
+    ///     .select_count(&db, "SELECT * FROM users WHERE active = ?", params![true])
+    ///     .unwrap();
+    /// ```
+    pub fn select_count(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select_count(self.app.clone(), connections, db, query, values, None)
+    }
 
-//Connection pool
-#[derive(Default, Clone)]
-pub struct ConnectionPool(pub Arc<Mutex<HashMap<String, Arc<Mutex<Connection>>>>>);
+    ///
+    /// Compiles `sql` once and returns a handle to `run` it repeatedly.
+    ///
+    /// ```ignore
+    /// let handle = app.rusqlite2_connection()
+    ///     .prepare(&db, "INSERT INTO events (name) VALUES (?)")
+    ///     .unwrap();
+    /// for name in names {
+    ///     app.rusqlite2_connection().run(&handle, params![name]).unwrap();
+    /// }
+    /// app.rusqlite2_connection().finalize(&handle).unwrap();
+    /// ```
+    pub fn prepare(&self, db: &str, sql: &str) -> Result<String, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::prepare(self.app.clone(), connections, db, sql)
+    }
 
-#[derive(Default, Clone)]
-pub struct TransactionManager(pub Arc<Mutex<HashMap<Uuid, Arc<Mutex<rusqlite::Connection>>>>>);
-#[derive(Clone)]
-pub struct Rusqlite2Connections<R: Runtime> {
-    pub app: AppHandle<R>,
-    pub connections: ConnectionManager,
-    pub pool: ConnectionPool,
-    pub transactions: TransactionManager,
-}
+    ///
+    /// Runs the statement of a `prepare` handle with `values`.
+    pub fn run(&self, handle: &str, values: Vec<JsonValue>) -> Result<RunResult, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::run(self.app.clone(), connections, handle, values)
+    }
 
-impl<R: Runtime> Rusqlite2Connections<R> {
-    ///Get a raw connection to run queries
-    pub fn get_conn(&self, db_alias: &str) -> Result<Arc<Mutex<Connection>>, crate::Error> {
-        let pool = lock_mutex(&self.pool.0, "ConnectionManager")?;
+    ///
+    /// Drops a `prepare` handle. Returns whether it existed.
+    pub fn finalize(&self, handle: &str) -> Result<bool, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::finalize(self.app.clone(), connections, handle)
+    }
 
-        if let Some(conn) = pool.get(db_alias) {
-            return Ok(conn.clone());
-        } else {
-            Err(Error::DatabaseNotLoaded(db_alias.to_string()))
-        }
+    ///
+    /// Sets SQLite limit `category` of `db` to `value` on its current and
+    /// future connections and returns the value in effect.
+    ///
+    /// ```ignore
+    /// app.rusqlite2_connection().set_limit(&db, SqliteLimit::VariableNumber, 32766).unwrap();
+    /// ```
+    pub fn set_limit(
+        &self,
+        db: &str,
+        category: SqliteLimit,
+        value: i32,
+    ) -> Result<i32, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::set_limit(self.app.clone(), connections, db, category, value)
     }
 
     ///
+    /// Runs `ANALYZE` on the whole database, or on one table or index.
     ///
-    /// A static initializer which connects to the underlying database and
-    /// returns the db string instance once a connection to the database is established.
+    /// ```ignore
+    /// app.rusqlite2_connection().analyze(&db, Some("users".into())).unwrap();
+    /// let stats = app.rusqlite2_connection().query_stats(&db).unwrap();
+    /// ```
+    pub fn analyze(&self, db: &str, target: Option<String>) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::analyze(self.app.clone(), connections, db, target)
+    }
+
     ///
-    /// # Sqlite
+    /// Returns the planner statistics written by `analyze`.
+    pub fn query_stats(&self, db: &str) -> Result<QueryStats, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::query_stats(self.app.clone(), connections, db)
+    }
+
     ///
-    /// The path is relative to `tauri::path::BaseDirectory::App` and must start with `sqlite:`.
+    /// Frees up to `pages` pages, or all free pages, of a database using
+    /// `auto_vacuum = INCREMENTAL`.
     ///
-    /// @example
     /// ```ignore
-    /// let app:tauri::AppHandle;
+    /// let report = app.rusqlite2_connection().incremental_vacuum(&db, Some(100)).unwrap();
+    /// ```
+    pub fn incremental_vacuum(
+        &self,
+        db: &str,
+        pages: Option<u32>,
+    ) -> Result<IncrementalVacuumReport, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::incremental_vacuum(self.app.clone(), connections, db, pages)
+    }
+
     ///
-    /// let db:String = app.rusqlite2_connection()
-    ///      .load("sqlite:test.db".to_string(), vec!["path/to/ext_1", "path/to/ext_2"])
-    ///      .unwrap();
+    /// Extracts `path` from the JSON stored in `json_column` of every row of
+    /// `table` matching `where_clause`. `?` placeholders in `where_clause` are
+    /// bound from `values`.
     ///
+    /// ```ignore
+    /// let names = app.rusqlite2_connection().json_get(
+    ///     db, "docs", "body", "$.name", Some("id > ?".to_string()), params![1],
+    /// ).unwrap();
     /// ```
-    ///
-    pub fn load(&self, db: &str, extensions: Vec<String>) -> Result<String, crate::Error> {
+    pub fn json_get(
+        &self,
+        db: &str,
+        table: &str,
+        json_column: &str,
+        path: &str,
+        where_clause: Option<String>,
+        values: Vec<JsonValue>,
+    ) -> Result<Vec<JsonValue>, crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::load(self.app.clone(), connections, db, extensions)
+        crate::commands::json_get(
+            self.app.clone(),
+            connections,
+            db,
+            table,
+            json_column,
+            path,
+            where_clause,
+            values,
+        )
     }
 
     ///
-    /// Removes the database alias association. This prevents new operations
-    /// from being started with this alias until `load` is called again.
-    /// Does not affect currently active transactions, which will continue until
-    /// committed or rolled back.
+    /// Copies every row of `table` from `src` into `dest`, creating the
+    /// destination table from the source schema when `create_if_missing` is set.
+    ///
+    /// * `returns` - The number of rows copied.
     ///
     /// ```ignore
-    /// const success:bool = app.rusqlite2_connection.close().unwrap();
+    /// let copied = app.rusqlite2_connection().copy_table(src, dest, "items", true).unwrap();
     /// ```
-    /// * `dbPath` - The specific database path/alias to close. If omitted, attempts to close the alias associated with this `Database` instance.
-    ///
-    pub fn close(&self, db: Option<String>) -> Result<bool, crate::Error> {
+    pub fn copy_table(
+        &self,
+        src: &str,
+        dest: &str,
+        table: &str,
+        create_if_missing: bool,
+    ) -> Result<usize, crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::close(self.app.clone(), connections, db)
+        crate::commands::copy_table(
+            self.app.clone(),
+            connections,
+            src,
+            dest,
+            table,
+            create_if_missing,
+        )
     }
 
     ///
+    /// Enables or disables `sql://progress` events for `db`. Every connection
+    /// opened for the alias reports progress every `progressInterval` VM
+    /// instructions while enabled. Requires the `hooks` feature.
     ///
-    /// Starts a new transaction and returns a unique transaction identifier.
-    /// All subsequent `execute` or `select` calls using this identifier will run
-    /// within the same transaction context.
+    /// ```ignore
+    /// app.rusqlite2_connection().set_progress_handler(db, true).unwrap();
+    /// ```
+    pub fn set_progress_handler(&self, db: &str, enabled: bool) -> Result<(), crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::set_progress_handler(self.app.clone(), connections, db, enabled)
+    }
+
     ///
-    /// * `returns` -  The transaction identifier string.
+    /// Interrupts the query currently running on the pooled connection of `db`.
+    /// The interrupted call fails with an `interrupted` error.
     ///
     /// ```ignore
-    /// let txId:String = app.rusqlite2_connection.begin_transaction().unwrap;
+    /// app.rusqlite2_connection().interrupt(db).unwrap();
     /// ```
-    pub fn begin_transaction(&self, db: &str) -> Result<String, crate::Error> {
+    pub fn interrupt(&self, db: &str) -> Result<(), crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::begin_transaction(self.app.clone(), connections, db)
+        crate::commands::interrupt(self.app.clone(), connections, db)
     }
 
     ///
-    /// Commits the transaction identified by `txId`.
-    ///  * `txId` - The transaction identifier returned by `beginTransaction`.
-    ///
-    ///```ignore
-    /// let res = app.rusqlite2_connection.commit_transaction(txId);
-    ///```
+    /// Emits a `sql://change` event with the old and new values of every row
+    /// inserted, updated or deleted through the connections of `db` until
+    /// `unwatch_changes`, including connections reopened later.
+    /// Requires the `preupdate_hook` feature.
     ///
-    pub fn commit_transaction(&self, tx_id: &str) -> Result<(), crate::Error> {
+    /// ```ignore
+    /// app.rusqlite2_connection().watch_changes(db).unwrap();
+    /// ```
+    pub fn watch_changes(&self, db: &str) -> Result<(), crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::commit_transaction(self.app.clone(), connections, tx_id)
+        crate::commands::watch_changes(self.app.clone(), connections, db)
     }
 
     ///
-    /// Rolls back the transaction identified by `txId`.
-    ///
-    /// * `txId`` - The transaction identifier returned by `begin_transaction`.
+    /// Stops emitting change events for `db`.
     ///
     /// ```ignore
-    /// let res = app.rusqlite2_connection.rollback_transaction(txId);
+    /// app.rusqlite2_connection().unwatch_changes(db).unwrap();
     /// ```
-    pub fn rollback_transaction(&self, tx_id: &str) -> Result<(), crate::Error> {
+    pub fn unwatch_changes(&self, db: &str) -> Result<(), crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::rollback_transaction(self.app.clone(), connections, tx_id)
+        crate::commands::unwatch_changes(self.app.clone(), connections, db)
     }
 
     ///
@@ -232,7 +2315,7 @@ impl<R: Runtime> Rusqlite2Connections<R> {
     ///      .unwrap();
     ///
     /// //Simple insert
-    /// let result:Result<(u64, LastInsertId), Error> =
+    /// let result:Result<ExecuteResult, Error> =
     ///     app.rusqlite2_connection().execute(
     ///         db,
     ///         "INSERT into users (name) VALUES (?)".to_string(),
@@ -263,9 +2346,103 @@ impl<R: Runtime> Rusqlite2Connections<R> {
         query: &str,
         values: Vec<JsonValue>,
         tx_id: Option<String>,
-    ) -> Result<(u64, LastInsertId), crate::Error> {
+    ) -> Result<ExecuteResult, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::execute(
+            self.app.clone(),
+            connections,
+            db,
+            query,
+            values,
+            tx_id,
+            None,
+            None,
+        )
+    }
+
+    ///
+    /// Runs a write statement with a `RETURNING` clause and returns its rows.
+    ///
+    /// ```ignore
+    /// let deleted = app.rusqlite2_connection()
+    ///     .execute_returning(&db, "DELETE FROM sessions WHERE expires < ? RETURNING id", params![now], None)
+    ///     .unwrap();
+    /// ```
+    pub fn execute_returning(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        tx_id: Option<String>,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::execute_returning(self.app.clone(), connections, db, query, values, tx_id)
+    }
+
+    ///
+    /// Runs `ops` atomically with foreign key checks deferred until the end,
+    /// then rolls them back if any violation remains and returns the
+    /// violations found.
+    pub fn with_foreign_keys_off(
+        &self,
+        db: &str,
+        ops: Vec<Operation>,
+    ) -> Result<Vec<ForeignKeyViolation>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::with_foreign_keys_off(self.app.clone(), connections, db, ops)
+    }
+
+    ///
+    /// Runs the SQL script at `src_path` statement by statement as it is read,
+    /// atomically, and returns the number of statements executed.
+    ///
+    /// ```ignore
+    /// let count = app.rusqlite2_connection()
+    ///     .import_sql_file(&db, "dumps/seed.sql", None)
+    ///     .unwrap();
+    /// ```
+    pub fn import_sql_file(
+        &self,
+        db: &str,
+        src_path: &str,
+        tx_id: Option<String>,
+    ) -> Result<u64, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::import_sql_file(self.app.clone(), connections, db, src_path, tx_id)
+    }
+
+    ///
+    /// Runs `query` once per entry of `values`. Without `per_row_commit` the
+    /// whole batch is atomic; with it, each row commits on its own and the
+    /// index of a failed row is returned instead of an error.
+    ///
+    /// ```ignore
+    /// let result = app.rusqlite2_connection().execute_many(
+    ///     &db,
+    ///     "INSERT INTO items (name) VALUES (?)",
+    ///     vec![params!["Laptop"], params!["Phone"]],
+    ///     None,
+    ///     None,
+    /// ).unwrap();
+    /// ```
+    pub fn execute_many(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<Vec<JsonValue>>,
+        tx_id: Option<String>,
+        per_row_commit: Option<bool>,
+    ) -> Result<ExecuteManyResult, crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::execute(self.app.clone(), connections, db, query, values, tx_id)
+        crate::commands::execute_many(
+            self.app.clone(),
+            connections,
+            db,
+            query,
+            values,
+            tx_id,
+            per_row_commit,
+        )
     }
 
     ///
@@ -278,6 +2455,9 @@ impl<R: Runtime> Rusqlite2Connections<R> {
     /// * `txId` - Optional transaction identifier. If provided, the query runs within that transaction.
     /// * `returns` - The selected rows.
     ///
+    /// Use `select_with_options` to skip NULL columns, parse JSON columns or
+    /// page the results.
+    ///
     ///
     /// ```ignore
     /// let db:String = app.rusqlite2_connection()
@@ -290,7 +2470,7 @@ impl<R: Runtime> Rusqlite2Connections<R> {
     ///         db,
     ///         "SELECT name from items WHERE owner_id = ?".to_string(),
     ///         params![1],
-    ///     None,
+    ///         None,
     /// );
     ///
     /// // Select within a transaction
@@ -317,8 +2497,71 @@ impl<R: Runtime> Rusqlite2Connections<R> {
         values: Vec<JsonValue>,
         tx_id: Option<String>,
     ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        self.select_with_options(db, query, values, tx_id, SelectOptions::default())
+    }
+
+    ///
+    /// Like `select`, with the optional arguments of the `select` command.
+    ///
+    /// ```ignore
+    /// let page = app.rusqlite2_connection().select_with_options(
+    ///     &db,
+    ///     "SELECT * FROM items ORDER BY id",
+    ///     vec![],
+    ///     None,
+    ///     SelectOptions {
+    ///         limit: Some(20),
+    ///         offset: Some(40),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    /// ```
+    pub fn select_with_options(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        tx_id: Option<String>,
+        options: SelectOptions,
+    ) -> Result<Vec<IndexMap<String, JsonValue>>, crate::Error> {
+        let connections = self.app.state::<Rusqlite2Connections<R>>();
+        crate::commands::select(
+            self.app.clone(),
+            connections,
+            db,
+            query,
+            values,
+            tx_id,
+            options.skip_nulls,
+            options.json_columns,
+            options.limit,
+            options.offset,
+        )
+    }
+
+    ///
+    /// Like `select`, but rows are arrays in column order and every column
+    /// reports the table and table column it comes from (with the
+    /// `column_metadata` feature), to disambiguate joined columns.
+    ///
+    /// ```ignore
+    /// let result = app.rusqlite2_connection().select_with_origin(
+    ///     &db,
+    ///     "SELECT u.id, o.id FROM users u JOIN orders o ON o.user_id = u.id",
+    ///     vec![],
+    ///     None,
+    /// ).unwrap();
+    /// assert_eq!(result.columns[1].table_name.as_deref(), Some("orders"));
+    /// ```
+    pub fn select_with_origin(
+        &self,
+        db: &str,
+        query: &str,
+        values: Vec<JsonValue>,
+        tx_id: Option<String>,
+    ) -> Result<OriginResult, crate::Error> {
         let connections = self.app.state::<Rusqlite2Connections<R>>();
-        crate::commands::select(self.app.clone(), connections, db, query, values, tx_id)
+        crate::commands::select_with_origin(self.app.clone(), connections, db, query, values, tx_id)
     }
 
     ///
@@ -355,10 +2598,39 @@ fn run_async_command<F: std::future::Future>(cmd: F) -> F::Output {
     }
 }
 
+/// Name the plugin is registered under. Commands are invoked as
+/// `plugin:rusqlite2|<command>` and permissions are referenced as
+/// `rusqlite2:<permission>`, so this plugin can coexist with the official
+/// `tauri-plugin-sql` (registered as `sql`).
+///
+/// The name is fixed rather than configurable: Tauri resolves command
+/// permissions by plugin name from the manifest generated at build time, so
+/// registering under a different name at runtime would reject every command.
+pub const PLUGIN_NAME: &str = "rusqlite2";
+
+/// Guard run before a transaction commits; returning `false` vetoes the commit,
+/// which is rolled back instead.
+#[cfg(feature = "hooks")]
+pub type CommitGuard = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Commit guards registered with `Builder::add_commit_guard`, keyed by database url.
+#[cfg(feature = "hooks")]
+#[derive(Default, Clone)]
+pub struct CommitGuards(pub HashMap<String, CommitGuard>);
+
+/// `PRAGMA application_id` written to databases created by `load`, set with
+/// `Builder::application_id`.
+#[derive(Debug, Clone, Copy)]
+pub struct ApplicationId(pub i32);
+
 /// Tauri SQL plugin builder.
 #[derive(Default)]
 pub struct Builder {
     migrations: Option<HashMap<String, MigrationList>>,
+    application_id: Option<i32>,
+    error_serializer: Option<ErrorSerializer>,
+    #[cfg(feature = "hooks")]
+    commit_guards: CommitGuards,
 }
 
 impl Builder {
@@ -375,8 +2647,62 @@ impl Builder {
         self
     }
 
+    /// Add a guard that can veto commits of transactions on a database.
+    ///
+    /// The guard runs on every `commit_transaction` of `db_url` and returns
+    /// `false` to roll the transaction back instead, failing the commit with
+    /// `Error::CommitVetoed`. It is only installed on the dedicated connections
+    /// of transactions begun with `begin_transaction`; statements run without a
+    /// transaction id are not guarded. Requires the `hooks` feature.
+    #[cfg(feature = "hooks")]
+    #[must_use]
+    pub fn add_commit_guard<F>(mut self, db_url: &str, guard: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.commit_guards
+            .0
+            .insert(db_url.to_string(), Arc::new(guard));
+        self
+    }
+
+    /// Set `PRAGMA application_id` on every database file `load` creates, so
+    /// the app can later recognize its own databases with the
+    /// `application_id` command. Existing databases are left untouched.
+    #[must_use]
+    pub fn application_id(mut self, application_id: i32) -> Self {
+        self.application_id = Some(application_id);
+        self
+    }
+
+    /// Replace how errors are sent to the frontend, which by default is the
+    /// error message as a string, e.g. to return `{ code, i18nKey, message }`
+    /// objects. `default_error_serializer` gives the default form.
+    ///
+    /// ```ignore
+    /// fn to_json(error: &Error) -> serde_json::Value {
+    ///     let code = match error {
+    ///         Error::DatabaseLocked(_) => "locked",
+    ///         _ => "other",
+    ///     };
+    ///     serde_json::json!({ "code": code, "message": error.to_string() })
+    /// }
+    /// Builder::new().error_serializer(to_json)
+    /// ```
+    ///
+    /// Errors are serialized without access to the app, so the serializer is
+    /// shared by every app in the process.
+    #[must_use]
+    pub fn error_serializer(mut self, serializer: ErrorSerializer) -> Self {
+        self.error_serializer = Some(serializer);
+        self
+    }
+
     pub fn build<R: Runtime>(mut self) -> TauriPlugin<R, Option<PluginConfig>> {
-        PluginBuilder::<R, Option<PluginConfig>>::new("rusqlite2")
+        if let Some(serializer) = self.error_serializer {
+            error::set_error_serializer(serializer);
+        }
+        PluginBuilder::<R, Option<PluginConfig>>::new(PLUGIN_NAME)
             .invoke_handler(tauri::generate_handler![
                 commands::load,
                 commands::execute,
@@ -387,24 +2713,114 @@ impl Builder {
                 commands::commit_transaction,
                 commands::rollback_transaction,
                 //migrate
-                commands::migrate
+                commands::migrate,
+                commands::rollback_all_transactions,
+                commands::load_in_memory_persistent,
+                commands::watch_changes,
+                commands::unwatch_changes,
+                commands::set_progress_handler,
+                commands::interrupt,
+                commands::json_get,
+                commands::copy_table,
+                commands::get_alias_config,
+                commands::schema_version,
+                commands::delete_by_ids,
+                commands::begin_read_transaction,
+                commands::migration_repair,
+                commands::reindex,
+                commands::vacuum_into,
+                commands::drop_table,
+                commands::execute_async,
+                commands::select_async,
+                commands::clone_database,
+                commands::get_path,
+                commands::begin_multi_transaction,
+                commands::commit_multi,
+                commands::rollback_multi,
+                commands::export_ndjson,
+                commands::expand_sql,
+                commands::set_migrations,
+                commands::run_migrations,
+                commands::truncate_table,
+                commands::diagnostics,
+                commands::select_multi,
+                commands::flush,
+                commands::table_hash,
+                commands::db_hash,
+                commands::pragma,
+                commands::application_id,
+                commands::poll_watch,
+                commands::poll_unwatch,
+                commands::migration_status,
+                commands::transaction_changes,
+                commands::execute_many,
+                commands::select_with_origin,
+                commands::ensure_table,
+                commands::indexes,
+                commands::release_connection,
+                commands::reset,
+                commands::now_iso,
+                commands::select_one,
+                commands::exists_where,
+                commands::prepare,
+                commands::run,
+                commands::finalize,
+                commands::analyze,
+                commands::query_stats,
+                commands::select_count,
+                commands::rekey_verified,
+                commands::execute_returning,
+                commands::with_foreign_keys_off,
+                commands::incremental_vacuum,
+                commands::select_json,
+                commands::set_limit,
+                commands::import_sql_file,
+                commands::is_writable,
+                commands::select_columnar,
+                commands::dump_schema,
+                commands::blob_size,
+                commands::schema_hash,
+                commands::assert_schema,
+                commands::list_background_tasks,
+                commands::cancel_background_task,
+                commands::insert,
+                commands::update,
+                commands::get_config
             ])
-            .setup(|app, api| {
+            .setup(move |app, api| {
                 let config = api.config().clone().unwrap_or_default();
-                app.manage(Mutex::new(MigrationList::default()));
+                #[cfg(not(feature = "column_decltype"))]
+                if config.coerce_to_affinity {
+                    return Err(Error::FeatureNotEnabled("column_decltype".to_string()).into());
+                }
+                app.manage(BuilderMigrations(
+                    self.migrations.clone().unwrap_or_default(),
+                ));
 
                 run_async_command(async move {
+                    #[cfg(feature = "hooks")]
+                    app.manage(self.commit_guards);
+                    if let Some(application_id) = self.application_id {
+                        app.manage(ApplicationId(application_id));
+                    }
                     // Register new states
                     app.manage(Rusqlite2Connections {
                         app: app.clone(),
                         connections: ConnectionManager::default(),
                         pool: ConnectionPool::default(),
                         transactions: TransactionManager::default(),
+                        multi_transactions: MultiTransactionManager::default(),
+                        runtime_migrations: RuntimeMigrations::default(),
+                        interrupts: InterruptManager::default(),
+                        background_tasks: BackgroundTaskManager::default(),
+                        prepared: PreparedStatementManager::default(),
+                        config: config.clone(),
                     });
 
                     for db in config.preload {
                         let conn_url =
-                            commands::get_conn_url(app.clone(), &db).expect("Failed to load DB");
+                            commands::get_conn_url(app.clone(), &db, config.create_missing_dirs)
+                                .expect("Failed to load DB");
 
                         //pass from the dbstring
                         let split_db_conn: Vec<&str> = db.splitn(3, ':').collect();
@@ -419,11 +2835,6 @@ impl Builder {
                         if let Some(migrations) =
                             self.migrations.as_mut().and_then(|mm| mm.remove(&db))
                         {
-                            let mig_state = app.state::<Mutex<MigrationList>>();
-                            let mut mig = mig_state.lock().unwrap();
-
-                            mig.0 = migrations.clone().0;
-
                             let resolved_migrations = migrations.resolve();
                             let migrations = Rusqlite2Migrations::new(resolved_migrations);
 
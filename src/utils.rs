@@ -10,3 +10,16 @@ pub(crate) fn lock_mutex<'a, T>(
         .lock()
         .map_err(|e| Error::LockPoisoned(format!("{}: {}", context, e)))
 }
+
+/// Quotes a table or column name for interpolation into SQL.
+/// Only plain identifiers (letters, digits and `_`, not starting with a digit) are accepted.
+pub(crate) fn quote_identifier(name: &str) -> Result<String, crate::Error> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !valid {
+        return Err(Error::InvalidIdentifier(name.to_string()));
+    }
+    Ok(format!("\"{}\"", name))
+}